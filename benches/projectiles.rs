@@ -0,0 +1,115 @@
+//! Compares firing bullets through the projectile pool with spawning and despawning an entity per bullet
+//!
+//! Run with `cargo bench --bench projectiles`
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+
+use space_shooter::components::*;
+use space_shooter::config::*;
+use space_shooter::constants::*;
+use space_shooter::player::*;
+use space_shooter::projectiles::*;
+use space_shooter::resources::*;
+use space_shooter::sprites::*;
+
+/// Bullets fired every update, each one flying for `LIFETIME` updates
+const FIRED: usize = 64;
+const LIFETIME: usize = 60;
+const UPDATES: usize = 2000;
+
+#[derive(Resource, Default)]
+struct InFlight(VecDeque<Vec<Entity>>);
+
+fn pooled(
+    mut commands: Commands,
+    mut projectile_pool: ResMut<ProjectilePool>,
+    mut in_flight: ResMut<InFlight>,
+    sprite_catalog: Res<SpriteCatalog>,
+    config: Res<GameConfig>
+) {
+    if in_flight.0.len() == LIFETIME {
+        for entity in in_flight.0.pop_front().unwrap_or_default() {
+            projectile_pool.release(&mut commands, entity);
+        }
+    }
+
+    let fired = (0..FIRED)
+        .filter_map(|_| spawn_bullet(&mut commands, &mut projectile_pool, &sprite_catalog, &config, 0, Vec2::ZERO))
+        .collect();
+    in_flight.0.push_back(fired);
+}
+
+fn spawned(
+    mut commands: Commands,
+    mut in_flight: ResMut<InFlight>,
+    sprite_catalog: Res<SpriteCatalog>,
+    config: Res<GameConfig>
+) {
+    if in_flight.0.len() == LIFETIME {
+        for entity in in_flight.0.pop_front().unwrap_or_default() {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    let fired = (0..FIRED)
+        .map(|_| commands.spawn((
+            sprite_catalog.sprite_sheet(
+                SpriteName::ShipBullet,
+                Transform::from_scale(Vec3::splat(SHIP_BULLET_SCALE))
+            ),
+            Speed(config.ship.bullet_speed),
+            Damage::new(config.ship.damage, config.ship.damage_type),
+            Hitbox(SHIP_BULLET_HITBOX),
+            Player(0),
+            Bullet
+        )).id())
+        .collect();
+    in_flight.0.push_back(fired);
+}
+
+/// Moves every bullet in flight, like the game does every tick
+fn move_bullets(mut bullets: Query<&mut Transform, With<Bullet>>) {
+    for mut transform in &mut bullets {
+        transform.translation.y += 1.0;
+    }
+}
+
+fn run<M>(fire: impl IntoSystemConfigs<M>) -> Duration {
+    let mut config = GameConfig::default();
+    config.projectiles.bullets = FIRED * LIFETIME;
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .insert_resource(config)
+        .insert_resource(SpriteCatalog::default())
+        .init_resource::<ProjectilePool>()
+        .init_resource::<InFlight>()
+        .add_systems(Update, (fire, move_bullets).chain());
+
+    // Warms up until bullets are released as fast as they are fired
+    for _ in 0..LIFETIME * 2 {
+        app.update();
+    }
+
+    let start = Instant::now();
+    for _ in 0..UPDATES {
+        app.update();
+    }
+    start.elapsed()
+}
+
+fn main() {
+    let bullets = (FIRED * UPDATES) as f64;
+
+    for (name, elapsed) in [("spawn/despawn", run(spawned)), ("pooled", run(pooled))] {
+        println!(
+            "{:<14} {:>8.2} ms  {:>6.2} M bullets/s",
+            name,
+            elapsed.as_secs_f64() * 1000.0,
+            bullets / elapsed.as_secs_f64() / 1e6
+        );
+    }
+}
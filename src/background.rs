@@ -1,62 +1,351 @@
 use bevy::prelude::*;
+use bevy::utils::HashMap;
 use bevy_parallax::{
-    LayerSpeed, LayerData, ParallaxPlugin, ParallaxMoveEvent, CreateParallaxEvent
+    LayerComponent, LayerRepeat, LayerTextureComponent, ParallaxPlugin, ParallaxMoveEvent,
+    RepeatStrategy
 };
+use serde::{Deserialize, Serialize};
 
 use crate::constants::*;
+use crate::level::*;
+use crate::loading::*;
+use crate::manifest::*;
+use crate::replay::*;
+use crate::resources::*;
 use crate::states::*;
 
-pub struct BackgroundPlugin;
+pub struct BackgroundPlugin {
+    /// Draws the layers with the parallax plugin, which needs a window to draw them in
+    pub parallax: bool
+}
+
+impl BackgroundPlugin {
+    pub fn headless() -> Self {
+        Self { parallax: false }
+    }
+}
+
+impl Default for BackgroundPlugin {
+    fn default() -> Self {
+        Self { parallax: true }
+    }
+}
 
 impl Plugin for BackgroundPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(ParallaxPlugin)
-            .add_systems(OnEnter(GameState::Playing), setup_background)
-            .add_systems(Update, move_background.run_if(in_state(GameState::Playing)));
-    }
-}
-
-fn setup_background(    
-    mut create_parallax: EventWriter<CreateParallaxEvent>,
-    camera_query: Query<Entity, With<Camera>>
-) {    
-    let camera_entity = camera_query.single();    
-
-    create_parallax.send(CreateParallaxEvent {
-        layers_data: vec![
-            LayerData {
-                speed: LayerSpeed::Vertical(0.85),
-                path: BACKGROUND_LAYER_1.to_string(),
-                tile_size: Vec2::new(BACKGROUND_WIDTH, BACKGROUND_HEIGHT),
-                cols: 4,
-                rows: 4,
-                scale: 2.5,
-                z: 1.0,
+        if self.parallax {
+            app.add_plugins(ParallaxPlugin);
+        }
+
+        // The scroll moves the camera through these events, left unread without the parallax plugin
+        app.add_event::<ParallaxMoveEvent>()
+            .insert_resource(ScrollSpeed::new(BACKGROUND_SCROLL_SPEED))
+            .init_resource::<ScrollPosition>()
+            .init_resource::<TerrainSpeed>()
+            .add_event::<SwapBackgroundEvent>()
+            .add_systems(Startup, preload_background)
+            .add_systems(OnEnter(GameState::Playing), reset_scroll)
+            .add_systems(Update, swap_background)
+            .add_systems(
+                FixedUpdate,
+                move_background
+                    .in_set(ScrollSet)
+                    .after(PlayerInputSet)
+                    .run_if(in_state(GameState::Playing))
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        require::<LevelProgress, LevelPlugin>(app);
+        require::<LoadingAssets, LoadingPlugin>(app);
+    }
+}
+
+/// Systems that depend on how far the background scrolled this tick run after this set
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ScrollSet;
+
+/// Background pictures, either a tile of the BackGrounds sheet or one of the standalone layers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BackgroundImage {
+    Void,
+    Amber,
+    Crimson,
+    Navy,
+    Stars,
+    FaintStars,
+    Layer1,
+    Layer2
+}
+
+impl BackgroundImage {
+    /// File the image lives in, and its index within that file
+    pub fn source(&self) -> (AssetName, usize) {
+        match self {
+            BackgroundImage::Void => (AssetName::Backgrounds, 0),
+            BackgroundImage::Amber => (AssetName::Backgrounds, 1),
+            BackgroundImage::Crimson => (AssetName::Backgrounds, 2),
+            BackgroundImage::Navy => (AssetName::Backgrounds, 3),
+            BackgroundImage::Stars => (AssetName::Backgrounds, 4),
+            BackgroundImage::FaintStars => (AssetName::Backgrounds, 5),
+            BackgroundImage::Layer1 => (AssetName::BackgroundLayer1, 0),
+            BackgroundImage::Layer2 => (AssetName::BackgroundLayer2, 0)
+        }
+    }
+}
+
+/// One scrolling layer of a background theme
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackgroundLayer {
+    pub image: BackgroundImage,
+    /// Speed relative to the camera, 1.0 stays still on screen and lower values scroll past faster
+    pub speed: f32,
+    #[serde(default = "default_layer_scale")]
+    pub scale: f32,
+    #[serde(default = "default_layer_tint")]
+    pub tint: Color
+}
+
+fn default_layer_scale() -> f32 { BACKGROUND_SCALE }
+
+fn default_layer_tint() -> Color { Color::WHITE }
+
+/// Layers of a level background, listed from the furthest to the nearest
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackgroundTheme {
+    pub layers: Vec<BackgroundLayer>,
+    /// Camera scroll speed in pixels per second when nothing overrides it
+    #[serde(default = "default_scroll_speed")]
+    pub scroll_speed: f32
+}
+
+fn default_scroll_speed() -> f32 { BACKGROUND_SCROLL_SPEED }
+
+impl BackgroundTheme {
+    /// Speed of the nearest layer, the terrain ground foes sit on
+    pub fn terrain_speed(&self) -> f32 {
+        self.layers.last().map_or(0.0, |layer| layer.speed)
+    }
+}
+
+/// Replaces the background layers on screen
+#[derive(Event, Debug, Clone)]
+pub struct SwapBackgroundEvent(pub BackgroundTheme);
+
+/// Camera scroll speed in pixels per second, easing towards `target` so changes are never abrupt
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ScrollSpeed {
+    pub current: f32,
+    pub target: f32,
+    /// Speed `resume` goes back to, set by the level background
+    pub base: f32
+}
+
+impl ScrollSpeed {
+    pub fn new(speed: f32) -> Self {
+        Self { current: speed, target: speed, base: speed }
+    }
+
+    pub fn set(&mut self, target: f32) {
+        self.target = target.max(0.0);
+    }
+
+    /// Speeds up or slows down relative to the base speed, e.g. 2.0 during a boss warning
+    pub fn scale(&mut self, factor: f32) {
+        self.set(self.base * factor);
+    }
+
+    pub fn stop(&mut self) {
+        self.set(0.0);
+    }
+
+    pub fn resume(&mut self) {
+        self.set(self.base);
+    }
+
+    /// Takes the base speed of a new background, staying stopped if the scroll was, e.g. during a boss fight
+    pub fn rebase(&mut self, base: f32) {
+        self.base = base;
+        if self.target != 0.0 {
+            self.resume();
+        }
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.current == 0.0 && self.target == 0.0
+    }
+}
+
+/// World height at the middle of the view, moved by the scroll alone so gameplay never sees the camera shake
+#[derive(Resource, Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct ScrollPosition(pub f32);
+
+/// Parallax speed of the terrain under the current level, set along with its background so ground foes
+/// scroll by exactly as the nearest layer does
+#[derive(Resource, Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct TerrainSpeed(pub f32);
+
+/// Atlases of every file background images come from
+#[derive(Resource, Default)]
+struct BackgroundAtlases(HashMap<AssetName, Handle<TextureAtlas>>);
+
+/// Parallax layer spawned from a background theme
+#[derive(Component)]
+struct BackgroundLayerMarker;
+
+fn preload_background(
+    mut commands: Commands,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    mut loading_assets: ResMut<LoadingAssets>,
+    asset_server: Res<AssetServer>
+) {
+    let mut atlases = BackgroundAtlases::default();
+
+    // The BackGrounds sheet is a 3x2 grid of tiles separated by a one pixel gap
+    let sheets = [
+        (AssetName::Backgrounds, 3, 2, Some(Vec2::ONE)),
+        (AssetName::BackgroundLayer1, 1, 1, None),
+        (AssetName::BackgroundLayer2, 1, 1, None)
+    ];
+
+    for (name, columns, rows, padding) in sheets {
+        let texture_handle: Handle<Image> = asset_server.load(name.path());
+        loading_assets.add(&texture_handle);
+
+        let texture_atlas = TextureAtlas::from_grid(
+            texture_handle,
+            BACKGROUND_TILE_SIZE,
+            columns,
+            rows,
+            padding,
+            None
+        );
+
+        atlases.0.insert(name, texture_atlases.add(texture_atlas));
+    }
+
+    commands.insert_resource(atlases);
+}
+
+fn reset_scroll(
+    mut scroll_position: ResMut<ScrollPosition>,
+    mut camera_query: Query<&mut Transform, With<Camera>>
+) {
+    // Keeps whatever shake offset the camera has on top of the scroll
+    if let Ok(mut camera_transform) = camera_query.get_single_mut() {
+        camera_transform.translation.y -= scroll_position.0;
+    }
+
+    scroll_position.0 = 0.0;
+}
+
+fn swap_background(
+    mut commands: Commands,
+    mut swap_events: EventReader<SwapBackgroundEvent>,
+    layers: Query<Entity, With<BackgroundLayerMarker>>,
+    camera_query: Query<(Entity, &Transform), With<Camera>>,
+    atlases: Res<BackgroundAtlases>
+) {
+    let Some(SwapBackgroundEvent(theme)) = swap_events.read().last() else { return };
+    let Ok((camera_entity, camera_transform)) = camera_query.get_single() else { return };
+
+    for entity in &layers {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    for (depth, layer) in theme.layers.iter().enumerate() {
+        let (file, index) = layer.image.source();
+        let Some(atlas) = atlases.0.get(&file) else { continue };
+
+        // Enough tiles to cover the window twice over vertically so the parallax plugin can wrap them
+        let tile_size = BACKGROUND_TILE_SIZE * layer.scale;
+        let half_columns = (WINDOW_WIDTH / tile_size.x * 0.5).ceil() as i32;
+        let max_row = (Vec2::new(WINDOW_WIDTH, WINDOW_HEIGHT).length() / tile_size.y).ceil() as i32;
+
+        commands.spawn((
+            SpatialBundle {
+                transform: Transform {
+                    translation: camera_transform.translation.truncate().extend(depth as f32),
+                    scale: Vec3::new(layer.scale, layer.scale, 1.0),
+                    ..default()
+                },
                 ..default()
             },
-            LayerData {
-                speed: LayerSpeed::Vertical(0.95),
-                path: BACKGROUND_LAYER_2.to_string(),
-                tile_size: Vec2::new(BACKGROUND_WIDTH, BACKGROUND_HEIGHT),
-                cols: 4,
-                rows: 4,
-                scale: 2.5,
-                z: 0.0,
-                ..default()
+            LayerComponent {
+                speed: Vec2::new(0.0, layer.speed),
+                repeat: LayerRepeat::vertically(RepeatStrategy::Same),
+                texture_count: Vec2::new(1.0, (2 * max_row) as f32),
+                camera: camera_entity
+            },
+            BackgroundLayerMarker
+        ))
+        .with_children(|parent| {
+            for column in -half_columns..=half_columns {
+                for row in (-max_row + 1)..=max_row {
+                    parent.spawn((
+                        SpriteSheetBundle {
+                            texture_atlas: atlas.clone(),
+                            sprite: TextureAtlasSprite {
+                                index,
+                                color: layer.tint,
+                                ..default()
+                            },
+                            transform: Transform::from_xyz(
+                                BACKGROUND_TILE_SIZE.x * column as f32,
+                                BACKGROUND_TILE_SIZE.y * row as f32,
+                                0.0
+                            ),
+                            ..default()
+                        },
+                        LayerTextureComponent {
+                            width: BACKGROUND_TILE_SIZE.x,
+                            height: BACKGROUND_TILE_SIZE.y
+                        }
+                    ));
+                }
             }
-        ],
-        camera: camera_entity
-    });
+        });
+    }
 }
 
 fn move_background(
     mut move_event_writer: EventWriter<ParallaxMoveEvent>,
-    camera_query: Query<Entity, With<Camera>>
+    mut scroll_speed: ResMut<ScrollSpeed>,
+    mut scroll_position: ResMut<ScrollPosition>,
+    mut progress: ResMut<LevelProgress>,
+    camera_query: Query<Entity, With<Camera>>,
+    time: Res<Time>
 ) {
-    let camera_entity = camera_query.single();
+    // Ease towards the target speed instead of jumping to it
+    let step = BACKGROUND_SCROLL_ACCELERATION * time.delta_seconds();
+    let difference = scroll_speed.target - scroll_speed.current;
+    scroll_speed.current += difference.clamp(-step, step);
+
+    if scroll_speed.current == 0.0 { return }
+
+    let distance = scroll_speed.current * time.delta_seconds();
+    progress.distance += distance;
+    scroll_position.0 += distance;
+
+    // The camera and the parallax layers follow the scroll, shaken on top of it by the feedback plugin
+    let Ok(camera_entity) = camera_query.get_single() else { return };
 
-    move_event_writer.send(ParallaxMoveEvent { 
-        camera_move_speed: Vec2::new(0.0, 2.0), 
+    move_event_writer.send(ParallaxMoveEvent {
+        camera_move_speed: Vec2::new(0.0, distance),
         camera: camera_entity
     });
-}
\ No newline at end of file
+}
+
+/// Moves the camera and the parallax layers as if the background scrolled `distance` further,
+/// e.g. back to where a restored snapshot was taken
+pub fn shift_view(world: &mut World, distance: f32) {
+    let mut cameras = world.query_filtered::<Entity, With<Camera>>();
+    let cameras: Vec<Entity> = cameras.iter(world).collect();
+
+    for camera in cameras {
+        world.send_event(ParallaxMoveEvent {
+            camera_move_speed: Vec2::new(0.0, distance),
+            camera
+        });
+    }
+}
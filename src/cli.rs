@@ -0,0 +1,320 @@
+use std::fs;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use clap::{ArgGroup, Parser};
+
+use crate::config::*;
+use crate::level::*;
+use crate::manifest::*;
+use crate::netcode::*;
+use crate::replay::*;
+use crate::resources::*;
+use crate::states::*;
+use crate::stats::*;
+
+/// Vertical scrolling space shooter
+#[derive(Parser, Debug, Clone, PartialEq)]
+#[command(name = "space_shooter", version, about)]
+#[command(group(ArgGroup::new("two_players").args(["co_op", "host"]).multiple(true)))]
+pub struct Cli {
+    /// Seed for the game RNG, the same seed and inputs always play out the same way
+    #[arg(long, conflicts_with = "replay")]
+    pub seed: Option<u64>,
+
+    /// Level to start at, from 1
+    #[arg(long, default_value_t = 1, conflicts_with = "replay")]
+    pub level: usize,
+
+    /// Wave of the starting level to start at, from 1
+    #[arg(long, default_value_t = 1, conflicts_with = "replay")]
+    pub wave: u32,
+
+    /// easy, normal, hard or insane
+    #[arg(long, default_value = "normal", conflicts_with = "replay")]
+    pub difficulty: Difficulty,
+
+    /// Raises or lowers the intensity during the run with how well it goes
+    #[arg(long, conflicts_with = "replay")]
+    pub rank: bool,
+
+    /// Plays endless mode instead of the campaign
+    #[arg(long, conflicts_with_all = ["replay", "wave"])]
+    pub endless: bool,
+
+    /// Two players on the same keyboard, the second one flying with WASD, F and G
+    #[arg(long, conflicts_with = "replay")]
+    pub co_op: bool,
+
+    /// Lets a co-op player out of lives take one from their teammate
+    #[arg(long, requires = "two_players")]
+    pub shared_continues: bool,
+
+    /// Hosts an online co-op run on this UDP port, the run starts once a peer joins
+    #[arg(long, value_name = "PORT", conflicts_with_all = ["replay", "join"])]
+    pub host: Option<u16>,
+
+    /// Joins the online co-op run hosted at this address, which picks how the run starts
+    #[arg(long, value_name = "ADDRESS", conflicts_with_all = ["replay", "seed", "rank", "endless", "co_op", "shared_continues"])]
+    pub join: Option<SocketAddr>,
+
+    /// Runs in borderless fullscreen instead of a window
+    #[arg(long, conflicts_with = "headless")]
+    pub fullscreen: bool,
+
+    /// Window size as WIDTHxHEIGHT, the view is scaled to fit
+    #[arg(long, value_name = "WIDTHxHEIGHT", value_parser = parse_resolution, conflicts_with = "headless")]
+    pub resolution: Option<Resolution>,
+
+    /// Replay file to play back instead of reading the keyboard
+    #[arg(long, value_name = "PATH", conflicts_with = "record")]
+    pub replay: Option<PathBuf>,
+
+    /// File the replay of the next finished run is saved to
+    #[arg(long, value_name = "PATH")]
+    pub record: Option<PathBuf>,
+
+    /// Runs the game logic without a window or audio, starting a run right away
+    #[arg(long)]
+    pub headless: bool,
+
+    /// Fixed ticks to simulate before exiting, the run goes on until game over otherwise
+    #[arg(long, requires = "headless", value_parser = clap::value_parser!(u32).range(1..))]
+    pub ticks: Option<u32>,
+
+    /// Game config file to use instead of assets/config.ron
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<PathBuf>
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Resolution {
+    pub width: u32,
+    pub height: u32
+}
+
+/// Reads a `WIDTHxHEIGHT` window size
+pub fn parse_resolution(value: &str) -> Result<Resolution, String> {
+    let error = || format!("'{}' is not a resolution, expected WIDTHxHEIGHT such as 1280x960", value);
+
+    let (width, height) = value.split_once(['x', 'X']).ok_or_else(error)?;
+    let width: u32 = width.trim().parse().map_err(|_| error())?;
+    let height: u32 = height.trim().parse().map_err(|_| error())?;
+
+    if !(320..=7680).contains(&width) || !(240..=4320).contains(&height) {
+        return Err(format!("resolution {}x{} must be between 320x240 and 7680x4320", width, height));
+    }
+
+    Ok(Resolution { width, height })
+}
+
+/// Everything the command line asks for, once checked against the files it refers to
+#[derive(Debug, Clone)]
+pub struct Launch {
+    pub start: StartOptions,
+    pub playback: Option<Replay>,
+    pub config: Option<PathBuf>,
+    pub net: Option<NetLaunch>,
+    pub options: LaunchOptions
+}
+
+/// Online co-op run to connect to at launch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetLaunch {
+    Host(u16),
+    Join(SocketAddr)
+}
+
+impl Cli {
+    /// Checks the values clap cannot check on its own, such as the starting level and the files given
+    pub fn validate(&self) -> Result<Launch, String> {
+        if !(1..=LEVEL_FILES.len()).contains(&self.level) {
+            return Err(format!("level {} does not exist, there are {} levels", self.level, LEVEL_FILES.len()));
+        }
+
+        if self.wave == 0 {
+            return Err("waves start at 1".to_string());
+        }
+
+        // A level file that cannot be read is reported by the loading screen instead
+        let level_path = asset_root().join(LEVEL_FILES[self.level - 1].path());
+        if let Ok(level) = fs::read_to_string(level_path).map_err(|error| error.to_string()).and_then(|contents| LevelData::parse(&contents)) {
+            let waves = level.timeline.iter()
+                .filter(|marker| matches!(marker.event, TimelineEvent::Wave { .. }))
+                .count() as u32;
+
+            if self.wave > waves {
+                return Err(format!("level {} only has {} waves, wave {} does not exist", self.level, waves, self.wave));
+            }
+        }
+
+        let playback = match &self.replay {
+            Some(path) => Some(
+                Replay::load(path).map_err(|error| format!("could not load replay {}: {}", path.display(), error))?
+            ),
+            None => None
+        };
+
+        if let Some(path) = &self.config {
+            if let Err(errors) = GameConfig::load(path) {
+                let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+                return Err(format!("invalid game config {}: {}", path.display(), errors.join(", ")));
+            }
+        }
+
+        if let Some(path) = &self.record {
+            let directory = path.parent().filter(|parent| !parent.as_os_str().is_empty());
+            if directory.is_some_and(|directory| !directory.is_dir()) {
+                return Err(format!("cannot record to {}, its directory does not exist", path.display()));
+            }
+        }
+
+        let net = match (self.host, self.join) {
+            (Some(port), _) => Some(NetLaunch::Host(port)),
+            (_, Some(address)) => Some(NetLaunch::Join(address)),
+            _ => None
+        };
+
+        Ok(Launch {
+            start: StartOptions {
+                seed: self.seed,
+                level: self.level - 1,
+                wave: self.wave,
+                difficulty: self.difficulty,
+                rank: self.rank,
+                mode: if self.endless { GameMode::Endless } else { GameMode::Campaign },
+                co_op: self.co_op || net.is_some(),
+                continues: if self.shared_continues { Continues::Shared } else { Continues::Separate }
+            },
+            config: self.config.clone(),
+            net,
+            options: LaunchOptions {
+                // An online run starts once the peer is there instead
+                auto_start: (self.headless || playback.is_some()) && net.is_none(),
+                headless: self.headless,
+                ticks: self.ticks,
+                record: self.record.clone()
+            },
+            playback
+        })
+    }
+}
+
+/// What the game does on its own at launch and when it exits
+#[derive(Resource, Debug, Clone, Default, PartialEq)]
+pub struct LaunchOptions {
+    /// Skips the menu and starts a run straight away
+    pub auto_start: bool,
+    /// Exits with a summary once the run is over
+    pub headless: bool,
+    /// Fixed ticks left to simulate before exiting in headless mode
+    pub ticks: Option<u32>,
+    pub record: Option<PathBuf>
+}
+
+/// Starts the game, records and exits the way `LaunchOptions` say
+pub struct LaunchPlugin {
+    pub options: LaunchOptions
+}
+
+impl Plugin for LaunchPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.options.clone())
+            .add_systems(Update, auto_start.run_if(in_state(GameState::Menu)))
+            .add_systems(
+                Update,
+                (save_recording, exit_after_game_over.run_if(in_state(GameState::GameOver))).chain()
+            )
+            .add_systems(
+                FixedUpdate,
+                count_ticks
+                    .after(PlayerInputSet)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(not(resource_exists::<Resimulating>()))
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        require::<RunStart, ReplayPlugin>(app);
+        require::<RunStats, StatsPlugin>(app);
+    }
+}
+
+fn auto_start(mut launch: ResMut<LaunchOptions>, mut next_state: ResMut<NextState<GameState>>) {
+    if !launch.auto_start { return }
+
+    launch.auto_start = false;
+    next_state.set(GameState::Playing);
+}
+
+fn save_recording(launch: Res<LaunchOptions>, last_replay: Option<Res<LastReplay>>) {
+    let (Some(path), Some(last_replay)) = (&launch.record, last_replay) else { return };
+    if !last_replay.is_changed() { return }
+
+    match last_replay.0.save(path) {
+        Ok(()) => info!("Saved replay to {}", path.display()),
+        Err(error) => error!("Could not save replay to {}: {}", path.display(), error)
+    }
+}
+
+fn print_summary(stats: &RunStats, run_start: &RunStart, net_report: Option<&NetReport>) {
+    println!(
+        "seed {} score {} wave {} kills {} time {:.2}s",
+        run_start.0.seed.unwrap_or_default(),
+        stats.score,
+        stats.wave_reached,
+        stats.total_kills(),
+        stats.time_survived
+    );
+
+    // Only online runs compare checksums with a peer
+    if let Some(report) = net_report.filter(|report| report.checked_ticks > 0) {
+        println!(
+            "rollbacks {} resimulated {} checked {} desyncs {}",
+            report.rollbacks,
+            report.resimulated_ticks,
+            report.checked_ticks,
+            report.desyncs
+        );
+    }
+}
+
+fn count_ticks(
+    mut launch: ResMut<LaunchOptions>,
+    mut app_exit_events: EventWriter<AppExit>,
+    recorder: Option<Res<ReplayRecorder>>,
+    stats: Res<RunStats>,
+    run_start: Res<RunStart>,
+    net_report: Option<Res<NetReport>>
+) {
+    if !launch.headless { return }
+    let Some(ticks) = launch.ticks.as_mut() else { return };
+
+    *ticks = ticks.saturating_sub(1);
+    if *ticks > 0 { return }
+
+    // The run is cut short, so the recording is saved from what was played so far
+    if let (Some(path), Some(recorder)) = (&launch.record, recorder) {
+        if let Err(error) = recorder.0.save(path) {
+            error!("Could not save replay to {}: {}", path.display(), error);
+        }
+    }
+
+    print_summary(&stats, &run_start, net_report.as_deref());
+    app_exit_events.send(AppExit);
+}
+
+fn exit_after_game_over(
+    launch: Res<LaunchOptions>,
+    mut app_exit_events: EventWriter<AppExit>,
+    stats: Res<RunStats>,
+    run_start: Res<RunStart>,
+    net_report: Option<Res<NetReport>>
+) {
+    if !launch.headless { return }
+
+    print_summary(&stats, &run_start, net_report.as_deref());
+    app_exit_events.send(AppExit);
+}
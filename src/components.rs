@@ -1,5 +1,10 @@
 use bevy::ecs::component::Component;
+use bevy::ecs::query::{Or, With, Without};
+use bevy::math::Vec2;
 use bevy::time::Timer;
+use serde::{Deserialize, Serialize};
+
+use crate::damage::DamageType;
 
 #[derive(Component)]
 pub struct SpaceShip;
@@ -7,15 +12,109 @@ pub struct SpaceShip;
 #[derive(Component)]
 pub struct Pumper;
 
+/// Marks the core of a ship, its hurtbox, shown while the player holds focus
+#[derive(Component)]
+pub struct FocusCore;
+
+/// Radius around a ship's core within which foe projectiles passing by are grazed
+#[derive(Component)]
+pub struct Graze(pub f32);
+
+/// Foe projectile that was already grazed, it only counts once
+#[derive(Component)]
+#[component(storage = "SparseSet")]
+pub struct Grazed;
+
+/// Foe that already rammed a ship, it only hurts once
 #[derive(Component)]
+#[component(storage = "SparseSet")]
+pub struct Rammed;
+
+/// Sparse so the projectile pool takes it off and back on without moving the rest of the entity
+#[derive(Component)]
+#[component(storage = "SparseSet")]
 pub struct Bullet;
 
+/// Player a ship, its pumper or a bullet belongs to, from 0
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Player(pub usize);
+
+/// Player whose bullet or bomb hit a foe last, credited with its kill
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct LastHitBy(pub usize);
+
 #[derive(Component)]
 pub struct Foe;
 
+/// Sits on the terrain, carried along at this parallax speed of the background layer below it
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Grounded(pub f32);
+
+/// Which kind of foe an entity is, used to tell kills apart in the run statistics
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FoeKind {
+    Fighter,
+    /// Slower and tougher fighter, mixed in as endless mode goes on
+    Heavy,
+    /// Ends every wave of endless mode
+    MiniBoss,
+    Boss,
+    /// Stationary ground target
+    Bunker,
+    /// Ground gun that turns to track the player before firing
+    Turret
+}
+
+impl FoeKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FoeKind::Fighter => "Fighter",
+            FoeKind::Heavy => "Heavy",
+            FoeKind::MiniBoss => "Mini-boss",
+            FoeKind::Boss => "Boss",
+            FoeKind::Bunker => "Bunker",
+            FoeKind::Turret => "Turret"
+        }
+    }
+
+    /// Ground foes sit on the terrain, ships fly over them
+    pub fn grounded(&self) -> bool {
+        matches!(self, FoeKind::Bunker | FoeKind::Turret)
+    }
+
+    /// Bosses stop in the upper part of the screen instead of flying past
+    pub fn hovers(&self) -> bool {
+        matches!(self, FoeKind::MiniBoss | FoeKind::Boss)
+    }
+}
+
+/// Sparse like `Bullet`, for the same reason
 #[derive(Component)]
+#[component(storage = "SparseSet")]
 pub struct FoeProjectile;
 
+/// Destroyed during the current tick, removed once at the end of it however many systems destroyed it
+#[derive(Component)]
+#[component(storage = "SparseSet")]
+pub struct Dead;
+
+/// Foes still in play, leaving out those destroyed earlier in the tick
+pub type LiveFoe = (With<Foe>, Without<Dead>);
+
+/// Ships that can still be hit, neither invulnerable nor destroyed earlier in the tick
+pub type HittableShip = (With<SpaceShip>, Without<Invulnerable>, Without<Dead>);
+
+/// The pumper and the focus core following a ship around
+pub type ShipAttachment = Or<(With<Pumper>, With<FocusCore>)>;
+
+/// Space ship that is not hurt by projectiles or by ramming foes
+#[derive(Component)]
+pub struct Invulnerable;
+
+/// Kept when the screen changes, e.g. overlays that live as long as the game
+#[derive(Component)]
+pub struct Persistent;
+
 #[derive(Component)]
 pub struct Speed(pub f32);
 
@@ -25,8 +124,21 @@ pub struct ShootingDelay(pub Timer);
 #[derive(Component)]
 pub struct Health(pub i32);
 
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct Damage {
+    pub amount: i32,
+    pub kind: DamageType
+}
+
+impl Damage {
+    pub fn new(amount: i32, kind: DamageType) -> Self {
+        Self { amount, kind }
+    }
+}
+
+/// Collision box size, independent from the sprite scale
 #[derive(Component)]
-pub struct Damage(pub i32);
+pub struct Hitbox(pub Vec2);
 
 #[derive(Component)]
 #[allow(dead_code)]
@@ -60,4 +172,48 @@ impl AnimationIndices {
 pub struct AnimationTimer(pub Timer);
 
 #[derive(Component)]
-pub struct GameBanner;
\ No newline at end of file
+pub struct GameBanner;
+
+/// Menu line showing the mode, difficulty, rank and players the next run starts with
+#[derive(Component)]
+pub struct MenuRunOptionsText;
+
+#[derive(Component)]
+pub struct LoadingScreen;
+
+#[derive(Component)]
+pub struct LoadingProgressBar;
+
+#[derive(Component)]
+pub struct LoadingErrorText;
+
+#[derive(Component)]
+pub struct Hud;
+
+/// Position of a segment in the HUD health bar, lit while the ship health is above it
+#[derive(Component)]
+pub struct HealthSegment(pub i32);
+
+/// HUD number of the player on the same entity
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HudPlayerCounter {
+    Lives,
+    Bombs,
+    /// Only shown in co-op, the team score is shown otherwise
+    Score,
+    /// Foe projectiles grazed
+    Graze
+}
+
+#[derive(Component)]
+pub struct HudScoreText;
+
+#[derive(Component)]
+pub struct HudHiScoreText;
+
+#[derive(Component)]
+pub struct HudWaveText;
+
+/// Blinking banner in the middle of the screen, with the seconds it stays up for
+#[derive(Component)]
+pub struct HudWarningText(pub f32);
@@ -0,0 +1,616 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::components::*;
+use crate::constants::*;
+use crate::damage::*;
+use crate::difficulty::*;
+use crate::endless::*;
+use crate::manifest::*;
+use crate::projectiles::*;
+use crate::resources::*;
+
+/// Loads the balancing values at startup and reloads them whenever the file changes on disk
+pub struct ConfigPlugin {
+    pub path: PathBuf
+}
+
+impl Default for ConfigPlugin {
+    fn default() -> Self {
+        Self { path: asset_root().join(AssetName::GameConfig.path()) }
+    }
+}
+
+impl Plugin for ConfigPlugin {
+    fn build(&self, app: &mut App) {
+        let config = match GameConfig::load(&self.path) {
+            Ok(config) => config,
+            Err(errors) => {
+                let mut loading_assets = app.world.get_resource_or_insert_with(LoadingAssets::default);
+                for config_error in errors {
+                    let message = format!("Invalid game config {}: {}", self.path.display(), config_error);
+                    error!("{}", message);
+                    loading_assets.errors.push(message);
+                }
+                GameConfig::default()
+            }
+        };
+
+        app.insert_resource(config)
+            .insert_resource(ConfigWatcher {
+                path: self.path.clone(),
+                modified: modified_time(&self.path),
+                timer: Timer::from_seconds(CONFIG_RELOAD_INTERVAL, TimerMode::Repeating)
+            })
+            .add_systems(Update, reload_config);
+    }
+}
+
+/// Balancing values, changes apply to everything spawned after the file is saved
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameConfig {
+    pub ship: ShipConfig,
+    pub foe: FoeConfig,
+    pub heavy: FoeConfig,
+    pub boss: BossConfig,
+    pub mini_boss: BossConfig,
+    pub bunker: BunkerConfig,
+    pub turret: TurretConfig,
+    pub difficulty: DifficultyConfig,
+    pub rank: RankConfig,
+    pub endless: EndlessConfig,
+    pub projectiles: ProjectileConfig
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            ship: ShipConfig::default(),
+            foe: FoeConfig::default(),
+            heavy: FoeConfig {
+                speed: 150.0,
+                damage: 3,
+                health: 36,
+                score: 300,
+                shoot_delay: 0.8,
+                projectile_speed: 420.0,
+                shield: ShieldConfig { points: 6, recharge_delay: 2.0, recharge_time: 0.25 },
+                ..default()
+            },
+            boss: BossConfig::default(),
+            mini_boss: BossConfig {
+                speed: 100.0,
+                health: 90,
+                score: 1500,
+                shoot_delay: 0.6,
+                armor: Armor { explosive: 0.5, ..default() }
+            },
+            bunker: BunkerConfig::default(),
+            turret: TurretConfig::default(),
+            difficulty: DifficultyConfig::default(),
+            rank: RankConfig::default(),
+            endless: EndlessConfig::default(),
+            projectiles: ProjectileConfig::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShipConfig {
+    pub speed: f32,
+    pub damage: i32,
+    pub health: i32,
+    pub bullet_speed: f32,
+    pub shooting_delay: f32,
+    pub lives: u32,
+    pub bombs: u32,
+    /// Explosive damage to every foe on screen
+    pub bomb_damage: i32,
+    /// Kinetic damage a foe deals by flying into the ship
+    pub ram_damage: i32,
+    pub respawn_delay: f32,
+    /// Share of `speed` kept while focused
+    pub focus_speed: f32,
+    pub graze_radius: f32,
+    /// Points for each foe projectile grazed
+    pub graze_score: u32,
+    /// Type of the damage bullets deal
+    pub damage_type: DamageType,
+    pub armor: Armor,
+    pub shield: ShieldConfig
+}
+
+impl Default for ShipConfig {
+    fn default() -> Self {
+        Self {
+            speed: 530.0,
+            damage: 2,
+            health: 10,
+            bullet_speed: 980.0,
+            shooting_delay: 0.12,
+            lives: 3,
+            bombs: 3,
+            bomb_damage: 20,
+            ram_damage: 5,
+            respawn_delay: 1.5,
+            focus_speed: 0.45,
+            graze_radius: 48.0,
+            graze_score: 10,
+            damage_type: DamageType::Kinetic,
+            armor: Armor::default(),
+            shield: ShieldConfig { points: 2, recharge_delay: 3.0, recharge_time: 1.0 }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FoeConfig {
+    pub speed: f32,
+    pub damage: i32,
+    pub health: i32,
+    pub score: u32,
+    pub shoot_delay: f32,
+    pub projectile_speed: f32,
+    /// Type of the damage projectiles deal
+    pub damage_type: DamageType,
+    pub armor: Armor,
+    pub shield: ShieldConfig
+}
+
+impl Default for FoeConfig {
+    fn default() -> Self {
+        Self {
+            speed: 230.0,
+            damage: 2,
+            health: 12,
+            score: 100,
+            shoot_delay: 1.2,
+            projectile_speed: 500.0,
+            damage_type: DamageType::Energy,
+            armor: Armor::default(),
+            shield: ShieldConfig::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BossConfig {
+    pub speed: f32,
+    pub health: i32,
+    pub score: u32,
+    pub shoot_delay: f32,
+    pub armor: Armor
+}
+
+impl Default for BossConfig {
+    fn default() -> Self {
+        Self {
+            speed: 80.0,
+            health: 240,
+            score: 5000,
+            shoot_delay: 0.4,
+            // Bombs take far less out of bosses
+            armor: Armor { explosive: 0.5, ..default() }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BunkerConfig {
+    pub health: i32,
+    pub score: u32,
+    pub armor: Armor
+}
+
+impl Default for BunkerConfig {
+    fn default() -> Self {
+        Self {
+            health: 20,
+            score: 200,
+            armor: Armor { kinetic: 0.25, ..default() }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TurretConfig {
+    pub damage: i32,
+    pub health: i32,
+    pub score: u32,
+    pub shoot_delay: f32,
+    pub projectile_speed: f32,
+    pub damage_type: DamageType,
+    pub armor: Armor,
+    /// Radians per second the gun turns to track the player
+    pub turn_rate: f32,
+    /// Radians off the player the gun may still fire at
+    pub aim_tolerance: f32
+}
+
+impl Default for TurretConfig {
+    fn default() -> Self {
+        Self {
+            damage: 2,
+            health: 16,
+            score: 250,
+            shoot_delay: 1.5,
+            projectile_speed: 380.0,
+            damage_type: DamageType::Energy,
+            armor: Armor { kinetic: 0.25, ..default() },
+            turn_rate: 1.5,
+            aim_tolerance: 0.1
+        }
+    }
+}
+
+/// Multipliers of every difficulty preset
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DifficultyConfig {
+    pub easy: DifficultyScaling,
+    pub normal: DifficultyScaling,
+    pub hard: DifficultyScaling,
+    pub insane: DifficultyScaling
+}
+
+impl Default for DifficultyConfig {
+    fn default() -> Self {
+        Self {
+            easy: DifficultyScaling { health: 0.75, projectile_speed: 0.8, fire_rate: 0.7, spawn_density: 0.75 },
+            normal: DifficultyScaling::default(),
+            hard: DifficultyScaling { health: 1.3, projectile_speed: 1.15, fire_rate: 1.3, spawn_density: 1.25 },
+            insane: DifficultyScaling { health: 1.7, projectile_speed: 1.35, fire_rate: 1.7, spawn_density: 1.5 }
+        }
+    }
+}
+
+/// How fast the rank moves, and how much it changes the intensity
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RankConfig {
+    /// Rank gained every second alive
+    pub time_gain: f32,
+    pub kill_gain: f32,
+    /// Rank lost for every point of damage taken
+    pub damage_loss: f32,
+    /// Intensity change at the highest rank, the lowest rank changes it by as much the other way
+    pub intensity: f32
+}
+
+impl Default for RankConfig {
+    fn default() -> Self {
+        Self {
+            time_gain: 0.005,
+            kill_gain: 0.02,
+            damage_loss: 0.05,
+            intensity: 0.3
+        }
+    }
+}
+
+/// How endless mode escalates
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EndlessConfig {
+    /// Points the spawn curve goes through, sorted by time, values in between are interpolated
+    pub curve: Vec<SpawnCurvePoint>,
+    /// Seconds in a wave, every wave ends with a mini-boss
+    pub wave_time: f32,
+    /// Score between two milestone rewards
+    pub milestone_score: u32,
+    /// Every this many milestones the reward is a life instead of a bomb
+    pub life_milestones: u32
+}
+
+impl Default for EndlessConfig {
+    fn default() -> Self {
+        Self {
+            curve: vec![
+                SpawnCurvePoint { time: 0.0, interval: 1.4, heavy_share: 0.0 },
+                SpawnCurvePoint { time: 60.0, interval: 0.9, heavy_share: 0.15 },
+                SpawnCurvePoint { time: 180.0, interval: 0.55, heavy_share: 0.35 },
+                SpawnCurvePoint { time: 420.0, interval: 0.3, heavy_share: 0.5 }
+            ],
+            wave_time: 45.0,
+            milestone_score: 10000,
+            life_milestones: 3
+        }
+    }
+}
+
+/// Most projectiles of each kind in flight at once, shots past the cap are dropped,
+/// and the behaviours every shot of a kind is fired with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectileConfig {
+    pub bullets: usize,
+    pub foe_projectiles: usize,
+    pub bullet_behaviours: ProjectileBehaviours,
+    pub foe_projectile_behaviours: ProjectileBehaviours
+}
+
+impl Default for ProjectileConfig {
+    fn default() -> Self {
+        Self {
+            bullets: 256,
+            foe_projectiles: 1024,
+            bullet_behaviours: ProjectileBehaviours::default(),
+            foe_projectile_behaviours: ProjectileBehaviours::default()
+        }
+    }
+}
+
+impl EndlessConfig {
+    /// Spawn rate and foe mix `time` seconds into the run, the last point holds once it is passed
+    pub fn sample(&self, time: f32) -> SpawnCurvePoint {
+        let Some(first) = self.curve.first() else { return SpawnCurvePoint::default() };
+        if time <= first.time { return *first }
+
+        for pair in self.curve.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+            if time > to.time { continue }
+
+            let t = (time - from.time) / (to.time - from.time).max(f32::EPSILON);
+            return SpawnCurvePoint {
+                time,
+                interval: from.interval + (to.interval - from.interval) * t,
+                heavy_share: from.heavy_share + (to.heavy_share - from.heavy_share) * t
+            };
+        }
+
+        *self.curve.last().unwrap_or(first)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    Read(String),
+    Parse(String),
+    OutOfRange { field: String, value: f64, min: f64, max: f64 }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Read(error) => write!(f, "could not read the file: {}", error),
+            ConfigError::Parse(error) => write!(f, "could not parse the file: {}", error),
+            ConfigError::OutOfRange { field, value, min, max } =>
+                write!(f, "{} is {}, it must be between {} and {}", field, value, min, max)
+        }
+    }
+}
+
+impl GameConfig {
+    pub fn load(path: &Path) -> Result<Self, Vec<ConfigError>> {
+        let contents = fs::read_to_string(path).map_err(|error| vec![ConfigError::Read(error.to_string())])?;
+        Self::parse(&contents)
+    }
+
+    /// Reads a config, missing fields keep their default value
+    pub fn parse(contents: &str) -> Result<Self, Vec<ConfigError>> {
+        let config: GameConfig = ron::from_str(contents).map_err(|error| vec![ConfigError::Parse(error.to_string())])?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Lists every value outside of its allowed range
+    pub fn validate(&self) -> Result<(), Vec<ConfigError>> {
+        let mut errors = Vec::new();
+        let mut check = |field: &str, value: f64, min: f64, max: f64| {
+            if !(min..=max).contains(&value) {
+                errors.push(ConfigError::OutOfRange { field: field.to_string(), value, min, max });
+            }
+        };
+
+        check("ship.speed", self.ship.speed as f64, 1.0, 5000.0);
+        check("ship.damage", self.ship.damage as f64, 1.0, 1000.0);
+        check("ship.health", self.ship.health as f64, 1.0, 100.0);
+        check("ship.bullet_speed", self.ship.bullet_speed as f64, 1.0, 5000.0);
+        check("ship.shooting_delay", self.ship.shooting_delay as f64, 0.01, 10.0);
+        check("ship.lives", self.ship.lives as f64, 1.0, 99.0);
+        check("ship.bombs", self.ship.bombs as f64, 0.0, 99.0);
+        check("ship.bomb_damage", self.ship.bomb_damage as f64, 0.0, 10000.0);
+        check("ship.ram_damage", self.ship.ram_damage as f64, 0.0, 10000.0);
+        check("ship.respawn_delay", self.ship.respawn_delay as f64, 0.0, 30.0);
+        check("ship.focus_speed", self.ship.focus_speed as f64, 0.05, 1.0);
+        check("ship.graze_radius", self.ship.graze_radius as f64, 0.0, 500.0);
+        check("ship.graze_score", self.ship.graze_score as f64, 0.0, 100000.0);
+
+        for (name, foe) in [("foe", &self.foe), ("heavy", &self.heavy)] {
+            check(&format!("{}.speed", name), foe.speed as f64, 1.0, 5000.0);
+            check(&format!("{}.damage", name), foe.damage as f64, 0.0, 1000.0);
+            check(&format!("{}.health", name), foe.health as f64, 1.0, 100000.0);
+            check(&format!("{}.score", name), foe.score as f64, 0.0, 1000000.0);
+            check(&format!("{}.shoot_delay", name), foe.shoot_delay as f64, 0.05, 60.0);
+            check(&format!("{}.projectile_speed", name), foe.projectile_speed as f64, 1.0, 5000.0);
+        }
+
+        for (name, boss) in [("boss", &self.boss), ("mini_boss", &self.mini_boss)] {
+            check(&format!("{}.speed", name), boss.speed as f64, 1.0, 5000.0);
+            check(&format!("{}.health", name), boss.health as f64, 1.0, 100000.0);
+            check(&format!("{}.score", name), boss.score as f64, 0.0, 1000000.0);
+            check(&format!("{}.shoot_delay", name), boss.shoot_delay as f64, 0.05, 60.0);
+        }
+
+        check("bunker.health", self.bunker.health as f64, 1.0, 100000.0);
+        check("bunker.score", self.bunker.score as f64, 0.0, 1000000.0);
+        check("turret.damage", self.turret.damage as f64, 0.0, 1000.0);
+        check("turret.health", self.turret.health as f64, 1.0, 100000.0);
+        check("turret.score", self.turret.score as f64, 0.0, 1000000.0);
+        check("turret.shoot_delay", self.turret.shoot_delay as f64, 0.05, 60.0);
+        check("turret.projectile_speed", self.turret.projectile_speed as f64, 1.0, 5000.0);
+        check("turret.turn_rate", self.turret.turn_rate as f64, 0.0, 50.0);
+        check("turret.aim_tolerance", self.turret.aim_tolerance as f64, 0.0, std::f64::consts::PI);
+
+        let armors = [
+            ("ship", &self.ship.armor),
+            ("foe", &self.foe.armor),
+            ("heavy", &self.heavy.armor),
+            ("boss", &self.boss.armor),
+            ("mini_boss", &self.mini_boss.armor),
+            ("bunker", &self.bunker.armor),
+            ("turret", &self.turret.armor)
+        ];
+        for (name, armor) in armors {
+            for (kind, value) in [("kinetic", armor.kinetic), ("energy", armor.energy), ("explosive", armor.explosive)] {
+                check(&format!("{}.armor.{}", name, kind), value as f64, 0.0, 1.0);
+            }
+        }
+
+        for (name, shield) in [("ship", &self.ship.shield), ("foe", &self.foe.shield), ("heavy", &self.heavy.shield)] {
+            check(&format!("{}.shield.points", name), shield.points as f64, 0.0, 1000.0);
+            check(&format!("{}.shield.recharge_delay", name), shield.recharge_delay as f64, 0.0, 60.0);
+            check(&format!("{}.shield.recharge_time", name), shield.recharge_time as f64, 0.01, 60.0);
+        }
+
+        let presets = [
+            ("difficulty.easy", &self.difficulty.easy),
+            ("difficulty.normal", &self.difficulty.normal),
+            ("difficulty.hard", &self.difficulty.hard),
+            ("difficulty.insane", &self.difficulty.insane)
+        ];
+        for (name, preset) in presets {
+            let fields = [
+                ("health", preset.health),
+                ("projectile_speed", preset.projectile_speed),
+                ("fire_rate", preset.fire_rate),
+                ("spawn_density", preset.spawn_density)
+            ];
+            for (field, value) in fields {
+                check(&format!("{}.{}", name, field), value as f64, 0.1, 10.0);
+            }
+        }
+
+        check("rank.time_gain", self.rank.time_gain as f64, 0.0, 1.0);
+        check("rank.kill_gain", self.rank.kill_gain as f64, 0.0, 1.0);
+        check("rank.damage_loss", self.rank.damage_loss as f64, 0.0, 1.0);
+        check("rank.intensity", self.rank.intensity as f64, 0.0, 0.9);
+
+        check("endless.curve points", self.endless.curve.len() as f64, 1.0, 100.0);
+        let mut previous_time = 0.0;
+        for (index, point) in self.endless.curve.iter().enumerate() {
+            // Times have to go up, so each point is checked against the one before it
+            check(&format!("endless.curve[{}].time", index), point.time as f64, previous_time, 36000.0);
+            check(&format!("endless.curve[{}].interval", index), point.interval as f64, 0.05, 30.0);
+            check(&format!("endless.curve[{}].heavy_share", index), point.heavy_share as f64, 0.0, 1.0);
+            previous_time = point.time as f64;
+        }
+        check("endless.wave_time", self.endless.wave_time as f64, 5.0, 3600.0);
+        check("endless.milestone_score", self.endless.milestone_score as f64, 1.0, 10000000.0);
+        check("endless.life_milestones", self.endless.life_milestones as f64, 1.0, 100.0);
+
+        check("projectiles.bullets", self.projectiles.bullets as f64, 1.0, 100000.0);
+        check("projectiles.foe_projectiles", self.projectiles.foe_projectiles as f64, 1.0, 100000.0);
+
+        let behaviours = [
+            ("projectiles.bullet_behaviours", &self.projectiles.bullet_behaviours),
+            ("projectiles.foe_projectile_behaviours", &self.projectiles.foe_projectile_behaviours)
+        ];
+        for (name, behaviours) in behaviours {
+            if let Some(pierce) = behaviours.pierce {
+                check(&format!("{}.pierce", name), pierce as f64, 0.0, 100.0);
+            }
+            if let Some(bounce) = behaviours.bounce {
+                check(&format!("{}.bounce", name), bounce as f64, 0.0, 100.0);
+            }
+            if let Some(turn_rate) = behaviours.homing {
+                check(&format!("{}.homing", name), turn_rate as f64, 0.0, 50.0);
+            }
+            if let Some(lifetime) = behaviours.lifetime {
+                check(&format!("{}.lifetime", name), lifetime as f64, 0.05, 60.0);
+            }
+            if let Some(split) = behaviours.split {
+                check(&format!("{}.split.count", name), split.count as f64, 1.0, 16.0);
+                check(&format!("{}.split.spread", name), split.spread as f64, 0.0, std::f64::consts::TAU);
+            }
+        }
+
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    pub fn scaling(&self, difficulty: Difficulty) -> DifficultyScaling {
+        match difficulty {
+            Difficulty::Easy => self.difficulty.easy,
+            Difficulty::Normal => self.difficulty.normal,
+            Difficulty::Hard => self.difficulty.hard,
+            Difficulty::Insane => self.difficulty.insane
+        }
+    }
+
+    pub fn score(&self, kind: FoeKind) -> u32 {
+        match kind {
+            FoeKind::Fighter => self.foe.score,
+            FoeKind::Heavy => self.heavy.score,
+            FoeKind::MiniBoss => self.mini_boss.score,
+            FoeKind::Boss => self.boss.score,
+            FoeKind::Bunker => self.bunker.score,
+            FoeKind::Turret => self.turret.score
+        }
+    }
+
+    /// Damage and speed of the projectiles a foe fires
+    pub fn projectile(&self, kind: FoeKind) -> (Damage, f32) {
+        if kind == FoeKind::Turret {
+            return (Damage::new(self.turret.damage, self.turret.damage_type), self.turret.projectile_speed);
+        }
+
+        let foe = if kind == FoeKind::Heavy { &self.heavy } else { &self.foe };
+        (Damage::new(foe.damage, foe.damage_type), foe.projectile_speed)
+    }
+
+    /// Armor and shield a foe is spawned with, bosses and ground foes have no shield
+    pub fn defences(&self, kind: FoeKind) -> (Armor, Option<Shield>) {
+        match kind {
+            FoeKind::Fighter => (self.foe.armor, self.foe.shield.shield()),
+            FoeKind::Heavy => (self.heavy.armor, self.heavy.shield.shield()),
+            FoeKind::MiniBoss => (self.mini_boss.armor, None),
+            FoeKind::Boss => (self.boss.armor, None),
+            FoeKind::Bunker => (self.bunker.armor, None),
+            FoeKind::Turret => (self.turret.armor, None)
+        }
+    }
+}
+
+/// Where the config was loaded from, and when the file was last seen changing
+#[derive(Resource)]
+pub struct ConfigWatcher {
+    pub path: PathBuf,
+    pub modified: Option<SystemTime>,
+    pub timer: Timer
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+fn reload_config(mut config: ResMut<GameConfig>, mut watcher: ResMut<ConfigWatcher>, time: Res<Time<Real>>) {
+    if !watcher.timer.tick(time.delta()).just_finished() { return }
+
+    let modified = modified_time(&watcher.path);
+    if modified == watcher.modified { return }
+    watcher.modified = modified;
+
+    // A broken edit keeps the previous values so the running game is not affected
+    match GameConfig::load(&watcher.path) {
+        Ok(new_config) => {
+            info!("Reloaded game config from {}", watcher.path.display());
+            *config = new_config;
+        },
+        Err(errors) => {
+            for config_error in errors {
+                warn!("Ignoring game config change in {}: {}", watcher.path.display(), config_error);
+            }
+        }
+    }
+}
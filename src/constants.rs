@@ -1,33 +1,115 @@
+use bevy::math::Vec2;
+use bevy::render::color::Color;
+
 // Window values
 pub const WINDOW_WIDTH: f32 = 640.0;
 pub const WINDOW_HEIGHT: f32 = 480.0;
 
-// Background layers
-pub const BACKGROUND_WIDTH: f32 = WINDOW_WIDTH / 4.0;
-pub const BACKGROUND_HEIGHT: f32 = WINDOW_HEIGHT / 4.0;
-pub const BACKGROUND_LAYER_1: &'static str = "textures\\background_layer1.png";
-pub const BACKGROUND_LAYER_2: &'static str = "textures\\background_layer2.png";
+// Settings and leaderboard files, relative to the working directory
+pub const SETTINGS_PATH: &str = "settings.ron";
+pub const LEADERBOARD_PATH: &str = "leaderboard.ron";
+pub const LEADERBOARD_SIZE: usize = 10;
+
+// Game config, balancing values themselves live in the config file
+pub const CONFIG_RELOAD_INTERVAL: f32 = 0.5;
 
-// Sprite textures paths
-pub const SHIP_SPRITES: &'static str = "textures\\SpaceShooterAssetPack_Ships.png";
-pub const PROJECTILE_SPRITES: &'static str = "textures\\SpaceShooterAssetPack_Projectiles.png";
-pub const MISCELLANEOUS_SPRITES: &'static str = "textures\\SpaceShooterAssetPack_Miscellaneous.png";
-pub const UI_SPRITES: &'static str = "textures\\SpaceShooterAssetPack_IU.png";
+// Background values
+pub const BACKGROUND_TILE_SIZE: Vec2 = Vec2::new(128.0, 256.0);
+pub const BACKGROUND_SCALE: f32 = 2.5;
+pub const BACKGROUND_SCROLL_SPEED: f32 = 120.0;
+pub const BACKGROUND_SCROLL_ACCELERATION: f32 = 240.0;
+
+// Sprite sheets
+pub const SPRITE_TILE_SIZE: f32 = 8.0;
 
 // Ship/player values
-pub const SHIP_SPEED: f32 = 530.0;
-pub const SHIP_DAMAGE: i32 = 2;
-pub const SHIP_HEALTH: i32 = 10;
-pub const SHIP_BULLET_SPEED: f32 = 980.0;
-pub const SHIP_SHOOTING_DELAY: f32 = 0.12;
+pub const MAX_PLAYERS: usize = 2;
+pub const SHIP_COLORS: [Color; MAX_PLAYERS] = [Color::WHITE, Color::rgb(0.6, 1.0, 0.6)];
+pub const SHIP_CO_OP_SPACING: f32 = 160.0;
 pub const SHIP_PUMPER_ANIMATION_TIME: f32 = 0.07;
+pub const SHIP_SCALE: f32 = 6.0;
+/// Whole body of the ship, foes flying into any of it ram it
+pub const SHIP_HITBOX: Vec2 = Vec2::new(30.0, 30.0);
+/// Only the core of the ship gets hurt, much smaller than its sprite
+pub const SHIP_CORE_HITBOX: Vec2 = Vec2::new(8.0, 8.0);
+pub const SHIP_CORE_COLOR: Color = Color::rgb(1.0, 0.3, 0.4);
+pub const SHIP_BULLET_SCALE: f32 = 6.0;
+pub const SHIP_BULLET_HITBOX: Vec2 = Vec2::new(10.0, 20.0);
 
 // Foe values
-pub const FOE_SPEED: f32 = 230.0;
-pub const FOE_DAMAGE: i32 = 2;
-pub const FOE_HEALTH: i32 = 12;
-pub const FOE_SPAWN_DELAY: f32 = 1.5;
 pub const FOE_UNITS: f32 = 32.0;
 pub const FOE_UNIT_WIDTH: f32 = WINDOW_WIDTH / FOE_UNITS;
-pub const FOE_SHOOT_DELAY: f32 = 1.2;
-pub const FOE_PROJECTILE_SPEED: f32 = 500.0;
\ No newline at end of file
+pub const FOE_SCALE: f32 = 4.0;
+pub const FOE_HITBOX: Vec2 = Vec2::new(30.0, 30.0);
+pub const FOE_PROJECTILE_SCALE: f32 = 3.0;
+pub const FOE_PROJECTILE_HITBOX: Vec2 = Vec2::new(16.0, 16.0);
+pub const HEAVY_SCALE: f32 = 5.5;
+pub const HEAVY_HITBOX: Vec2 = Vec2::new(40.0, 40.0);
+pub const HEAVY_COLOR: Color = Color::rgb(1.0, 0.7, 0.5);
+
+// Ground values, ground foes and their wrecks are drawn between the background and everything flying
+pub const GROUND_Z: f32 = -5.0;
+pub const BUNKER_SCALE: f32 = 5.0;
+pub const BUNKER_HITBOX: Vec2 = Vec2::new(36.0, 36.0);
+pub const BUNKER_COLOR: Color = Color::rgb(0.7, 0.8, 0.6);
+pub const TURRET_SCALE: f32 = 4.5;
+pub const TURRET_HITBOX: Vec2 = Vec2::new(32.0, 32.0);
+pub const TURRET_COLOR: Color = Color::rgb(0.9, 0.6, 0.9);
+pub const WRECK_COLOR: Color = Color::rgb(0.25, 0.22, 0.2);
+// How far past the top and bottom of the screen ground foes come in and they and their wrecks are gone
+pub const GROUND_MARGIN: f32 = 50.0;
+
+// Formation values, how far past the screen squadrons come in and their members are gone
+pub const FORMATION_MARGIN: f32 = 50.0;
+pub const MAX_SQUADRON_SIZE: u32 = 32;
+
+// Boss values
+pub const BOSS_SCALE: f32 = 10.0;
+pub const BOSS_HITBOX: Vec2 = Vec2::new(70.0, 70.0);
+pub const BOSS_HOVER_HEIGHT: f32 = WINDOW_HEIGHT * 0.25;
+pub const MINI_BOSS_SCALE: f32 = 7.0;
+pub const MINI_BOSS_HITBOX: Vec2 = Vec2::new(50.0, 50.0);
+pub const MINI_BOSS_COLOR: Color = Color::rgb(0.6, 0.8, 1.0);
+
+// Difficulty values
+pub const RANK_MIN: f32 = -1.0;
+pub const RANK_MAX: f32 = 1.0;
+
+// Netcode values
+pub const NET_PROTOCOL_VERSION: u8 = 3;
+pub const NET_MAX_PACKET_SIZE: usize = 1472;
+pub const NET_MAX_PACKET_INPUTS: usize = 64;
+pub const NET_MAX_PREDICTION: u32 = 8;
+pub const NET_CHECK_INTERVAL: u32 = 16;
+pub const NET_TIMEOUT: f32 = 5.0;
+pub const NET_HELLO_INTERVAL: f32 = 0.1;
+
+// Debug overlay values
+pub const DEBUG_FONT_SIZE: f32 = 14.0;
+pub const DEBUG_CONSOLE_LINES: usize = 8;
+
+// Particle values
+pub const PARTICLE_CAPACITY: usize = 1024;
+
+// Hit feedback values
+pub const FEEDBACK_FLASH_TIME: f32 = 0.08;
+pub const FEEDBACK_FLASH_BRIGHTNESS: f32 = 4.0;
+pub const FEEDBACK_SHAKE_MAX_OFFSET: f32 = 14.0;
+pub const FEEDBACK_SHAKE_DECAY: f32 = 1.6;
+pub const FEEDBACK_TRAUMA_FOE_HIT: f32 = 0.08;
+pub const FEEDBACK_TRAUMA_FOE_KILLED: f32 = 0.25;
+pub const FEEDBACK_TRAUMA_PLAYER_HIT: f32 = 0.45;
+pub const FEEDBACK_HIT_STOP_TIME: f32 = 0.06;
+pub const FEEDBACK_HIT_STOP_KILLS: usize = 2;
+
+// HUD values
+pub const HUD_FONT_SIZE: f32 = 18.0;
+pub const HUD_ICON_SIZE: f32 = 16.0;
+pub const HUD_SEGMENT_WIDTH: f32 = 10.0;
+pub const HUD_EMPTY_SEGMENT_COLOR: Color = Color::rgba(0.2, 0.2, 0.2, 0.8);
+pub const HUD_WARNING_FONT_SIZE: f32 = 36.0;
+pub const HUD_WARNING_TIME: f32 = 3.0;
+pub const HUD_WARNING_BLINK: f32 = 0.25;
+
+// Run statistics values
+pub const STATS_COMBO_WINDOW: f32 = 2.0;
@@ -0,0 +1,212 @@
+use bevy::ecs::query::{ReadOnlyWorldQuery, WorldQuery};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::components::*;
+use crate::events::*;
+use crate::replay::*;
+use crate::states::*;
+
+/// Takes the hits sent as `DamageEvent` off ships and foes and recharges shields
+pub struct DamagePlugin;
+
+impl Plugin for DamagePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<DamageEvent>()
+            .add_event::<FoeHitEvent>()
+            .add_event::<PlayerHitEvent>()
+            .configure_sets(
+                FixedUpdate,
+                (
+                    DamageSet::Foes.in_set(HitSet).in_set(FoeHitSet),
+                    DamageSet::Ships.in_set(HitSet).after(FoeHitSet)
+                )
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    recharge_shields.after(PlayerInputSet).before(HitSet),
+                    apply_damage::<With<Foe>>.in_set(DamageSet::Foes),
+                    apply_damage::<With<SpaceShip>>.in_set(DamageSet::Ships)
+                ).run_if(in_state(GameState::Playing))
+            );
+    }
+}
+
+/// What a hit is made of, armor tells them apart
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DamageType {
+    /// Bullets and anything else that hits hard
+    #[default]
+    Kinetic,
+    /// Foe projectiles
+    Energy,
+    /// Bombs
+    Explosive
+}
+
+/// Share of the damage of each type taken off what gets through the shield, from 0.0 to 1.0
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Armor {
+    pub kinetic: f32,
+    pub energy: f32,
+    pub explosive: f32
+}
+
+impl Armor {
+    pub fn reduction(&self, kind: DamageType) -> f32 {
+        match kind {
+            DamageType::Kinetic => self.kinetic,
+            DamageType::Energy => self.energy,
+            DamageType::Explosive => self.explosive
+        }
+    }
+
+    fn reduce(&self, taken: &mut DamageTaken) {
+        let reduced = (taken.dealt as f32 * (1.0 - self.reduction(taken.kind))).round() as i32;
+        taken.resisted = taken.dealt - reduced.max(0);
+        taken.dealt -= taken.resisted;
+    }
+}
+
+/// Absorbs hits before armor and `Health` while it has points left, recharging a point every `recharge`
+/// once `delay` went by without a hit
+#[derive(Component, Debug, Clone, Serialize, Deserialize)]
+pub struct Shield {
+    pub points: i32,
+    pub max: i32,
+    pub delay: Timer,
+    pub recharge: Timer
+}
+
+impl Shield {
+    fn absorb(&mut self, taken: &mut DamageTaken) {
+        if taken.dealt <= 0 { return }
+
+        taken.absorbed = taken.dealt.min(self.points);
+        taken.dealt -= taken.absorbed;
+        self.points -= taken.absorbed;
+        self.delay.reset();
+        self.recharge.reset();
+    }
+}
+
+/// Balancing values of a shield, none is fitted with no points
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShieldConfig {
+    pub points: i32,
+    /// Seconds without a hit before it starts recharging
+    pub recharge_delay: f32,
+    /// Seconds to recharge each point
+    pub recharge_time: f32
+}
+
+impl Default for ShieldConfig {
+    fn default() -> Self {
+        Self { points: 0, recharge_delay: 2.0, recharge_time: 0.5 }
+    }
+}
+
+impl ShieldConfig {
+    /// Fully charged shield, if it has any points
+    pub fn shield(&self) -> Option<Shield> {
+        if self.points <= 0 { return None }
+
+        Some(Shield {
+            points: self.points,
+            max: self.points,
+            delay: Timer::from_seconds(self.recharge_delay, TimerMode::Once),
+            recharge: Timer::from_seconds(self.recharge_time, TimerMode::Repeating)
+        })
+    }
+}
+
+/// What became of a hit on its way through shield and armor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DamageTaken {
+    pub kind: DamageType,
+    /// Damage of the hit before any modifier
+    pub raw: i32,
+    /// Taken by the shield
+    pub absorbed: i32,
+    /// Taken off by armor
+    pub resisted: i32,
+    /// What reached the health
+    pub dealt: i32
+}
+
+/// Everything between a hit and the `Health` of a ship or foe
+#[derive(WorldQuery)]
+#[world_query(mutable)]
+pub struct Defences {
+    pub health: &'static mut Health,
+    pub armor: Option<&'static Armor>,
+    pub shield: Option<&'static mut Shield>
+}
+
+impl DefencesItem<'_> {
+    /// Runs a hit through the modifiers in order, the shield absorbing what it can then armor reducing
+    /// the rest by type, and takes what is left off `Health`
+    pub fn take(&mut self, damage: Damage) -> DamageTaken {
+        let mut taken = DamageTaken {
+            kind: damage.kind,
+            raw: damage.amount,
+            absorbed: 0,
+            resisted: 0,
+            dealt: damage.amount
+        };
+
+        if let Some(shield) = self.shield.as_mut() {
+            shield.absorb(&mut taken);
+        }
+        if let Some(armor) = self.armor {
+            armor.reduce(&mut taken);
+        }
+
+        self.health.0 -= taken.dealt;
+        taken
+    }
+}
+
+/// Takes the hits of the tick on targets matching `F` through their defences in the order they were sent,
+/// reporting what reached their health. Hits on a target destroyed earlier in the tick are dropped
+fn apply_damage<F: ReadOnlyWorldQuery + 'static>(
+    mut damage_events: EventReader<DamageEvent>,
+    mut targets: Query<(Defences, Has<SpaceShip>), (F, Without<Dead>)>,
+    mut foe_hit_events: EventWriter<FoeHitEvent>,
+    mut player_hit_events: EventWriter<PlayerHitEvent>
+) {
+    for event in damage_events.read() {
+        let Ok((mut defences, ship)) = targets.get_mut(event.target) else { continue };
+        if defences.health.0 <= 0 { continue }
+
+        let taken = defences.take(event.damage);
+        if ship {
+            player_hit_events.send(PlayerHitEvent {
+                ship: event.target,
+                position: event.position,
+                damage: taken.dealt,
+                fatal: defences.health.0 <= 0
+            });
+        } else if event.source == DamageSource::Projectile {
+            foe_hit_events.send(FoeHitEvent {
+                foe: event.target,
+                position: event.position,
+                damage: taken.dealt
+            });
+        }
+    }
+}
+
+fn recharge_shields(mut shields: Query<&mut Shield>, time: Res<Time>) {
+    for mut shield in &mut shields {
+        if shield.points >= shield.max { continue }
+        if !shield.delay.tick(time.delta()).finished() { continue }
+
+        if shield.recharge.tick(time.delta()).just_finished() {
+            shield.points += 1;
+        }
+    }
+}
@@ -0,0 +1,525 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use bevy::diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin};
+use bevy::ecs::system::SystemParam;
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use bevy::window::ReceivedCharacter;
+
+use crate::background::*;
+use crate::components::*;
+use crate::config::*;
+use crate::constants::*;
+use crate::difficulty::*;
+use crate::endless::*;
+use crate::foe::*;
+use crate::ground::*;
+use crate::level::*;
+use crate::player::*;
+use crate::projectiles::*;
+use crate::resources::*;
+use crate::snapshot::*;
+use crate::states::*;
+
+/// Collider outlines, counters and a cheat console, only built with the `dev` feature
+pub struct DebugPlugin;
+
+impl Plugin for DebugPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+            app.add_plugins(FrameTimeDiagnosticsPlugin);
+        }
+
+        app.init_resource::<DebugOverlay>()
+            .init_resource::<DebugConsole>()
+            .init_resource::<GodMode>()
+            .init_resource::<FixedTicks>()
+            .add_systems(Startup, setup_debug_ui)
+            .add_systems(PreUpdate, read_console_input.after(InputSystem))
+            .add_systems(FixedUpdate, count_fixed_ticks)
+            .add_systems(
+                Update,
+                (
+                    run_console_commands,
+                    apply_god_mode,
+                    draw_colliders,
+                    update_debug_text,
+                    update_console_text
+                ).chain()
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        require::<GameConfig, ConfigPlugin>(app);
+        require::<DifficultyScaling, DifficultyPlugin>(app);
+        require::<ScrollPosition, BackgroundPlugin>(app);
+        require::<TerrainSpeed, BackgroundPlugin>(app);
+        require::<Players, PlayerPlugin>(app);
+    }
+}
+
+/// Whether the debug overlay is drawn, toggled with F3
+#[derive(Resource, Debug, Default)]
+pub struct DebugOverlay {
+    pub visible: bool
+}
+
+/// Console toggled with the backquote key, typing there does not reach the game
+#[derive(Resource, Debug, Default)]
+pub struct DebugConsole {
+    pub open: bool,
+    pub input: String,
+    /// Commands entered since the last frame, run once the frame's update starts
+    pub submitted: Vec<String>,
+    pub log: Vec<String>
+}
+
+impl DebugConsole {
+    pub fn print(&mut self, line: impl Into<String>) {
+        self.log.push(line.into());
+        if self.log.len() > DEBUG_CONSOLE_LINES {
+            self.log.remove(0);
+        }
+    }
+}
+
+/// The space ship ignores every hit while this is on
+#[derive(Resource, Debug, Default)]
+pub struct GodMode(pub bool);
+
+/// Fixed ticks run during the last frame
+#[derive(Resource, Debug, Default)]
+struct FixedTicks {
+    counting: u32,
+    last_frame: u32
+}
+
+#[derive(Component)]
+struct DebugText;
+
+#[derive(Component)]
+struct ConsoleText;
+
+/// Everything the console understands
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConsoleCommand {
+    Help,
+    God,
+    /// A foe entering from the top of the screen, `x` pixels from its middle
+    Spawn { kind: FoeKind, x: f32 },
+    Wave(u32),
+    Give(Pickup),
+    TimeScale(f32),
+    KillAll,
+    /// Writes a snapshot of the run to a file
+    Save(PathBuf),
+    /// Puts the run back in the state of a snapshot file
+    Load(PathBuf)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pickup {
+    Bomb,
+    Life
+}
+
+impl FromStr for ConsoleCommand {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+
+        match words.as_slice() {
+            ["help"] => Ok(ConsoleCommand::Help),
+            ["god"] => Ok(ConsoleCommand::God),
+            ["spawn", kind, x] => {
+                let kind = match kind.to_lowercase().as_str() {
+                    "fighter" => FoeKind::Fighter,
+                    "heavy" => FoeKind::Heavy,
+                    "mini_boss" | "miniboss" => FoeKind::MiniBoss,
+                    "boss" => FoeKind::Boss,
+                    "bunker" => FoeKind::Bunker,
+                    "turret" => FoeKind::Turret,
+                    _ => return Err(format!("unknown foe '{}', expected fighter, heavy, mini_boss, boss, bunker or turret", kind))
+                };
+                let x: f32 = x.parse().map_err(|_| format!("'{}' is not a position", x))?;
+                let half_width = WINDOW_WIDTH * 0.5;
+                if !(-half_width..=half_width).contains(&x) {
+                    return Err(format!("x must be between {} and {}", -half_width, half_width));
+                }
+                Ok(ConsoleCommand::Spawn { kind, x })
+            },
+            ["wave", wave] => match wave.parse() {
+                Ok(wave) if wave >= 1 => Ok(ConsoleCommand::Wave(wave)),
+                _ => Err(format!("'{}' is not a wave, waves start at 1", wave))
+            },
+            ["give", "bomb"] => Ok(ConsoleCommand::Give(Pickup::Bomb)),
+            ["give", "life"] => Ok(ConsoleCommand::Give(Pickup::Life)),
+            ["timescale", scale] => match scale.parse::<f32>() {
+                Ok(scale) if (0.05..=10.0).contains(&scale) => Ok(ConsoleCommand::TimeScale(scale)),
+                _ => Err(format!("'{}' is not a time scale between 0.05 and 10", scale))
+            },
+            ["kill_all"] => Ok(ConsoleCommand::KillAll),
+            ["save", path] => Ok(ConsoleCommand::Save(PathBuf::from(path))),
+            ["load", path] => Ok(ConsoleCommand::Load(PathBuf::from(path))),
+            [] => Err("type help to list the commands".to_string()),
+            _ => Err(format!("unknown command '{}', type help to list the commands", line.trim()))
+        }
+    }
+}
+
+/// Everything needed to skip to another wave of the run being played
+#[derive(SystemParam)]
+struct WaveSkip<'w> {
+    wave: Option<ResMut<'w, Wave>>,
+    progress: ResMut<'w, LevelProgress>,
+    spawn_queue: ResMut<'w, FoeSpawnQueue>,
+    endless_run: Option<ResMut<'w, EndlessRun>>,
+    run_start: Res<'w, RunStart>,
+    levels: Res<'w, Levels>,
+    current_level: Res<'w, CurrentLevel>
+}
+
+impl WaveSkip<'_> {
+    fn skip_to(&mut self, target: u32, config: &GameConfig) -> Result<(), String> {
+        let Some(wave) = self.wave.as_mut() else { return Err("no run going on".to_string()) };
+
+        match self.run_start.0.mode {
+            GameMode::Campaign => {
+                let level = self.levels.get(self.current_level.0).ok_or("no level loaded")?;
+                let (index, marker) = level.timeline.iter()
+                    .enumerate()
+                    .filter(|(_, marker)| matches!(marker.event, TimelineEvent::Wave { .. }))
+                    .nth(target as usize - 1)
+                    .ok_or_else(|| format!("this level has no wave {}", target))?;
+
+                // The timeline fires the wave marker on the next tick
+                self.progress.distance = marker.distance;
+                self.progress.next_marker = index;
+                self.spawn_queue.0.clear();
+            },
+            GameMode::Endless => {
+                let endless_run = self.endless_run.as_mut().ok_or("no endless run going on")?;
+                endless_run.time = (target - 1) as f32 * config.endless.wave_time;
+            }
+        }
+
+        wave.0 = target - 1;
+        Ok(())
+    }
+}
+
+fn debug_text(style: TextStyle, layout: Style, background: Color, z_index: i32) -> TextBundle {
+    let mut text = TextBundle::from_section("", style)
+        .with_style(layout)
+        .with_background_color(background);
+    text.z_index = ZIndex::Global(z_index);
+    text.visibility = Visibility::Hidden;
+    text
+}
+
+fn setup_debug_ui(mut commands: Commands) {
+    let text_style = TextStyle {
+        font_size: DEBUG_FONT_SIZE,
+        color: Color::YELLOW,
+        ..default()
+    };
+
+    commands.spawn((
+        debug_text(
+            text_style.clone(),
+            Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(40.0),
+                left: Val::Px(8.0),
+                ..default()
+            },
+            Color::rgba(0.0, 0.0, 0.0, 0.6),
+            100
+        ),
+        DebugText,
+        Persistent
+    ));
+
+    commands.spawn((
+        debug_text(
+            TextStyle { color: Color::WHITE, ..text_style },
+            Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(0.0),
+                left: Val::Px(0.0),
+                width: Val::Percent(100.0),
+                padding: UiRect::all(Val::Px(6.0)),
+                ..default()
+            },
+            Color::rgba(0.0, 0.0, 0.0, 0.8),
+            101
+        ),
+        ConsoleText,
+        Persistent
+    ));
+}
+
+fn read_console_input(
+    mut overlay: ResMut<DebugOverlay>,
+    mut console: ResMut<DebugConsole>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut characters: EventReader<ReceivedCharacter>
+) {
+    if keyboard_input.just_pressed(KeyCode::F3) {
+        overlay.visible = !overlay.visible;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Grave) {
+        console.open = !console.open;
+        characters.clear();
+        keyboard_input.reset_all();
+        return;
+    }
+
+    if !console.open {
+        characters.clear();
+        return;
+    }
+
+    for character in characters.read() {
+        if !character.char.is_control() && character.char != '`' {
+            console.input.push(character.char);
+        }
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        console.input.pop();
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Return) {
+        let line = std::mem::take(&mut console.input);
+        console.submitted.push(line);
+    }
+
+    // Keys typed into the console must not fly the ship or open menus
+    keyboard_input.reset_all();
+}
+
+fn count_fixed_ticks(mut fixed_ticks: ResMut<FixedTicks>) {
+    fixed_ticks.counting += 1;
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_console_commands(
+    mut commands: Commands,
+    mut console: ResMut<DebugConsole>,
+    mut god_mode: ResMut<GodMode>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut foes: Query<&mut Health, With<Foe>>,
+    mut players: ResMut<Players>,
+    mut wave_skip: WaveSkip,
+    game_state: Res<State<GameState>>,
+    scroll_position: Res<ScrollPosition>,
+    terrain_speed: Res<TerrainSpeed>,
+    sprite_catalog: Res<SpriteCatalog>,
+    config: Res<GameConfig>,
+    scaling: Res<DifficultyScaling>
+) {
+    for line in std::mem::take(&mut console.submitted) {
+        console.print(format!("> {}", line));
+
+        let command = match line.parse::<ConsoleCommand>() {
+            Ok(command) => command,
+            Err(error) => {
+                console.print(error);
+                continue;
+            }
+        };
+
+        // Everything but the time scale and god mode needs a run going on
+        let needs_run = !matches!(command, ConsoleCommand::Help | ConsoleCommand::God | ConsoleCommand::TimeScale(_));
+        if needs_run && *game_state.get() != GameState::Playing {
+            console.print("only available while playing");
+            continue;
+        }
+
+        match command {
+            ConsoleCommand::Help => {
+                console.print("god | spawn <fighter|heavy|mini_boss|boss|bunker|turret> <x> | wave <n>");
+                console.print("give <bomb|life> | timescale <scale> | kill_all");
+                console.print("save <path> | load <path>");
+            },
+            ConsoleCommand::God => {
+                god_mode.0 = !god_mode.0;
+                console.print(format!("god mode {}", if god_mode.0 { "on" } else { "off" }));
+            },
+            ConsoleCommand::Spawn { kind, x } => {
+                let position = Vec2::new(x, scroll_position.0 + WINDOW_HEIGHT * 0.5 + 50.0);
+                if kind.grounded() {
+                    spawn_ground_foe(&mut commands, &sprite_catalog, &config, &scaling, kind, position, terrain_speed.0);
+                } else {
+                    spawn_foe(&mut commands, &sprite_catalog, &config, &scaling, kind, position);
+                }
+                console.print(format!("spawned a {} at {}", kind.name(), x));
+            },
+            ConsoleCommand::Wave(target) => match wave_skip.skip_to(target, &config) {
+                Ok(()) => console.print(format!("skipping to wave {}", target)),
+                Err(error) => console.print(error)
+            },
+            ConsoleCommand::Give(pickup) => {
+                // Players out of lives stay out
+                for (index, player) in players.0.iter_mut().enumerate().filter(|(_, player)| player.lives > 0) {
+                    match pickup {
+                        Pickup::Bomb => {
+                            player.bombs += 1;
+                            console.print(format!("{}P: {} bombs", index + 1, player.bombs));
+                        },
+                        Pickup::Life => {
+                            player.lives += 1;
+                            console.print(format!("{}P: {} lives", index + 1, player.lives));
+                        }
+                    }
+                }
+            },
+            ConsoleCommand::TimeScale(scale) => {
+                virtual_time.set_relative_speed(scale);
+                console.print(format!("time scale {}", scale));
+            },
+            ConsoleCommand::KillAll => {
+                // Foes die on the next tick like any other, so kills and score are counted as usual
+                let mut count = 0;
+                for mut health in &mut foes {
+                    health.0 = 0;
+                    count += 1;
+                }
+                console.print(format!("killed {} foes", count));
+            },
+            // Snapshots need the whole world, so they are taken once the frame's systems are done
+            ConsoleCommand::Save(path) => commands.add(move |world: &mut World| {
+                let result = GameSnapshot::capture(world).save(&path);
+                world.resource_mut::<DebugConsole>().print(match result {
+                    Ok(()) => format!("saved to {}", path.display()),
+                    Err(error) => format!("could not save to {}: {}", path.display(), error)
+                });
+            }),
+            ConsoleCommand::Load(path) => commands.add(move |world: &mut World| {
+                let result = GameSnapshot::load(&path).map(|snapshot| snapshot.restore(world));
+                world.resource_mut::<DebugConsole>().print(match result {
+                    Ok(()) => format!("loaded {}", path.display()),
+                    Err(error) => format!("could not load {}: {}", path.display(), error)
+                });
+            })
+        }
+    }
+}
+
+fn apply_god_mode(
+    mut commands: Commands,
+    god_mode: Res<GodMode>,
+    mortal_ships: Query<Entity, (With<SpaceShip>, Without<Invulnerable>)>,
+    invulnerable_ships: Query<Entity, (With<SpaceShip>, With<Invulnerable>)>
+) {
+    // Runs every frame so a respawned ship is covered too
+    if god_mode.0 {
+        for ship in &mortal_ships {
+            commands.entity(ship).insert(Invulnerable);
+        }
+    } else if god_mode.is_changed() {
+        for ship in &invulnerable_ships {
+            commands.entity(ship).remove::<Invulnerable>();
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn draw_colliders(
+    mut gizmos: Gizmos,
+    overlay: Res<DebugOverlay>,
+    colliders: Query<(
+        &GlobalTransform,
+        &Hitbox,
+        Option<&Graze>,
+        Has<SpaceShip>,
+        Has<Foe>,
+        Has<Bullet>,
+        Has<FoeProjectile>,
+        Has<PooledProjectile>
+    )>
+) {
+    if !overlay.visible { return }
+
+    for (transform, hitbox, graze, ship, foe, bullet, foe_projectile, pooled) in &colliders {
+        // Projectiles waiting in the pool for reuse
+        if pooled && !bullet && !foe_projectile { continue }
+
+        let color = if ship {
+            Color::CYAN
+        } else if foe {
+            Color::RED
+        } else if bullet {
+            Color::GREEN
+        } else if foe_projectile {
+            Color::ORANGE
+        } else {
+            Color::WHITE
+        };
+
+        gizmos.rect_2d(transform.translation().truncate(), 0.0, hitbox.0, color);
+        if let Some(graze) = graze {
+            gizmos.circle_2d(transform.translation().truncate(), graze.0, Color::YELLOW);
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_debug_text(
+    mut fixed_ticks: ResMut<FixedTicks>,
+    mut text_query: Query<(&mut Text, &mut Visibility), With<DebugText>>,
+    overlay: Res<DebugOverlay>,
+    diagnostics: Res<DiagnosticsStore>,
+    fixed_time: Res<Time<Fixed>>,
+    virtual_time: Res<Time<Virtual>>,
+    game_state: Res<State<GameState>>,
+    god_mode: Res<GodMode>,
+    entities: Query<Entity>,
+    foes: Query<(), With<Foe>>,
+    bullets: Query<(), With<Bullet>>,
+    foe_projectiles: Query<(), With<FoeProjectile>>
+) {
+    fixed_ticks.last_frame = std::mem::take(&mut fixed_ticks.counting);
+
+    let Ok((mut text, mut visibility)) = text_query.get_single_mut() else { return };
+    *visibility = if overlay.visible { Visibility::Inherited } else { Visibility::Hidden };
+    if !overlay.visible { return }
+
+    let fps = diagnostics.get(FrameTimeDiagnosticsPlugin::FPS).and_then(|fps| fps.smoothed()).unwrap_or(0.0);
+    let timestep = fixed_time.timestep().as_secs_f32();
+
+    text.sections[0].value = format!(
+        "FPS {:.0}\nFixed {:.0} Hz, {} ticks last frame, overstep {:.1} ms\nTime scale {}\nState {:?}{}\nEntities {}\nFoe {}  Bullet {}  FoeProjectile {}",
+        fps,
+        1.0 / timestep,
+        fixed_ticks.last_frame,
+        fixed_time.overstep().as_secs_f32() * 1000.0,
+        virtual_time.relative_speed(),
+        game_state.get(),
+        if god_mode.0 { "  GOD" } else { "" },
+        entities.iter().count(),
+        foes.iter().count(),
+        bullets.iter().count(),
+        foe_projectiles.iter().count()
+    );
+}
+
+fn update_console_text(
+    mut text_query: Query<(&mut Text, &mut Visibility), With<ConsoleText>>,
+    console: Res<DebugConsole>
+) {
+    if !console.is_changed() { return }
+
+    let Ok((mut text, mut visibility)) = text_query.get_single_mut() else { return };
+    *visibility = if console.open { Visibility::Inherited } else { Visibility::Hidden };
+
+    let mut lines = console.log.join("\n");
+    if !lines.is_empty() {
+        lines.push('\n');
+    }
+    text.sections[0].value = format!("{}> {}_", lines, console.input);
+}
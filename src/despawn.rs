@@ -0,0 +1,44 @@
+use bevy::prelude::*;
+
+use crate::components::*;
+use crate::events::*;
+use crate::projectiles::*;
+use crate::resources::*;
+
+/// Removes the entities destroyed during a fixed tick once everything else in the tick ran
+pub struct DespawnPlugin;
+
+impl Plugin for DespawnPlugin {
+    fn build(&self, app: &mut App) {
+        app.configure_sets(FixedUpdate, DespawnSet.after(HitSet))
+            .add_systems(
+                FixedUpdate,
+                // Marks made with commands earlier in the tick are applied first
+                (apply_deferred, despawn_dead).chain().in_set(DespawnSet)
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        require::<ProjectilePool, ProjectilePlugin>(app);
+    }
+}
+
+/// Ends every fixed tick, systems marking entities `Dead` run before it
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DespawnSet;
+
+/// Pooled projectiles go back to the pool, anything else is despawned exactly once
+fn despawn_dead(
+    mut commands: Commands,
+    mut projectile_pool: ResMut<ProjectilePool>,
+    dead: Query<(Entity, Has<PooledProjectile>), With<Dead>>
+) {
+    for (entity, pooled) in &dead {
+        if pooled {
+            commands.entity(entity).remove::<Dead>();
+            projectile_pool.release(&mut commands, entity);
+        } else {
+            commands.entity(entity).despawn();
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::config::*;
+use crate::constants::*;
+use crate::events::*;
+use crate::replay::*;
+use crate::resources::*;
+use crate::states::*;
+
+/// Keeps `DifficultyScaling` in line with the chosen difficulty and, when enabled, the rank
+pub struct DifficultyPlugin;
+
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Difficulty>()
+            .init_resource::<Rank>()
+            .init_resource::<DifficultyScaling>()
+            .add_event::<FoeKilledEvent>()
+            .add_event::<PlayerHitEvent>()
+            .add_systems(OnEnter(GameState::Playing), reset_rank.after(RunSetupSet))
+            .add_systems(
+                FixedUpdate,
+                (
+                    update_scaling.in_set(DifficultySet).after(PlayerInputSet),
+                    // Hits count in the tick they happen, so no event is left over between two ticks
+                    update_rank.after(HitSet)
+                ).run_if(in_state(GameState::Playing))
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        require::<GameConfig, ConfigPlugin>(app);
+        require::<RunStart, ReplayPlugin>(app);
+    }
+}
+
+/// Systems spawning foes or their projectiles run after this set
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DifficultySet;
+
+/// Multipliers applied to the foe balancing values, 1.0 leaves a value as the config sets it
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DifficultyScaling {
+    pub health: f32,
+    pub projectile_speed: f32,
+    pub fire_rate: f32,
+    /// More foes per wave, over the same stretch of the level
+    pub spawn_density: f32
+}
+
+impl Default for DifficultyScaling {
+    fn default() -> Self {
+        Self { health: 1.0, projectile_speed: 1.0, fire_rate: 1.0, spawn_density: 1.0 }
+    }
+}
+
+impl DifficultyScaling {
+    /// Every multiplier times `intensity`
+    pub fn scaled(&self, intensity: f32) -> Self {
+        Self {
+            health: self.health * intensity,
+            projectile_speed: self.projectile_speed * intensity,
+            fire_rate: self.fire_rate * intensity,
+            spawn_density: self.spawn_density * intensity
+        }
+    }
+
+    pub fn foe_health(&self, health: i32) -> i32 {
+        ((health as f32 * self.health).round() as i32).max(1)
+    }
+
+    pub fn wave_foes(&self, foes: u32) -> u32 {
+        ((foes as f32 * self.spawn_density).round() as u32).max(1)
+    }
+}
+
+/// Performance of the player during the run, from `RANK_MIN` when struggling to `RANK_MAX` when doing well
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Rank {
+    pub enabled: bool,
+    pub value: f32
+}
+
+impl Rank {
+    /// Factor applied on top of the difficulty preset, always 1.0 while the rank is disabled
+    pub fn intensity(&self, config: &RankConfig) -> f32 {
+        if !self.enabled { return 1.0 }
+
+        1.0 + self.value * config.intensity
+    }
+}
+
+fn reset_rank(mut rank: ResMut<Rank>, run_start: Res<RunStart>) {
+    *rank = Rank { enabled: run_start.0.rank, value: 0.0 };
+}
+
+fn update_rank(
+    mut rank: ResMut<Rank>,
+    mut foe_killed_events: EventReader<FoeKilledEvent>,
+    mut player_hit_events: EventReader<PlayerHitEvent>,
+    config: Res<GameConfig>,
+    time: Res<Time>
+) {
+    let kills = foe_killed_events.read().count() as f32;
+    let damage: i32 = player_hit_events.read().map(|event| event.damage).sum();
+
+    if !rank.enabled { return }
+
+    // Staying alive and killing raise the rank, taking damage lowers it
+    rank.value += config.rank.time_gain * time.delta_seconds()
+        + config.rank.kill_gain * kills
+        - config.rank.damage_loss * damage as f32;
+    rank.value = rank.value.clamp(RANK_MIN, RANK_MAX);
+}
+
+fn update_scaling(
+    mut scaling: ResMut<DifficultyScaling>,
+    difficulty: Res<Difficulty>,
+    rank: Res<Rank>,
+    config: Res<GameConfig>
+) {
+    let new_scaling = config.scaling(*difficulty).scaled(rank.intensity(&config.rank));
+    if *scaling != new_scaling {
+        *scaling = new_scaling;
+    }
+}
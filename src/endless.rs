@@ -0,0 +1,173 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::background::*;
+use crate::components::*;
+use crate::config::*;
+use crate::constants::*;
+use crate::difficulty::*;
+use crate::events::*;
+use crate::foe::*;
+use crate::level::*;
+use crate::player::*;
+use crate::replay::*;
+use crate::resources::*;
+use crate::sound::*;
+use crate::states::*;
+
+/// Spawns foes in endless mode, ever faster and tougher, with a mini-boss closing every wave
+pub struct EndlessPlugin;
+
+impl Plugin for EndlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EndlessRun>()
+            .add_event::<WaveStartEvent>()
+            .add_event::<WarningEvent>()
+            .add_event::<MilestoneEvent>()
+            .add_event::<PlayMusicEvent>()
+            .add_systems(
+                OnEnter(GameState::Playing),
+                start_endless
+                    .after(RunSetupSet)
+                    .run_if(in_mode(GameMode::Endless))
+            )
+            .add_systems(
+                FixedUpdate,
+                (spawn_endless_foes, award_milestones)
+                    .in_set(TimelineSet)
+                    .after(ScrollSet)
+                    .after(DifficultySet)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(in_mode(GameMode::Endless))
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        require::<GameConfig, ConfigPlugin>(app);
+        require::<RunStart, ReplayPlugin>(app);
+        require::<DifficultyScaling, DifficultyPlugin>(app);
+        require::<ScrollPosition, BackgroundPlugin>(app);
+        require::<Levels, LevelPlugin>(app);
+        require::<Players, PlayerPlugin>(app);
+    }
+}
+
+/// One point of the endless spawn curve
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct SpawnCurvePoint {
+    /// Seconds into the run
+    pub time: f32,
+    /// Seconds between two foes
+    pub interval: f32,
+    /// Chance of each foe being a heavy one, between 0.0 and 1.0
+    pub heavy_share: f32
+}
+
+/// Progress of the endless run being played
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EndlessRun {
+    pub time: f32,
+    /// Time left before the next foe, counted down faster on denser difficulties
+    pub spawn_timer: f32,
+    pub milestones: u32
+}
+
+fn start_endless(
+    mut endless_run: ResMut<EndlessRun>,
+    mut music_events: EventWriter<PlayMusicEvent>,
+    run_start: Res<RunStart>,
+    levels: Res<Levels>
+) {
+    *endless_run = EndlessRun::default();
+
+    // The level timeline does not run, so its music is started here
+    let music = levels.get(run_start.0.level).and_then(|level| {
+        level.timeline.iter().find_map(|marker| match marker.event {
+            TimelineEvent::Music(track) => Some(track),
+            _ => None
+        })
+    });
+    music_events.send(PlayMusicEvent(music));
+}
+
+#[allow(clippy::too_many_arguments)]
+fn spawn_endless_foes(
+    mut commands: Commands,
+    mut endless_run: ResMut<EndlessRun>,
+    mut rng: ResMut<GameRng>,
+    mut wave: ResMut<Wave>,
+    mut wave_start_events: EventWriter<WaveStartEvent>,
+    mut warning_events: EventWriter<WarningEvent>,
+    scroll_position: Res<ScrollPosition>,
+    sprite_catalog: Res<SpriteCatalog>,
+    config: Res<GameConfig>,
+    scaling: Res<DifficultyScaling>,
+    time: Res<Time>
+) {
+    endless_run.time += time.delta_seconds();
+    let top = scroll_position.0 + WINDOW_HEIGHT * 0.5 + 50.0;
+
+    // Every wave after the first one is announced by a mini-boss
+    let reached_wave = (endless_run.time / config.endless.wave_time) as u32 + 1;
+    if reached_wave > wave.0 {
+        if wave.0 > 0 {
+            let position = Vec2::new(0.0, top + MINI_BOSS_HITBOX.y);
+            spawn_foe(&mut commands, &sprite_catalog, &config, &scaling, FoeKind::MiniBoss, position);
+            warning_events.send(WarningEvent { text: "WARNING: mini-boss incoming".to_string() });
+        }
+
+        wave.0 = reached_wave;
+        wave_start_events.send(WaveStartEvent { wave: wave.0 });
+    }
+
+    let point = config.endless.sample(endless_run.time);
+    endless_run.spawn_timer -= time.delta_seconds() * scaling.spawn_density;
+
+    while endless_run.spawn_timer <= 0.0 {
+        endless_run.spawn_timer += point.interval;
+
+        let kind = if rng.0.gen::<f32>() < point.heavy_share { FoeKind::Heavy } else { FoeKind::Fighter };
+        let random_unit = rng.0.gen_range(-15..=15) as f32;
+        let position = Vec2::new(FOE_UNIT_WIDTH * random_unit, top);
+
+        spawn_foe(&mut commands, &sprite_catalog, &config, &scaling, kind, position);
+    }
+}
+
+fn award_milestones(
+    mut endless_run: ResMut<EndlessRun>,
+    mut players: ResMut<Players>,
+    mut milestone_events: EventWriter<MilestoneEvent>,
+    mut warning_events: EventWriter<WarningEvent>,
+    score: Res<Score>,
+    config: Res<GameConfig>
+) {
+    let reached = score.0 / config.endless.milestone_score;
+
+    while endless_run.milestones < reached {
+        endless_run.milestones += 1;
+
+        let reward = if endless_run.milestones.is_multiple_of(config.endless.life_milestones) {
+            MilestoneReward::Life
+        } else {
+            MilestoneReward::Bomb
+        };
+
+        // Every player still in the game gets the reward
+        for player in players.in_game_mut() {
+            match reward {
+                MilestoneReward::Bomb => player.bombs += 1,
+                MilestoneReward::Life => player.lives += 1
+            }
+        }
+
+        milestone_events.send(MilestoneEvent { milestone: endless_run.milestones, reward });
+        warning_events.send(WarningEvent {
+            text: match reward {
+                MilestoneReward::Bomb => "MILESTONE: extra bomb".to_string(),
+                MilestoneReward::Life => "MILESTONE: extra life".to_string()
+            }
+        });
+    }
+}
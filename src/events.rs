@@ -0,0 +1,149 @@
+use bevy::ecs::entity::Entity;
+use bevy::ecs::event::Event;
+use bevy::ecs::schedule::SystemSet;
+use bevy::math::Vec3;
+
+use crate::components::{Damage, FoeKind};
+use crate::formation::SquadronData;
+
+/// Systems sending hit and kill events during a fixed tick, those reacting to them in the same tick run after it
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HitSet;
+
+/// Hits on foes, first in `HitSet` so foes destroyed by them are `Dead` before ships check for rams
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FoeHitSet;
+
+/// Hits sent as `DamageEvent` come off the health of their targets, those on foes before ships check for rams
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DamageSet {
+    Foes,
+    Ships
+}
+
+/// What dealt a hit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DamageSource {
+    Projectile,
+    Ram,
+    Bomb
+}
+
+/// A hit on a ship or foe, `DamageSet` runs it through its shield then its armor before taking the rest off its health
+#[derive(Event, Debug, Clone, Copy)]
+pub struct DamageEvent {
+    pub target: Entity,
+    pub damage: Damage,
+    pub source: DamageSource,
+    pub position: Vec3
+}
+
+/// A player bullet hit a foe, `position` being the point of impact
+#[derive(Event, Debug, Clone, Copy)]
+pub struct FoeHitEvent {
+    pub foe: Entity,
+    pub position: Vec3,
+    pub damage: i32
+}
+
+/// A foe ran out of health
+#[derive(Event, Debug, Clone, Copy)]
+pub struct FoeKilledEvent {
+    pub foe: Entity,
+    pub kind: FoeKind,
+    pub position: Vec3
+}
+
+/// A bullet or foe projectile hit `target`, whether it was spent by the hit or went through
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ProjectileHitEvent {
+    pub projectile: Entity,
+    pub target: Entity,
+    pub position: Vec3,
+    pub spent: bool
+}
+
+/// The space ship took damage, from a projectile or by ramming a foe
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PlayerHitEvent {
+    pub ship: Entity,
+    pub position: Vec3,
+    pub damage: i32,
+    pub fatal: bool
+}
+
+/// A foe projectile passed close to the core of the space ship without hitting it, once per projectile
+#[derive(Event, Debug, Clone, Copy)]
+pub struct GrazeEvent {
+    pub ship: Entity,
+    pub projectile: Entity,
+    pub position: Vec3
+}
+
+/// The space ship fired a volley of `bullets` bullets
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PlayerShotEvent {
+    pub position: Vec3,
+    pub bullets: u32
+}
+
+/// The space ship collected a pickup
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PickupEvent {
+    pub position: Vec3
+}
+
+/// The space ship detonated a bomb
+#[derive(Event, Debug, Clone, Copy)]
+pub struct BombEvent {
+    pub position: Vec3
+}
+
+/// A new wave of foes begins
+#[derive(Event, Debug, Clone, Copy)]
+pub struct WaveStartEvent {
+    pub wave: u32
+}
+
+/// Level timeline asked for a warning banner, e.g. before the boss shows up
+#[derive(Event, Debug, Clone)]
+pub struct WarningEvent {
+    pub text: String
+}
+
+/// Level timeline reached the boss marker
+#[derive(Event, Debug, Clone, Copy)]
+pub struct BossStartEvent;
+
+/// Level timeline reached a squadron marker
+#[derive(Event, Debug, Clone)]
+pub struct SquadronStartEvent(pub SquadronData);
+
+/// Level timeline reached a ground foe marker
+#[derive(Event, Debug, Clone, Copy)]
+pub struct GroundStartEvent {
+    pub kind: FoeKind,
+    pub x: f32
+}
+
+/// Every member of a squadron was destroyed, the last one by `player`
+#[derive(Event, Debug, Clone, Copy)]
+pub struct SquadronDestroyedEvent {
+    pub player: usize,
+    pub bonus: u32,
+    pub position: Vec3
+}
+
+/// What a milestone of endless mode gives
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MilestoneReward {
+    Bomb,
+    Life
+}
+
+/// The score went past another milestone of endless mode
+#[derive(Event, Debug, Clone, Copy)]
+pub struct MilestoneEvent {
+    pub milestone: u32,
+    pub reward: MilestoneReward
+}
@@ -0,0 +1,185 @@
+use bevy::prelude::*;
+use bevy_parallax::{ParallaxCameraComponent, ParallaxSystems};
+use rand::Rng;
+
+use crate::constants::*;
+use crate::events::*;
+
+pub struct FeedbackPlugin;
+
+impl Plugin for FeedbackPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FeedbackSettings>()
+            .init_resource::<CameraShake>()
+            .init_resource::<HitStop>()
+            .add_event::<FoeHitEvent>()
+            .add_event::<FoeKilledEvent>()
+            .add_event::<PlayerHitEvent>()
+            .add_systems(
+                Update,
+                (
+                    trigger_feedback,
+                    update_damage_flash,
+                    update_hit_stop,
+                    apply_camera_shake.after(ParallaxSystems)
+                ).chain()
+            );
+    }
+}
+
+/// Player facing knobs for hit feedback, every intensity goes from 0.0 (off) upwards
+#[derive(Resource, Debug, Clone)]
+pub struct FeedbackSettings {
+    pub flash_intensity: f32,
+    pub shake_intensity: f32,
+    pub hit_stop_intensity: f32,
+    /// Accessibility toggle, turns off screen shake and flashes whatever their intensity
+    pub reduce_effects: bool
+}
+
+impl Default for FeedbackSettings {
+    fn default() -> Self {
+        Self { flash_intensity: 1.0, shake_intensity: 1.0, hit_stop_intensity: 1.0, reduce_effects: false }
+    }
+}
+
+impl FeedbackSettings {
+    pub fn flashes_enabled(&self) -> bool {
+        !self.reduce_effects && self.flash_intensity > 0.0
+    }
+
+    pub fn shake_enabled(&self) -> bool {
+        !self.reduce_effects && self.shake_intensity > 0.0
+    }
+}
+
+/// Trauma based camera shake, the offset grows with the square of the trauma
+#[derive(Resource, Default)]
+pub struct CameraShake {
+    pub trauma: f32,
+    offset: Vec2
+}
+
+impl CameraShake {
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).min(1.0);
+    }
+}
+
+/// Freezes gameplay time for a few frames to sell heavy impacts
+#[derive(Resource, Default)]
+pub struct HitStop {
+    remaining: f32
+}
+
+impl HitStop {
+    pub fn is_active(&self) -> bool {
+        self.remaining > 0.0
+    }
+}
+
+#[derive(Component)]
+pub struct DamageFlash(pub Timer);
+
+#[allow(clippy::too_many_arguments)]
+fn trigger_feedback(
+    mut commands: Commands,
+    mut foe_hit_events: EventReader<FoeHitEvent>,
+    mut foe_killed_events: EventReader<FoeKilledEvent>,
+    mut player_hit_events: EventReader<PlayerHitEvent>,
+    mut camera_shake: ResMut<CameraShake>,
+    mut hit_stop: ResMut<HitStop>,
+    mut time: ResMut<Time<Virtual>>,
+    settings: Res<FeedbackSettings>
+) {
+    let mut flashed = Vec::new();
+    let mut trauma = 0.0;
+    let mut big_hit = false;
+
+    for hit in foe_hit_events.read() {
+        flashed.push(hit.foe);
+        trauma += FEEDBACK_TRAUMA_FOE_HIT;
+    }
+
+    let kills = foe_killed_events.read().count();
+    trauma += kills as f32 * FEEDBACK_TRAUMA_FOE_KILLED;
+    big_hit |= kills >= FEEDBACK_HIT_STOP_KILLS;
+
+    for hit in player_hit_events.read() {
+        flashed.push(hit.ship);
+        trauma += FEEDBACK_TRAUMA_PLAYER_HIT;
+        big_hit |= hit.fatal;
+    }
+
+    if settings.flashes_enabled() {
+        for entity in flashed {
+            if let Some(mut entity_commands) = commands.get_entity(entity) {
+                entity_commands.insert(DamageFlash(Timer::from_seconds(FEEDBACK_FLASH_TIME, TimerMode::Once)));
+            }
+        }
+    }
+
+    if settings.shake_enabled() {
+        camera_shake.add_trauma(trauma * settings.shake_intensity);
+    }
+
+    if big_hit && settings.hit_stop_intensity > 0.0 {
+        hit_stop.remaining = FEEDBACK_HIT_STOP_TIME * settings.hit_stop_intensity;
+        time.pause();
+    }
+}
+
+fn update_damage_flash(
+    mut commands: Commands,
+    mut flashing: Query<(Entity, &mut DamageFlash, &mut TextureAtlasSprite)>,
+    settings: Res<FeedbackSettings>,
+    time: Res<Time<Real>>
+) {
+    for (entity, mut flash, mut sprite) in &mut flashing {
+        // Sprite colors multiply the texture, so a bright tint saturates it towards white
+        let brightness = 1.0 + (FEEDBACK_FLASH_BRIGHTNESS - 1.0) * settings.flash_intensity;
+
+        if flash.0.tick(time.delta()).finished() || !settings.flashes_enabled() {
+            sprite.color = Color::WHITE;
+            commands.entity(entity).remove::<DamageFlash>();
+        } else {
+            sprite.color = Color::rgb(brightness, brightness, brightness);
+        }
+    }
+}
+
+fn update_hit_stop(
+    mut hit_stop: ResMut<HitStop>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    time: Res<Time<Real>>
+) {
+    if !hit_stop.is_active() { return }
+
+    hit_stop.remaining -= time.delta_seconds();
+
+    if !hit_stop.is_active() {
+        virtual_time.unpause();
+    }
+}
+
+fn apply_camera_shake(
+    mut camera_shake: ResMut<CameraShake>,
+    mut camera_query: Query<&mut Transform, With<ParallaxCameraComponent>>,
+    settings: Res<FeedbackSettings>,
+    time: Res<Time<Real>>
+) {
+    let Ok(mut camera_transform) = camera_query.get_single_mut() else { return };
+
+    camera_shake.trauma = (camera_shake.trauma - FEEDBACK_SHAKE_DECAY * time.delta_seconds()).max(0.0);
+    if !settings.shake_enabled() {
+        camera_shake.trauma = 0.0;
+    }
+
+    // Undo last frame offset so the shake never drifts the camera away from the parallax scroll
+    let shake = camera_shake.trauma * camera_shake.trauma;
+    let mut rng = rand::thread_rng();
+    let offset = Vec2::new(rng.gen_range(-1.0..=1.0), rng.gen_range(-1.0..=1.0)) * shake * FEEDBACK_SHAKE_MAX_OFFSET;
+
+    camera_transform.translation += (offset - camera_shake.offset).extend(0.0);
+    camera_shake.offset = offset;
+}
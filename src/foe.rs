@@ -2,9 +2,20 @@ use bevy::sprite::collide_aabb::collide;
 use bevy::prelude::*;
 use rand::Rng;
 
+use crate::background::*;
 use crate::components::*;
-use crate::resources::*;
+use crate::config::*;
 use crate::constants::*;
+use crate::damage::*;
+use crate::despawn::*;
+use crate::difficulty::*;
+use crate::events::*;
+use crate::formation::*;
+use crate::level::*;
+use crate::player::*;
+use crate::projectiles::*;
+use crate::resources::*;
+use crate::sprites::*;
 use crate::states::*;
 use crate::utils::*;
 
@@ -12,159 +23,320 @@ pub struct FoePlugin;
 
 impl Plugin for FoePlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::Playing), setup_foes)  
+        app.add_event::<FoeHitEvent>()
+            .add_event::<FoeKilledEvent>()
+            .add_event::<BossStartEvent>()
+            .add_event::<ProjectileHitEvent>()
+            .add_event::<BombEvent>()
+            .init_resource::<FoeSpawnQueue>()
             .add_systems(
                 FixedUpdate, 
                 (
-                    spawn_foes,
-                    update_foes,                    
-                    check_if_foe_hit,
-                    foes_shooting,
-                    update_foe_projectiles
-                ).run_if(in_state(GameState::Playing))
+                    (spawn_foes, spawn_boss, update_foes, foes_shooting).chain().before(HitSet),
+                    // Foes destroyed by a hit are marked `Dead` before the rest of `HitSet` runs
+                    (
+                        check_if_foe_hit.before(DamageSet::Foes),
+                        (destroy_foes, apply_deferred).chain().after(DamageSet::Foes)
+                    ).in_set(HitSet).in_set(FoeHitSet)
+                ).after(TimelineSet).after(DifficultySet).before(DespawnSet).run_if(in_state(GameState::Playing))
             );
     }
+
+    fn finish(&self, app: &mut App) {
+        require::<GameConfig, ConfigPlugin>(app);
+        require::<DifficultyScaling, DifficultyPlugin>(app);
+        require::<ScrollPosition, BackgroundPlugin>(app);
+        require::<ScrollSpeed, BackgroundPlugin>(app);
+        require::<LevelProgress, LevelPlugin>(app);
+        require::<Players, PlayerPlugin>(app);
+        require::<HiScore, PlayerPlugin>(app);
+        require::<ProjectilePool, ProjectilePlugin>(app);
+        require::<Events<DamageEvent>, DamagePlugin>(app);
+    }
 }
 
-fn setup_foes(mut commands: Commands) {
-    // Insert enemy spawning delay, so they don't spawn too quickly
-    commands.insert_resource(EnemySpawnDelay(Timer::from_seconds(FOE_SPAWN_DELAY, TimerMode::Repeating)));
+/// Spawns a foe of the given kind with its balancing values scaled by the difficulty
+pub fn spawn_foe(
+    commands: &mut Commands,
+    sprite_catalog: &SpriteCatalog,
+    config: &GameConfig,
+    scaling: &DifficultyScaling,
+    kind: FoeKind,
+    position: Vec2
+) -> Entity {
+    let (speed, health, shoot_delay) = match kind {
+        FoeKind::Fighter => (config.foe.speed, config.foe.health, Some(config.foe.shoot_delay)),
+        FoeKind::Heavy => (config.heavy.speed, config.heavy.health, Some(config.heavy.shoot_delay)),
+        FoeKind::MiniBoss => (config.mini_boss.speed, config.mini_boss.health, Some(config.mini_boss.shoot_delay)),
+        FoeKind::Boss => (config.boss.speed, config.boss.health, Some(config.boss.shoot_delay)),
+        // Ground foes move with the terrain alone, bunkers never fire
+        FoeKind::Bunker => (0.0, config.bunker.health, None),
+        FoeKind::Turret => (0.0, config.turret.health, Some(config.turret.shoot_delay))
+    };
+
+    let (scale, hitbox, color) = match kind {
+        FoeKind::Fighter => (FOE_SCALE, FOE_HITBOX, Color::WHITE),
+        FoeKind::Heavy => (HEAVY_SCALE, HEAVY_HITBOX, HEAVY_COLOR),
+        FoeKind::MiniBoss => (MINI_BOSS_SCALE, MINI_BOSS_HITBOX, MINI_BOSS_COLOR),
+        FoeKind::Boss => (BOSS_SCALE, BOSS_HITBOX, Color::WHITE),
+        FoeKind::Bunker => (BUNKER_SCALE, BUNKER_HITBOX, BUNKER_COLOR),
+        FoeKind::Turret => (TURRET_SCALE, TURRET_HITBOX, TURRET_COLOR)
+    };
+    let z = if kind.grounded() { GROUND_Z } else { 0.0 };
+
+    let mut sprite = sprite_catalog.sprite_sheet(
+        SpriteName::Foe,
+        Transform {
+            translation: position.extend(z),
+            scale: Vec3::splat(scale),
+            ..default()
+        }
+    );
+    sprite.sprite.color = color;
+
+    let (armor, shield) = config.defences(kind);
+    let foe = commands.spawn((
+        sprite,
+        Health(scaling.foe_health(health)),
+        armor,
+        Speed(speed),
+        Hitbox(hitbox),
+        LastHitBy::default(),
+        kind,
+        Foe
+    )).id();
+    if let Some(shoot_delay) = shoot_delay {
+        commands.entity(foe).insert(ShootingDelay(Timer::from_seconds(shoot_delay, TimerMode::Repeating)));
+    }
+    if let Some(shield) = shield {
+        commands.entity(foe).insert(shield);
+    }
+    foe
 }
 
+/// Fires a foe projectile from the projectile pool with the configured behaviours,
+/// nothing is fired once the pool is full
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_foe_projectile(
+    commands: &mut Commands,
+    projectile_pool: &mut ProjectilePool,
+    sprite_catalog: &SpriteCatalog,
+    config: &GameConfig,
+    position: Vec2,
+    direction: Vec2,
+    speed: f32,
+    damage: Damage
+) -> Option<Entity> {
+    let projectile = projectile_pool.spawn(commands, ProjectileKind::FoeProjectile, config.projectiles.foe_projectiles, (
+        sprite_catalog.sprite_sheet(
+            SpriteName::FoeProjectile,
+            Transform {
+                translation: position.extend(0.0),
+                scale: Vec3::splat(FOE_PROJECTILE_SCALE),
+                ..default()
+            }
+        ),
+        GDirection::new(direction.x, direction.y),
+        Speed(speed),
+        damage,
+        Hitbox(FOE_PROJECTILE_HITBOX),
+        FoeProjectile
+    ))?;
+
+    config.projectiles.foe_projectile_behaviours.insert(&mut commands.entity(projectile));
+    Some(projectile)
+}
+
+#[allow(clippy::too_many_arguments)]
 fn spawn_foes(
     mut commands: Commands, 
-    mut foe_timer: ResMut<EnemySpawnDelay>,
-    time: Res<Time>
+    mut spawn_queue: ResMut<FoeSpawnQueue>,
+    mut rng: ResMut<GameRng>,
+    scroll_position: Res<ScrollPosition>,
+    progress: Res<LevelProgress>,
+    sprite_catalog: Res<SpriteCatalog>,
+    config: Res<GameConfig>,
+    scaling: Res<DifficultyScaling>
 ) {
-    if foe_timer.0.tick(time.delta()).just_finished() {
-        let random_unit = rand::thread_rng().gen_range(-15..=15) as f32;
+    // Foes of a wave come in one by one as their distance scrolls by
+    let due = spawn_queue.0.iter().filter(|distance| **distance <= progress.distance).count();
+    spawn_queue.0.retain(|distance| *distance > progress.distance);
+
+    for _ in 0..due {
+        let random_unit = rng.0.gen_range(-15..=15) as f32;
         let foe_x = FOE_UNIT_WIDTH * random_unit;
-        let foe_y = (WINDOW_HEIGHT * 0.5) + 50.0;
-
-        commands.spawn((
-            SpriteBundle {
-                transform: Transform {
-                    translation: Vec3::new(foe_x, foe_y, 0.0),
-                    scale: Vec3::new(30.0, 30.0, 0.0),
-                    ..default()
-                },
-                sprite: Sprite {
-                    color: Color::RED,
-                    ..default()
-                },
-                ..default()                
-            },            
-            ShootingDelay(Timer::from_seconds(FOE_SHOOT_DELAY, TimerMode::Repeating)),
-            Health(FOE_HEALTH),
-            Speed(FOE_SPEED),
-            Foe
-        ));
+        let foe_y = scroll_position.0 + (WINDOW_HEIGHT * 0.5) + 50.0;
+
+        spawn_foe(&mut commands, &sprite_catalog, &config, &scaling, FoeKind::Fighter, Vec2::new(foe_x, foe_y));
     }
 }
 
+fn spawn_boss(
+    mut commands: Commands,
+    mut boss_events: EventReader<BossStartEvent>,
+    scroll_position: Res<ScrollPosition>,
+    sprite_catalog: Res<SpriteCatalog>,
+    config: Res<GameConfig>,
+    scaling: Res<DifficultyScaling>
+) {
+    for _ in boss_events.read() {
+        let position = Vec2::new(0.0, scroll_position.0 + WINDOW_HEIGHT * 0.5 + BOSS_HITBOX.y);
+        spawn_foe(&mut commands, &sprite_catalog, &config, &scaling, FoeKind::Boss, position);
+    }
+}
+
+#[allow(clippy::type_complexity)]
 fn update_foes(
     mut commands: Commands,
-    mut foes: Query<(Entity, &mut Transform, &Speed), With<Foe>>,
+    mut foes: Query<(Entity, &mut Transform, &Speed, &FoeKind), (With<Foe>, Without<SquadronMember>, Without<Grounded>)>,
+    scroll_position: Res<ScrollPosition>,
     time: Res<Time>
 ) {
-    for (foe_entity, mut transform, speed) in &mut foes {
+    for (foe_entity, mut transform, speed, kind) in &mut foes {
         transform.translation.y += -speed.0 * time.delta_seconds();
-        if transform.translation.y < ((-WINDOW_HEIGHT * 0.5) - 25.0) {
-            commands.entity(foe_entity).despawn();
+
+        // Bosses stay on screen until destroyed
+        if kind.hovers() {
+            transform.translation.y = transform.translation.y.max(scroll_position.0 + BOSS_HOVER_HEIGHT);
+            continue;
+        }
+
+        if transform.translation.y < scroll_position.0 - (WINDOW_HEIGHT * 0.5) - 25.0 {
+            commands.entity(foe_entity).insert(Dead);
         }
     }
 }
 
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 fn foes_shooting(
     mut commands: Commands,
-    mut foes: Query<(&Transform, &mut ShootingDelay), With<Foe>>,
-    player: Query<&Transform, With<SpaceShip>>,
+    mut projectile_pool: ResMut<ProjectilePool>,
+    mut foes: Query<(&Transform, &mut ShootingDelay, &FoeKind), (With<Foe>, Without<Grounded>)>,
+    ships: Query<(&Transform, &Health), With<SpaceShip>>,
+    sprite_catalog: Res<SpriteCatalog>,
+    config: Res<GameConfig>,
+    scaling: Res<DifficultyScaling>,
     time: Res<Time>
 ) {
-    if foes.is_empty() || player.is_empty() { return }
-
-    let player = player.single();
-    let player_x = player.translation.x;
-    let player_y = player.translation.y;
+    // Ships destroyed earlier this tick are not targeted anymore
+    let targets: Vec<Vec2> = ships.iter()
+        .filter(|(_, health)| health.0 > 0)
+        .map(|(transform, _)| transform.translation.truncate())
+        .collect();
+    if foes.is_empty() || targets.is_empty() { return }
 
-    for (f_transform, mut shoot_delay) in &mut foes {
+    for (f_transform, mut shoot_delay, kind) in &mut foes {
         let foe_x = f_transform.translation.x;
         let foe_y = f_transform.translation.y;
 
-        if shoot_delay.0.tick(time.delta()).just_finished() {            
+        let foe_position = f_transform.translation.truncate();
+        let Some(target) = targets.iter().min_by(|a, b| {
+            a.distance_squared(foe_position).total_cmp(&b.distance_squared(foe_position))
+        }) else { continue };
+
+        // A higher fire rate runs the shooting timers faster
+        if shoot_delay.0.tick(time.delta().mul_f32(scaling.fire_rate)).just_finished() {            
             let (dir_x, dir_y) = calc_bullet_direction(
                 foe_x, 
                 foe_y, 
-                player_x, 
-                player_y                
+                target.x, 
+                target.y
             );
 
-            commands.spawn((
-                SpriteBundle {
-                    transform: Transform {
-                        translation: Vec3::new(foe_x, foe_y, 0.0),
-                        scale: Vec3::new(20.0, 20.0, 0.0),
-                        ..default()
-                    },
-                    sprite: Sprite {
-                        color: Color::GREEN,
-                        ..default()
-                    },
-                    ..default()
-                },
-                GDirection::new(dir_x, dir_y),                
-                Speed(FOE_PROJECTILE_SPEED),
-                Damage(FOE_DAMAGE),
-                FoeProjectile,                
-            ));
-        }
-    }
-}
+            let (damage, projectile_speed) = config.projectile(*kind);
 
-fn update_foe_projectiles(
-    mut commands: Commands,
-    mut projectiles: Query<(Entity, &mut Transform, &GDirection, &Speed), With<FoeProjectile>>,
-    time: Res<Time>
-) {
-    for (entity, mut transform, gdirection, speed) in &mut projectiles {            
-        if transform.translation.y < (-WINDOW_HEIGHT * 0.5) 
-        || transform.translation.x < (-WINDOW_WIDTH * 0.5)
-        || transform.translation.x > (WINDOW_WIDTH * 0.5) {
-            commands.entity(entity).despawn();
+            spawn_foe_projectile(
+                &mut commands,
+                &mut projectile_pool,
+                &sprite_catalog,
+                &config,
+                f_transform.translation.truncate(),
+                Vec2::new(dir_x, dir_y),
+                projectile_speed * scaling.projectile_speed,
+                damage
+            );
         }
-
-        transform.translation.x += gdirection.x * speed.0 * time.delta_seconds();
-        transform.translation.y += gdirection.y * speed.0 * time.delta_seconds();  
     }
 }
 
+#[allow(clippy::type_complexity)]
 fn check_if_foe_hit(
     mut commands: Commands,
-    mut foes: Query<(Entity, &Transform, &mut Health), With<Foe>>,
-    bullets: Query<(Entity, &Transform, &Damage), With<Bullet>>
+    mut foes: Query<(Entity, &Transform, &Hitbox, &mut LastHitBy), LiveFoe>,
+    mut bullets: Query<(Entity, &Transform, &Hitbox, &Damage, &Player, Option<&mut Pierce>), (With<Bullet>, Without<Dead>)>,
+    mut bomb_events: EventReader<BombEvent>,
+    mut projectile_hit_events: EventWriter<ProjectileHitEvent>,
+    mut damage_events: EventWriter<DamageEvent>
 ) {
-    for (foe_entity, foe_transform, mut foe_health) in &mut foes {
-        for (bullet_entity, bullet_transform, bullet_damage) in &bullets {
-            let bullet_translation = bullet_transform.translation;
-            let bullet_scale = bullet_transform.scale.truncate();
-            let foe_translation = foe_transform.translation;
-            let foe_scale = foe_transform.scale.truncate();
+    // Bombs going off this tick already hit every foe, bullets fly through them
+    if bomb_events.read().count() > 0 { return }
+
+    // A bullet overlapping several foes only hits the first one
+    let mut spent = Vec::new();
+
+    for (foe_entity, foe_transform, foe_hitbox, mut last_hit_by) in &mut foes {
+        for (bullet_entity, bullet_transform, bullet_hitbox, bullet_damage, player, mut pierce) in &mut bullets {
+            if spent.contains(&bullet_entity) { continue }
+            if pierce.as_ref().is_some_and(|pierce| pierce.has_hit(foe_entity)) { continue }
 
             let collision = collide(
-                bullet_translation,
-                bullet_scale,
-                foe_translation,
-                foe_scale
+                bullet_transform.translation,
+                bullet_hitbox.0,
+                foe_transform.translation,
+                foe_hitbox.0
             );
 
-            if let Some(_) = collision {
-                commands.entity(bullet_entity).despawn();
-                foe_health.0 -= bullet_damage.0;
+            if collision.is_some() {
+                let bullet_spent = pierce.as_mut().is_none_or(|pierce| pierce.strike(foe_entity));
+                if bullet_spent {
+                    commands.entity(bullet_entity).insert(Dead);
+                    spent.push(bullet_entity);
+                }
+                projectile_hit_events.send(ProjectileHitEvent {
+                    projectile: bullet_entity,
+                    target: foe_entity,
+                    position: bullet_transform.translation,
+                    spent: bullet_spent
+                });
+                damage_events.send(DamageEvent {
+                    target: foe_entity,
+                    damage: *bullet_damage,
+                    source: DamageSource::Projectile,
+                    position: bullet_transform.translation
+                });
+                last_hit_by.0 = player.0;
+
+                // A foe takes one hit a tick, the other bullets fly on to the foes behind it
+                break;
             }
         }
+    }
+}
+
+/// Marks the foes destroyed by the hits of the tick `Dead`, scoring them for whoever hit them last
+fn destroy_foes(
+    mut commands: Commands,
+    foes: Query<(Entity, &Transform, &FoeKind, &Health, &LastHitBy), LiveFoe>,
+    mut foe_killed_events: EventWriter<FoeKilledEvent>,
+    mut scoring: Scoring,
+    mut scroll_speed: ResMut<ScrollSpeed>,
+    config: Res<GameConfig>
+) {
+    for (foe_entity, foe_transform, foe_kind, health, last_hit_by) in &foes {
+        if health.0 > 0 { continue }
+
+        commands.entity(foe_entity).insert(Dead);
+        foe_killed_events.send(FoeKilledEvent {
+            foe: foe_entity,
+            kind: *foe_kind,
+            position: foe_transform.translation
+        });
+
+        scoring.award(last_hit_by.0, config.score(*foe_kind));
 
-        if foe_health.0 <= 0 {
-            commands.entity(foe_entity).despawn();                
+        // The scroll stopped for the boss goes on once it is destroyed
+        if *foe_kind == FoeKind::Boss {
+            scroll_speed.resume();
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,364 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::background::*;
+use crate::components::*;
+use crate::config::*;
+use crate::constants::*;
+use crate::despawn::*;
+use crate::difficulty::*;
+use crate::events::*;
+use crate::foe::*;
+use crate::level::*;
+use crate::player::*;
+use crate::resources::*;
+use crate::states::*;
+
+/// Brings in the squadrons of the level timeline, flies them in formation along their leader path
+/// and pays out a bonus for those destroyed to the last member
+pub struct FormationPlugin;
+
+impl Plugin for FormationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SquadronStartEvent>()
+            .add_event::<SquadronDestroyedEvent>()
+            .add_systems(
+                FixedUpdate,
+                (
+                    (spawn_squadrons, fly_squadrons).chain().before(HitSet),
+                    track_squadrons.after(HitSet)
+                ).after(TimelineSet).after(DifficultySet).before(DespawnSet).run_if(in_state(GameState::Playing))
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        require::<GameConfig, ConfigPlugin>(app);
+        require::<DifficultyScaling, DifficultyPlugin>(app);
+        require::<ScrollPosition, BackgroundPlugin>(app);
+        require::<Players, PlayerPlugin>(app);
+        require::<HiScore, PlayerPlugin>(app);
+    }
+}
+
+/// Shape the members of a squadron keep around their leader
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Formation {
+    /// Leader at the tip, the others trailing behind on both sides
+    #[default]
+    V,
+    /// Side by side
+    Line,
+    /// One behind the other
+    Column,
+    /// Evenly around the leader path
+    Circle,
+    /// Rows as wide as the square root of the squadron
+    Grid
+}
+
+impl Formation {
+    /// Offset of each member from the leader path, `spacing` pixels apart, the leader first
+    pub fn offsets(&self, size: u32, spacing: f32) -> Vec<Vec2> {
+        (0..size).map(|member| {
+            let member = member as f32;
+            match self {
+                Formation::V => {
+                    let row = ((member + 1.0) * 0.5).floor();
+                    let side = if member as u32 % 2 == 1 { -1.0 } else { 1.0 };
+                    Vec2::new(side * row * spacing, row * spacing)
+                },
+                Formation::Line => Vec2::new((member - (size - 1) as f32 * 0.5) * spacing, 0.0),
+                Formation::Column => Vec2::new(0.0, member * spacing),
+                Formation::Circle => {
+                    // Wide enough for neighbours to stay `spacing` apart
+                    let radius = spacing * size as f32 / TAU;
+                    let angle = member * TAU / size as f32;
+                    Vec2::new(angle.sin(), angle.cos()) * radius
+                },
+                Formation::Grid => {
+                    let columns = (size as f32).sqrt().ceil().max(1.0);
+                    let column = member % columns;
+                    let row = (member / columns).floor();
+                    Vec2::new((column - (columns - 1.0) * 0.5) * spacing, row * spacing)
+                }
+            }
+        }).collect()
+    }
+}
+
+/// Sideways motion of the leader path on its way down
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum FormationPath {
+    #[default]
+    Straight,
+    /// Weaves `amplitude` pixels to each side, a full sway every `period` seconds
+    Weave { amplitude: f32, period: f32 },
+    /// Slides sideways at this many pixels per second, negative to the left
+    Drift(f32)
+}
+
+impl FormationPath {
+    /// Sideways offset from where the leader came in, `time` seconds after
+    pub fn x(&self, time: f32) -> f32 {
+        match self {
+            FormationPath::Straight => 0.0,
+            FormationPath::Weave { amplitude, period } => amplitude * (time * TAU / period.max(f32::EPSILON)).sin(),
+            FormationPath::Drift(speed) => speed * time
+        }
+    }
+}
+
+/// A squadron as written in the level files
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SquadronData {
+    pub formation: Formation,
+    pub size: u32,
+    pub kind: FoeKind,
+    /// Pixels between neighbouring members
+    pub spacing: f32,
+    /// Where the leader path comes in, in pixels from the middle of the screen
+    pub x: f32,
+    pub path: FormationPath,
+    /// Downward speed of the leader path
+    pub speed: f32,
+    /// Seconds in formation before members start breaking off to dive at the player, never if left out
+    pub break_off: Option<f32>,
+    /// Seconds between two members breaking off
+    pub dive_interval: f32,
+    pub dive_speed: f32,
+    /// Points for destroying every member before any gets away
+    pub bonus: u32
+}
+
+impl SquadronData {
+    /// Checks the values a level file may get wrong, the first one out of range is reported
+    pub fn validate(&self) -> Result<(), String> {
+        let check = |field: &str, value: f32, min: f32, max: f32| {
+            if (min..=max).contains(&value) {
+                Ok(())
+            } else {
+                Err(format!("{} is {}, expected between {} and {}", field, value, min, max))
+            }
+        };
+
+        check("size", self.size as f32, 1.0, MAX_SQUADRON_SIZE as f32)?;
+        check("spacing", self.spacing, 0.0, WINDOW_WIDTH)?;
+        check("x", self.x, -WINDOW_WIDTH * 0.5, WINDOW_WIDTH * 0.5)?;
+        check("speed", self.speed, 1.0, 5000.0)?;
+        check("break_off", self.break_off.unwrap_or_default(), 0.0, 600.0)?;
+        check("dive_interval", self.dive_interval, 0.01, 60.0)?;
+        check("dive_speed", self.dive_speed, 1.0, 5000.0)?;
+        match self.path {
+            FormationPath::Straight => Ok(()),
+            FormationPath::Weave { amplitude, period } => {
+                check("path.amplitude", amplitude, 0.0, WINDOW_WIDTH)?;
+                check("path.period", period, 0.1, 600.0)
+            },
+            FormationPath::Drift(speed) => check("path.drift", speed, -5000.0, 5000.0)
+        }
+    }
+}
+
+impl Default for SquadronData {
+    fn default() -> Self {
+        Self {
+            formation: Formation::V,
+            size: 5,
+            kind: FoeKind::Fighter,
+            spacing: 48.0,
+            x: 0.0,
+            path: FormationPath::Straight,
+            speed: 120.0,
+            break_off: None,
+            dive_interval: 0.5,
+            dive_speed: 320.0,
+            bonus: 1000
+        }
+    }
+}
+
+/// Leader path of a squadron in flight, its members follow it from their `SquadronMember` offset
+#[derive(Component, Debug, Clone, Serialize, Deserialize)]
+pub struct Squadron {
+    pub data: SquadronData,
+    /// Where the leader path came in
+    pub origin: Vec2,
+    /// Seconds since it came in
+    pub elapsed: f32,
+    /// Time until the next member breaks off, once break-off started
+    pub dive: Timer,
+    /// Members neither destroyed nor gone yet
+    pub alive: u32,
+    /// A member got away, the squadron does not pay its bonus anymore
+    pub escaped: bool
+}
+
+impl Squadron {
+    pub fn new(data: SquadronData, origin: Vec2) -> Self {
+        Self {
+            dive: Timer::from_seconds(data.dive_interval, TimerMode::Repeating),
+            alive: data.size,
+            escaped: false,
+            elapsed: 0.0,
+            origin,
+            data
+        }
+    }
+
+    /// Where the leader path is now
+    pub fn leader(&self) -> Vec2 {
+        self.origin + Vec2::new(self.data.path.x(self.elapsed), -self.data.speed * self.elapsed)
+    }
+
+    fn breaking_off(&self) -> bool {
+        self.data.break_off.is_some_and(|break_off| self.elapsed >= break_off)
+    }
+}
+
+/// Foe flying with a squadron, kept at `offset` from the leader path until it dives
+#[derive(Component, Debug, Clone, Copy)]
+pub struct SquadronMember {
+    pub squadron: Entity,
+    pub offset: Vec2
+}
+
+/// Squadron member that broke off the formation, flying straight at where the player was
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Diving(pub Vec2);
+
+/// Spawns a squadron with its leader path coming in at `origin`, members in front of it included
+pub fn spawn_squadron(
+    commands: &mut Commands,
+    sprite_catalog: &SpriteCatalog,
+    config: &GameConfig,
+    scaling: &DifficultyScaling,
+    data: &SquadronData,
+    origin: Vec2
+) -> Entity {
+    let squadron = commands.spawn(Squadron::new(data.clone(), origin)).id();
+
+    for offset in data.formation.offsets(data.size, data.spacing) {
+        let foe = spawn_foe(commands, sprite_catalog, config, scaling, data.kind, origin + offset);
+        commands.entity(foe).insert(SquadronMember { squadron, offset });
+    }
+    squadron
+}
+
+fn spawn_squadrons(
+    mut commands: Commands,
+    mut squadron_events: EventReader<SquadronStartEvent>,
+    scroll_position: Res<ScrollPosition>,
+    sprite_catalog: Res<SpriteCatalog>,
+    config: Res<GameConfig>,
+    scaling: Res<DifficultyScaling>
+) {
+    for SquadronStartEvent(data) in squadron_events.read() {
+        // The whole formation comes in from above the screen
+        let lowest = data.formation.offsets(data.size, data.spacing).iter()
+            .map(|offset| offset.y)
+            .fold(0.0, f32::min);
+        let origin = Vec2::new(data.x, scroll_position.0 + WINDOW_HEIGHT * 0.5 + FORMATION_MARGIN - lowest);
+
+        spawn_squadron(&mut commands, &sprite_catalog, &config, &scaling, data, origin);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn fly_squadrons(
+    mut commands: Commands,
+    mut squadrons: Query<(Entity, &mut Squadron)>,
+    mut members: Query<(Entity, &mut Transform, &SquadronMember, Option<&Diving>), With<Foe>>,
+    ships: Query<(&Transform, &Health), (With<SpaceShip>, Without<Foe>)>,
+    time: Res<Time>
+) {
+    for (_, mut squadron) in &mut squadrons {
+        squadron.elapsed += time.delta_seconds();
+    }
+
+    for (_, mut transform, member, diving) in &mut members {
+        let translation = match diving {
+            Some(diving) => transform.translation.truncate() + diving.0 * time.delta_seconds(),
+            None => match squadrons.get(member.squadron) {
+                Ok((_, squadron)) => squadron.leader() + member.offset,
+                Err(_) => continue
+            }
+        };
+        transform.translation = translation.extend(transform.translation.z);
+    }
+
+    // Runs before the hits of the tick, destroyed ships are already despawned and this only skips ships left
+    // without health by something else
+    let targets: Vec<Vec2> = ships.iter()
+        .filter(|(_, health)| health.0 > 0)
+        .map(|(transform, _)| transform.translation.truncate())
+        .collect();
+
+    for (squadron_entity, mut squadron) in &mut squadrons {
+        if !squadron.breaking_off() { continue }
+        if !squadron.dive.tick(time.delta()).just_finished() { continue }
+
+        // Members at the back of the formation break off first
+        let next = members.iter()
+            .filter(|(_, _, member, diving)| member.squadron == squadron_entity && diving.is_none())
+            .max_by(|(_, _, a, _), (_, _, b, _)| a.offset.y.total_cmp(&b.offset.y).then(a.offset.x.total_cmp(&b.offset.x)));
+        let Some((member_entity, transform, _, _)) = next else { continue };
+
+        let position = transform.translation.truncate();
+        let direction = targets.iter()
+            .min_by(|a, b| a.distance_squared(position).total_cmp(&b.distance_squared(position)))
+            .map(|target| (*target - position).normalize_or_zero())
+            .filter(|direction| *direction != Vec2::ZERO)
+            .unwrap_or(Vec2::NEG_Y);
+        commands.entity(member_entity).insert(Diving(direction * squadron.data.dive_speed));
+    }
+}
+
+/// Counts members destroyed or gone off screen after the hits of the tick, paying the bonus to whoever
+/// destroyed the last one if none got away
+#[allow(clippy::type_complexity)]
+fn track_squadrons(
+    mut commands: Commands,
+    mut squadrons: Query<(Entity, &mut Squadron)>,
+    members: Query<(Entity, &Transform, &Health, &LastHitBy, &SquadronMember, Option<&Diving>), With<Foe>>,
+    mut squadron_events: EventWriter<SquadronDestroyedEvent>,
+    mut scoring: Scoring,
+    scroll_position: Res<ScrollPosition>
+) {
+    let bottom = scroll_position.0 - WINDOW_HEIGHT * 0.5 - FORMATION_MARGIN;
+    let top = scroll_position.0 + WINDOW_HEIGHT * 0.5 + FORMATION_MARGIN;
+    let side = WINDOW_WIDTH * 0.5 + FORMATION_MARGIN;
+
+    for (member_entity, transform, health, last_hit_by, member, diving) in &members {
+        let Ok((_, mut squadron)) = squadrons.get_mut(member.squadron) else { continue };
+        let position = transform.translation;
+
+        // Members in formation may come in from past the top and sides, only divers heading out get away through them
+        let gone = position.y < bottom || diving.is_some_and(|diving| {
+            (position.y > top && diving.0.y > 0.0) || (position.x.abs() > side && diving.0.x * position.x > 0.0)
+        });
+        if health.0 > 0 && !gone { continue }
+
+        squadron.alive = squadron.alive.saturating_sub(1);
+        commands.entity(member_entity).remove::<SquadronMember>();
+
+        if health.0 > 0 {
+            commands.entity(member_entity).insert(Dead);
+            squadron.escaped = true;
+            continue;
+        }
+        if squadron.alive > 0 || squadron.escaped { continue }
+
+        let bonus = squadron.data.bonus;
+        scoring.award(last_hit_by.0, bonus);
+        squadron_events.send(SquadronDestroyedEvent { player: last_hit_by.0, bonus, position });
+    }
+
+    for (squadron_entity, squadron) in &squadrons {
+        if squadron.alive == 0 {
+            commands.entity(squadron_entity).insert(Dead);
+        }
+    }
+}
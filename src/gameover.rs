@@ -0,0 +1,146 @@
+use bevy::prelude::*;
+
+use crate::components::*;
+use crate::leaderboard::*;
+use crate::replay::*;
+use crate::resources::*;
+use crate::stats::*;
+use crate::states::*;
+
+/// Results screen shown once the run is over, with the way back into the game
+pub struct GameOverPlugin;
+
+impl Plugin for GameOverPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::GameOver), setup_gameover.after(LeaderboardSet))
+            .add_systems(Update, check_gameover_options.run_if(in_state(GameState::GameOver)));
+    }
+
+    fn finish(&self, app: &mut App) {
+        require::<RunStart, ReplayPlugin>(app);
+        require::<RunStats, StatsPlugin>(app);
+        require::<LeaderboardPlacement, LeaderboardPlugin>(app);
+    }
+}
+
+fn stat_line(label: &str, value: String) -> TextBundle {
+    TextBundle::from_section(
+        format!("{:<16}{:>10}", label, value),
+        TextStyle {
+            font_size: 20.0,
+            color: Color::WHITE,
+            ..default()
+        }
+    )
+}
+
+fn option_line(label: &str) -> TextBundle {
+    TextBundle::from_section(
+        label,
+        TextStyle {
+            font_size: 24.0,
+            color: Color::ORANGE,
+            ..default()
+        }
+    )
+}
+
+fn setup_gameover(
+    mut commands: Commands,
+    stats: Res<RunStats>,
+    run_start: Res<RunStart>,
+    placement: Res<LeaderboardPlacement>,
+    players: Option<Res<Players>>,
+    last_replay: Option<Res<LastReplay>>
+) {
+    let minutes = stats.time_survived as u32 / 60;
+    let seconds = stats.time_survived as u32 % 60;
+
+    let mut kills: Vec<_> = stats.kills.iter().collect();
+    kills.sort_by_key(|(kind, _)| kind.name());
+
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            ..default()
+        },
+        GameBanner
+    ))
+    .with_children(|parent| {
+        parent.spawn(
+            TextBundle::from_section(
+                "Game Over",
+                TextStyle {
+                    font_size: 80.0,
+                    color: Color::PURPLE,
+                    ..default()
+                }
+            )
+        );
+
+        parent.spawn(stat_line("Mode", run_start.0.mode.name().to_string()));
+        parent.spawn(stat_line("Difficulty", run_start.0.difficulty.name().to_string()));
+        parent.spawn(stat_line("Score", stats.score.to_string()));
+        if let Some(players) = players.filter(|_| run_start.0.co_op) {
+            for (index, player) in players.0.iter().enumerate() {
+                parent.spawn(stat_line(&format!("  {}P", index + 1), player.score.to_string()));
+            }
+        }
+        if let Some(place) = placement.0 {
+            parent.spawn(stat_line("Leaderboard", format!("#{}", place + 1)));
+        }
+        parent.spawn(stat_line("Time survived", format!("{}:{:02}", minutes, seconds)));
+        parent.spawn(stat_line("Foes killed", stats.total_kills().to_string()));
+        for (kind, count) in kills {
+            parent.spawn(stat_line(&format!("  {}", kind.name()), count.to_string()));
+        }
+        parent.spawn(stat_line("Shots fired", stats.shots_fired.to_string()));
+        parent.spawn(stat_line("Accuracy", format!("{:.0}%", stats.accuracy() * 100.0)));
+        parent.spawn(stat_line("Damage taken", stats.damage_taken.to_string()));
+        parent.spawn(stat_line("Max combo", stats.max_combo.to_string()));
+        parent.spawn(stat_line("Wave reached", stats.wave_reached.to_string()));
+
+        parent.spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                margin: UiRect::top(Val::Px(16.0)),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn(option_line("Space - Retry"));
+            if last_replay.is_some() {
+                parent.spawn(option_line("R - View replay"));
+            }
+            parent.spawn(option_line("Escape - Return to menu"));
+        });
+    });
+}
+
+fn check_gameover_options(
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<GameState>>,
+    last_replay: Option<Res<LastReplay>>,
+    keyboard_input: Res<Input<KeyCode>>
+) {
+    if keyboard_input.just_pressed(KeyCode::Space) {
+        next_state.set(GameState::Playing);
+    } else if keyboard_input.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::Menu);
+    } else if keyboard_input.just_pressed(KeyCode::R) {
+        let Some(last_replay) = last_replay else { return };
+
+        commands.insert_resource(ReplayPlayback::new(last_replay.0.clone()));
+        next_state.set(GameState::Playing);
+    }
+}
@@ -0,0 +1,210 @@
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+use bevy::prelude::*;
+
+use crate::background::*;
+use crate::components::*;
+use crate::config::*;
+use crate::constants::*;
+use crate::despawn::*;
+use crate::difficulty::*;
+use crate::events::*;
+use crate::foe::*;
+use crate::level::*;
+use crate::projectiles::*;
+use crate::resources::*;
+use crate::sprites::*;
+use crate::states::*;
+
+/// Puts the ground foes of the level timeline on the terrain, scrolls them along with it,
+/// turns turrets towards the player and leaves wrecks behind
+pub struct GroundPlugin;
+
+impl Plugin for GroundPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<GroundStartEvent>()
+            .add_event::<FoeKilledEvent>()
+            .add_systems(
+                FixedUpdate,
+                (
+                    (spawn_ground_foes, scroll_with_terrain, aim_turrets).chain().after(ScrollSet).before(HitSet),
+                    leave_wrecks.after(HitSet)
+                ).after(TimelineSet).after(DifficultySet).before(DespawnSet).run_if(in_state(GameState::Playing))
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        require::<GameConfig, ConfigPlugin>(app);
+        require::<DifficultyScaling, DifficultyPlugin>(app);
+        require::<ScrollPosition, BackgroundPlugin>(app);
+        require::<ScrollSpeed, BackgroundPlugin>(app);
+        require::<TerrainSpeed, BackgroundPlugin>(app);
+        require::<ProjectilePool, ProjectilePlugin>(app);
+    }
+}
+
+/// Gun of a ground turret, `angle` in radians from the x axis towards where it points
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Turret {
+    pub angle: f32
+}
+
+impl Turret {
+    pub fn direction(&self) -> Vec2 {
+        Vec2::from_angle(self.angle)
+    }
+}
+
+/// What is left of a destroyed ground foe, it stays on the terrain until it scrolls out of sight
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Wreck(pub FoeKind);
+
+/// Spawns a foe on the terrain, scrolling by at `terrain_speed` like the background layer below it
+pub fn spawn_ground_foe(
+    commands: &mut Commands,
+    sprite_catalog: &SpriteCatalog,
+    config: &GameConfig,
+    scaling: &DifficultyScaling,
+    kind: FoeKind,
+    position: Vec2,
+    terrain_speed: f32
+) -> Entity {
+    let foe = spawn_foe(commands, sprite_catalog, config, scaling, kind, position);
+    commands.entity(foe).insert(Grounded(terrain_speed));
+
+    // Turrets come in pointing down and hold their fire until they are lined up with the player
+    if kind == FoeKind::Turret {
+        let turret = Turret { angle: -FRAC_PI_2 };
+        commands.entity(foe).insert((
+            Transform {
+                translation: position.extend(GROUND_Z),
+                rotation: turret_rotation(&turret),
+                scale: Vec3::splat(TURRET_SCALE)
+            },
+            ShootingDelay(Timer::from_seconds(config.turret.shoot_delay, TimerMode::Once)),
+            turret
+        ));
+    }
+    foe
+}
+
+/// Spawns the destroyed-state sprite of a ground foe where it was, keeping its look and rotation
+pub fn spawn_wreck(
+    commands: &mut Commands,
+    sprite_catalog: &SpriteCatalog,
+    kind: FoeKind,
+    mut transform: Transform,
+    terrain_speed: f32
+) -> Entity {
+    // Under the ground foes still standing
+    transform.translation.z = GROUND_Z - 1.0;
+
+    let mut sprite = sprite_catalog.sprite_sheet(SpriteName::Foe, transform);
+    sprite.sprite.color = WRECK_COLOR;
+
+    commands.spawn((sprite, Grounded(terrain_speed), Wreck(kind))).id()
+}
+
+/// Sprites face down, the way foes fly
+fn turret_rotation(turret: &Turret) -> Quat {
+    Quat::from_rotation_z(turret.angle + FRAC_PI_2)
+}
+
+fn spawn_ground_foes(
+    mut commands: Commands,
+    mut ground_events: EventReader<GroundStartEvent>,
+    scroll_position: Res<ScrollPosition>,
+    terrain_speed: Res<TerrainSpeed>,
+    sprite_catalog: Res<SpriteCatalog>,
+    config: Res<GameConfig>,
+    scaling: Res<DifficultyScaling>
+) {
+    for event in ground_events.read() {
+        let position = Vec2::new(event.x, scroll_position.0 + WINDOW_HEIGHT * 0.5 + GROUND_MARGIN);
+        spawn_ground_foe(&mut commands, &sprite_catalog, &config, &scaling, event.kind, position, terrain_speed.0);
+    }
+}
+
+/// Moves ground foes and wrecks as far as their background layer moved this tick
+fn scroll_with_terrain(
+    mut commands: Commands,
+    mut grounded: Query<(Entity, &mut Transform, &Grounded)>,
+    scroll_position: Res<ScrollPosition>,
+    scroll_speed: Res<ScrollSpeed>,
+    time: Res<Time>
+) {
+    let distance = scroll_speed.current * time.delta_seconds();
+
+    for (entity, mut transform, grounded) in &mut grounded {
+        transform.translation.y += distance * grounded.0;
+
+        if transform.translation.y < scroll_position.0 - (WINDOW_HEIGHT * 0.5) - GROUND_MARGIN {
+            commands.entity(entity).insert(Dead);
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn aim_turrets(
+    mut commands: Commands,
+    mut projectile_pool: ResMut<ProjectilePool>,
+    mut turrets: Query<(&mut Transform, &mut Turret, &mut ShootingDelay), (With<Foe>, With<Grounded>)>,
+    ships: Query<(&Transform, &Health), (With<SpaceShip>, Without<Turret>)>,
+    sprite_catalog: Res<SpriteCatalog>,
+    config: Res<GameConfig>,
+    scaling: Res<DifficultyScaling>,
+    time: Res<Time>
+) {
+    // Runs before the hits of the tick, destroyed ships are already despawned and this only skips ships left
+    // without health by something else
+    let targets: Vec<Vec2> = ships.iter()
+        .filter(|(_, health)| health.0 > 0)
+        .map(|(transform, _)| transform.translation.truncate())
+        .collect();
+
+    for (mut transform, mut turret, mut shoot_delay) in &mut turrets {
+        // Reloads whatever it points at, a higher fire rate reloads faster
+        shoot_delay.0.tick(time.delta().mul_f32(scaling.fire_rate));
+
+        let position = transform.translation.truncate();
+        let Some(target) = targets.iter().min_by(|a, b| {
+            a.distance_squared(position).total_cmp(&b.distance_squared(position))
+        }) else { continue };
+
+        // Turns the short way round, no faster than its turn rate
+        let offset = *target - position;
+        let wanted = offset.y.atan2(offset.x);
+        let step = config.turret.turn_rate * time.delta_seconds();
+        let difference = (wanted - turret.angle + PI).rem_euclid(TAU) - PI;
+        turret.angle = (turret.angle + difference.clamp(-step, step) + PI).rem_euclid(TAU) - PI;
+        transform.rotation = turret_rotation(&turret);
+
+        let off_target = ((wanted - turret.angle + PI).rem_euclid(TAU) - PI).abs();
+        if !shoot_delay.0.finished() || off_target > config.turret.aim_tolerance { continue }
+
+        shoot_delay.0.reset();
+        let (damage, projectile_speed) = config.projectile(FoeKind::Turret);
+        spawn_foe_projectile(
+            &mut commands,
+            &mut projectile_pool,
+            &sprite_catalog,
+            &config,
+            position,
+            turret.direction(),
+            projectile_speed * scaling.projectile_speed,
+            damage
+        );
+    }
+}
+
+fn leave_wrecks(
+    mut commands: Commands,
+    mut foe_killed_events: EventReader<FoeKilledEvent>,
+    ground_foes: Query<(&Transform, &Grounded), With<Foe>>,
+    sprite_catalog: Res<SpriteCatalog>
+) {
+    for event in foe_killed_events.read() {
+        let Ok((transform, grounded)) = ground_foes.get(event.foe) else { continue };
+        spawn_wreck(&mut commands, &sprite_catalog, event.kind, *transform, grounded.0);
+    }
+}
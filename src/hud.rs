@@ -0,0 +1,278 @@
+use bevy::prelude::*;
+
+use crate::components::*;
+use crate::config::*;
+use crate::constants::*;
+use crate::events::*;
+use crate::replay::*;
+use crate::resources::*;
+use crate::sprites::*;
+use crate::states::*;
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<WarningEvent>()
+            .add_systems(OnEnter(GameState::Playing), setup_hud.after(RunSetupSet))
+            .add_systems(
+                Update,
+                (
+                    update_health_bar,
+                    update_player_counters,
+                    update_hud_counter::<Score, HudScoreText>,
+                    update_hud_counter::<HiScore, HudHiScoreText>,
+                    update_hud_counter::<Wave, HudWaveText>,
+                    show_warnings
+                ).run_if(in_state(GameState::Playing))
+            )
+            .add_systems(OnExit(GameState::Playing), teardown_hud);
+    }
+
+    fn finish(&self, app: &mut App) {
+        require::<GameConfig, ConfigPlugin>(app);
+        require::<RunStart, ReplayPlugin>(app);
+    }
+}
+
+/// Resources the HUD prints as a single number
+pub trait HudCounter: Resource {
+    fn label(&self) -> String;
+}
+
+impl HudCounter for Score {
+    fn label(&self) -> String { format!("SCORE {:08}", self.0) }
+}
+
+impl HudCounter for HiScore {
+    fn label(&self) -> String { format!("HI {:08}", self.0) }
+}
+
+impl HudCounter for Wave {
+    fn label(&self) -> String { format!("WAVE {}", self.0) }
+}
+
+fn hud_text<T: Component>(marker: T) -> (TextBundle, T) {
+    (
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: HUD_FONT_SIZE,
+                color: Color::WHITE,
+                ..default()
+            }
+        ),
+        marker
+    )
+}
+
+fn hud_icon(sprite_catalog: &SpriteCatalog, name: SpriteName) -> AtlasImageBundle {
+    let mut icon = sprite_catalog.atlas_image(name);
+    icon.style = Style {
+        width: Val::Px(HUD_ICON_SIZE),
+        height: Val::Px(HUD_ICON_SIZE),
+        ..default()
+    };
+    icon
+}
+
+fn setup_hud(
+    mut commands: Commands,
+    sprite_catalog: Res<SpriteCatalog>,
+    config: Res<GameConfig>,
+    run_start: Res<RunStart>
+) {
+    let co_op = run_start.0.co_op;
+
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                justify_content: JustifyContent::SpaceBetween,
+                ..default()
+            },
+            ..default()
+        },
+        Hud
+    ))
+    .with_children(|parent| {
+        // Health bar, lives and bombs of every player on the left
+        parent.spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            for player in 0..run_start.0.players() {
+                if co_op {
+                    parent.spawn((hud_text(HudPlayerCounter::Score), Player(player)));
+                }
+
+                parent.spawn(NodeBundle {
+                    style: Style {
+                        column_gap: Val::Px(1.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for segment in 0..config.ship.health {
+                        let mut bundle = sprite_catalog.atlas_image(SpriteName::HealthSegment);
+                        bundle.style = Style {
+                            width: Val::Px(HUD_SEGMENT_WIDTH),
+                            height: Val::Px(HUD_ICON_SIZE),
+                            ..default()
+                        };
+                        parent.spawn((bundle, HealthSegment(segment), Player(player)));
+                    }
+                });
+
+                parent.spawn(NodeBundle {
+                    style: Style {
+                        align_items: AlignItems::Center,
+                        column_gap: Val::Px(4.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    let mut ship_icon = hud_icon(&sprite_catalog, SpriteName::Ship);
+                    ship_icon.background_color = SHIP_COLORS[player % MAX_PLAYERS].into();
+                    parent.spawn(ship_icon);
+                    parent.spawn((hud_text(HudPlayerCounter::Lives), Player(player)));
+                    parent.spawn(hud_icon(&sprite_catalog, SpriteName::BombIcon));
+                    parent.spawn((hud_text(HudPlayerCounter::Bombs), Player(player)));
+                    parent.spawn((hud_text(HudPlayerCounter::Graze), Player(player)));
+                });
+            }
+        });
+
+        // Score, hi-score and wave on the right
+        parent.spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::FlexEnd,
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn(hud_text(HudScoreText));
+            parent.spawn(hud_text(HudHiScoreText));
+            parent.spawn(hud_text(HudWaveText));
+        });
+    });
+
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            ..default()
+        },
+        Hud
+    ))
+    .with_children(|parent| {
+        parent.spawn((
+            TextBundle {
+                text: Text::from_section(
+                    "",
+                    TextStyle {
+                        font_size: HUD_WARNING_FONT_SIZE,
+                        color: Color::RED,
+                        ..default()
+                    }
+                ),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            HudWarningText(0.0)
+        ));
+    });
+}
+
+fn update_health_bar(
+    ship_query: Query<(Ref<Health>, &Player), With<SpaceShip>>,
+    mut segments: Query<(&HealthSegment, &Player, &mut BackgroundColor)>
+) {
+    for (health, player) in &ship_query {
+        if !health.is_changed() { continue }
+
+        for (segment, _, mut color) in segments.iter_mut().filter(|(_, owner, _)| *owner == player) {
+            *color = if health.0 > segment.0 {
+                Color::WHITE.into()
+            } else {
+                HUD_EMPTY_SEGMENT_COLOR.into()
+            };
+        }
+    }
+}
+
+fn update_player_counters(
+    players: Option<Res<Players>>,
+    mut texts: Query<(&mut Text, &HudPlayerCounter, &Player)>
+) {
+    let Some(players) = players else { return };
+
+    for (mut text, counter, player) in &mut texts {
+        // Freshly spawned texts need a first value even when the players did not change
+        if !players.is_changed() && !text.is_added() { continue }
+        let Some(state) = players.0.get(player.0) else { continue };
+
+        text.sections[0].value = match counter {
+            HudPlayerCounter::Lives => format!("x{}", state.lives),
+            HudPlayerCounter::Bombs => format!("x{}", state.bombs),
+            HudPlayerCounter::Score => format!("{}P {:08}", player.0 + 1, state.score),
+            HudPlayerCounter::Graze => format!("GRAZE {}", state.graze)
+        };
+    }
+}
+
+fn update_hud_counter<R: HudCounter, T: Component>(
+    counter: Option<Res<R>>,
+    mut texts: Query<&mut Text, With<T>>
+) {
+    let Some(counter) = counter else { return };
+
+    for mut text in &mut texts {
+        // Freshly spawned texts need a first value even when the counter did not change
+        if counter.is_changed() || text.is_added() {
+            text.sections[0].value = counter.label();
+        }
+    }
+}
+
+fn show_warnings(
+    mut warnings: Query<(&mut Text, &mut Visibility, &mut HudWarningText)>,
+    mut warning_events: EventReader<WarningEvent>,
+    time: Res<Time>
+) {
+    let Ok((mut text, mut visibility, mut warning)) = warnings.get_single_mut() else { return };
+
+    if let Some(event) = warning_events.read().last() {
+        text.sections[0].value = event.text.clone();
+        warning.0 = HUD_WARNING_TIME;
+    }
+
+    warning.0 = (warning.0 - time.delta_seconds()).max(0.0);
+
+    // Blinks while up, on for one blink period and off for the next
+    let blink_on = (warning.0 / HUD_WARNING_BLINK) as u32 % 2 == 1;
+    *visibility = if warning.0 > 0.0 && blink_on { Visibility::Inherited } else { Visibility::Hidden };
+}
+
+fn teardown_hud(mut commands: Commands, huds: Query<Entity, With<Hud>>) {
+    for entity in &huds {
+        commands.entity(entity).despawn_recursive();
+    }
+}
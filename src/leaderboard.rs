@@ -0,0 +1,148 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::constants::*;
+use crate::player::*;
+use crate::replay::*;
+use crate::resources::*;
+use crate::states::*;
+use crate::stats::*;
+
+/// Keeps the best runs of every game mode on disk, the hi-score shows the best one of the mode being played
+pub struct LeaderboardPlugin {
+    pub path: PathBuf
+}
+
+impl Default for LeaderboardPlugin {
+    fn default() -> Self {
+        Self { path: PathBuf::from(LEADERBOARD_PATH) }
+    }
+}
+
+impl Plugin for LeaderboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Leaderboard::load(&self.path))
+            .insert_resource(LeaderboardPath(self.path.clone()))
+            .init_resource::<LeaderboardPlacement>()
+            .add_systems(OnEnter(GameState::Playing), show_best_score.after(RunSetupSet))
+            .add_systems(OnEnter(GameState::GameOver), record_run.in_set(LeaderboardSet));
+    }
+
+    fn finish(&self, app: &mut App) {
+        require::<RunStart, ReplayPlugin>(app);
+        require::<RunStats, StatsPlugin>(app);
+        require::<HiScore, PlayerPlugin>(app);
+    }
+}
+
+/// The finished run is on the leaderboard once this set ran
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LeaderboardSet;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+    pub score: u32,
+    pub wave: u32,
+    pub difficulty: Difficulty,
+    /// Seconds the run lasted
+    pub time: f32
+}
+
+/// Best runs of every game mode, highest score first
+#[derive(Resource, Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Leaderboard {
+    pub campaign: Vec<LeaderboardEntry>,
+    pub endless: Vec<LeaderboardEntry>
+}
+
+impl Leaderboard {
+    pub fn load(path: &Path) -> Self {
+        let Ok(contents) = fs::read_to_string(path) else { return Leaderboard::default() };
+
+        match ron::from_str::<Leaderboard>(&contents) {
+            Ok(leaderboard) => leaderboard,
+            Err(error) => {
+                warn!("Ignoring invalid leaderboard file {}: {}", path.display(), error);
+                Leaderboard::default()
+            }
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|error| error.to_string())?;
+
+        fs::write(path, contents).map_err(|error| error.to_string())
+    }
+
+    pub fn entries(&self, mode: GameMode) -> &[LeaderboardEntry] {
+        match mode {
+            GameMode::Campaign => &self.campaign,
+            GameMode::Endless => &self.endless
+        }
+    }
+
+    pub fn best(&self, mode: GameMode) -> u32 {
+        self.entries(mode).first().map_or(0, |entry| entry.score)
+    }
+
+    /// Adds a run to its mode, returning its place from 0 or `None` when it did not make the top `LEADERBOARD_SIZE`
+    pub fn submit(&mut self, mode: GameMode, entry: LeaderboardEntry) -> Option<usize> {
+        let entries = match mode {
+            GameMode::Campaign => &mut self.campaign,
+            GameMode::Endless => &mut self.endless
+        };
+
+        // Ties go below the runs that got there first
+        let place = entries.iter().position(|other| entry.score > other.score).unwrap_or(entries.len());
+        if place >= LEADERBOARD_SIZE { return None }
+
+        entries.insert(place, entry);
+        entries.truncate(LEADERBOARD_SIZE);
+        Some(place)
+    }
+}
+
+#[derive(Resource)]
+pub struct LeaderboardPath(pub PathBuf);
+
+/// Where the last finished run placed on the leaderboard of its mode, if it made it
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LeaderboardPlacement(pub Option<usize>);
+
+fn show_best_score(mut hi_score: ResMut<HiScore>, leaderboard: Res<Leaderboard>, run_start: Res<RunStart>) {
+    hi_score.0 = leaderboard.best(run_start.0.mode);
+}
+
+fn record_run(
+    mut leaderboard: ResMut<Leaderboard>,
+    mut placement: ResMut<LeaderboardPlacement>,
+    playback: Option<Res<ReplayPlayback>>,
+    score: Option<Res<Score>>,
+    stats: Res<RunStats>,
+    run_start: Res<RunStart>,
+    path: Res<LeaderboardPath>
+) {
+    placement.0 = None;
+
+    // Watching a replay does not count as a new run
+    if playback.is_some() { return }
+
+    let entry = LeaderboardEntry {
+        score: score.map_or(stats.score, |score| score.0),
+        wave: stats.wave_reached,
+        difficulty: run_start.0.difficulty,
+        time: stats.time_survived
+    };
+
+    placement.0 = leaderboard.submit(run_start.0.mode, entry);
+    if placement.0.is_none() { return }
+
+    if let Err(error) = leaderboard.save(&path.0) {
+        error!("Could not save the leaderboard to {}: {}", path.0.display(), error);
+    }
+}
@@ -0,0 +1,273 @@
+use std::fs;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::background::*;
+use crate::components::*;
+use crate::difficulty::*;
+use crate::events::*;
+use crate::foe::*;
+use crate::formation::*;
+use crate::loading::*;
+use crate::manifest::*;
+use crate::replay::*;
+use crate::resources::*;
+use crate::sound::*;
+use crate::states::*;
+
+/// Reads the level files at startup and plays their timeline as the background scrolls
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Levels>()
+            .init_resource::<CurrentLevel>()
+            .init_resource::<LevelProgress>()
+            .add_event::<SwapBackgroundEvent>()
+            .add_event::<PlayMusicEvent>()
+            .add_event::<WaveStartEvent>()
+            .add_event::<WarningEvent>()
+            .add_event::<BossStartEvent>()
+            .add_event::<SquadronStartEvent>()
+            .add_event::<GroundStartEvent>()
+            .add_systems(Startup, load_levels)
+            .add_systems(OnEnter(GameState::Playing), start_level.after(RunSetupSet))
+            .add_systems(
+                FixedUpdate,
+                run_timeline
+                    .in_set(TimelineSet)
+                    .after(ScrollSet)
+                    .after(DifficultySet)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(in_mode(GameMode::Campaign))
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        require::<LoadingAssets, LoadingPlugin>(app);
+        require::<RunStart, ReplayPlugin>(app);
+        require::<DifficultyScaling, DifficultyPlugin>(app);
+        require::<ScrollSpeed, BackgroundPlugin>(app);
+        require::<TerrainSpeed, BackgroundPlugin>(app);
+        require::<FoeSpawnQueue, FoePlugin>(app);
+    }
+}
+
+/// Systems reacting to timeline markers during a fixed tick run after this set
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TimelineSet;
+
+/// Level files, in the order they are played
+pub const LEVEL_FILES: [AssetName; 2] = [AssetName::Level1, AssetName::Level2];
+
+/// Everything a level file describes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LevelData {
+    pub name: String,
+    pub background: BackgroundTheme,
+    /// Scroll distance at which the level is over and the next one starts
+    pub length: f32,
+    /// Markers sorted by distance
+    pub timeline: Vec<TimelineMarker>
+}
+
+/// Something that happens once the background scrolled `distance` pixels into the level
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimelineMarker {
+    pub distance: f32,
+    pub event: TimelineEvent
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TimelineEvent {
+    /// Starts a new wave of `foes` foes, one every `spacing` pixels of scroll
+    Wave { foes: u32, spacing: f32 },
+    Music(MusicTrack),
+    Warning(String),
+    /// Scroll speed relative to the level base speed, 0.0 stops the scroll and 1.0 resumes it
+    Scroll(f32),
+    Background(BackgroundTheme),
+    /// Brings in a squadron flying in formation, part of the current wave
+    Squadron(SquadronData),
+    /// Puts a ground foe on the terrain coming in at the top of the screen, `x` pixels from its middle
+    Ground { kind: FoeKind, x: f32 },
+    /// Brings in the boss, the scroll stops until it is destroyed
+    Boss
+}
+
+impl LevelData {
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let mut level: LevelData = ron::from_str(contents).map_err(|error| error.to_string())?;
+        level.timeline.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+
+        for marker in &level.timeline {
+            if let TimelineEvent::Squadron(data) = &marker.event {
+                data.validate().map_err(|error| format!("squadron at {}: {}", marker.distance, error))?;
+            }
+        }
+        Ok(level)
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct Levels(pub Vec<LevelData>);
+
+impl Levels {
+    pub fn get(&self, index: usize) -> Option<&LevelData> {
+        self.0.get(index)
+    }
+}
+
+/// Index into `Levels` of the level being played
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CurrentLevel(pub usize);
+
+/// How far into the current level the background scrolled, and the next marker to fire
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LevelProgress {
+    pub distance: f32,
+    pub next_marker: usize
+}
+
+fn load_levels(mut levels: ResMut<Levels>, mut loading_assets: ResMut<LoadingAssets>) {
+    let root = asset_root();
+
+    for name in LEVEL_FILES {
+        let level = fs::read_to_string(root.join(name.path()))
+            .map_err(|error| error.to_string())
+            .and_then(|contents| LevelData::parse(&contents));
+
+        match level {
+            Ok(level) => levels.0.push(level),
+            Err(error) => {
+                let message = format!("Could not load level '{}': {}", name.path(), error);
+                error!("{}", message);
+                loading_assets.errors.push(message);
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn start_level(
+    mut commands: Commands,
+    mut current_level: ResMut<CurrentLevel>,
+    mut progress: ResMut<LevelProgress>,
+    mut spawn_queue: ResMut<FoeSpawnQueue>,
+    mut scroll_speed: ResMut<ScrollSpeed>,
+    mut terrain_speed: ResMut<TerrainSpeed>,
+    mut swap_events: EventWriter<SwapBackgroundEvent>,
+    mut music_events: EventWriter<PlayMusicEvent>,
+    run_start: Res<RunStart>,
+    levels: Res<Levels>
+) {
+    let start = run_start.0;
+
+    *current_level = CurrentLevel(start.level.min(levels.0.len().saturating_sub(1)));
+    *progress = LevelProgress::default();
+    spawn_queue.0.clear();
+    commands.insert_resource(Wave(0));
+
+    let Some(level) = levels.get(current_level.0) else { return };
+    let mut background = &level.background;
+
+    // Starting further in skips every marker before the requested wave, except for the music and background
+    if start.wave > 1 {
+        let mut waves = 0;
+        let mut music = None;
+
+        for (index, marker) in level.timeline.iter().enumerate() {
+            match &marker.event {
+                TimelineEvent::Wave { .. } => {
+                    waves += 1;
+                    if waves == start.wave {
+                        progress.distance = marker.distance;
+                        progress.next_marker = index;
+                        commands.insert_resource(Wave(waves - 1));
+                        break;
+                    }
+                },
+                TimelineEvent::Music(track) => music = Some(*track),
+                TimelineEvent::Background(theme) => background = theme,
+                _ => ()
+            }
+        }
+
+        if music.is_some() {
+            music_events.send(PlayMusicEvent(music));
+        }
+    }
+
+    *scroll_speed = ScrollSpeed::new(background.scroll_speed);
+    *terrain_speed = TerrainSpeed(background.terrain_speed());
+    swap_events.send(SwapBackgroundEvent(background.clone()));
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_timeline(
+    mut current_level: ResMut<CurrentLevel>,
+    mut progress: ResMut<LevelProgress>,
+    mut spawn_queue: ResMut<FoeSpawnQueue>,
+    mut scroll_speed: ResMut<ScrollSpeed>,
+    mut terrain_speed: ResMut<TerrainSpeed>,
+    mut wave: ResMut<Wave>,
+    mut swap_events: EventWriter<SwapBackgroundEvent>,
+    mut music_events: EventWriter<PlayMusicEvent>,
+    mut wave_start_events: EventWriter<WaveStartEvent>,
+    mut warning_events: EventWriter<WarningEvent>,
+    mut boss_events: EventWriter<BossStartEvent>,
+    mut squadron_events: EventWriter<SquadronStartEvent>,
+    mut ground_events: EventWriter<GroundStartEvent>,
+    levels: Res<Levels>,
+    scaling: Res<DifficultyScaling>
+) {
+    let Some(level) = levels.get(current_level.0) else { return };
+
+    while let Some(marker) = level.timeline.get(progress.next_marker) {
+        if marker.distance > progress.distance { break }
+
+        progress.next_marker += 1;
+
+        match &marker.event {
+            TimelineEvent::Wave { foes, spacing } => {
+                wave.0 += 1;
+                wave_start_events.send(WaveStartEvent { wave: wave.0 });
+
+                // Denser waves fit more foes over the same stretch of the level
+                let count = scaling.wave_foes(*foes);
+                let spacing = spacing * *foes as f32 / count as f32;
+                let start = progress.distance;
+                spawn_queue.0.extend((0..count).map(|foe| start + foe as f32 * spacing));
+            },
+            TimelineEvent::Music(track) => music_events.send(PlayMusicEvent(Some(*track))),
+            TimelineEvent::Warning(text) => warning_events.send(WarningEvent { text: text.clone() }),
+            TimelineEvent::Scroll(factor) => scroll_speed.scale(*factor),
+            TimelineEvent::Background(theme) => {
+                // The speed changes with the tick, only the pictures wait for the next frame
+                scroll_speed.rebase(theme.scroll_speed);
+                *terrain_speed = TerrainSpeed(theme.terrain_speed());
+                swap_events.send(SwapBackgroundEvent(theme.clone()));
+            },
+            TimelineEvent::Squadron(data) => squadron_events.send(SquadronStartEvent(data.clone())),
+            TimelineEvent::Ground { kind, x } => ground_events.send(GroundStartEvent { kind: *kind, x: *x }),
+            TimelineEvent::Boss => {
+                scroll_speed.stop();
+                boss_events.send(BossStartEvent);
+            }
+        }
+    }
+
+    if progress.distance < level.length { return }
+
+    // Foes still queued from the last wave would come in at distances the next level has yet to reach
+    current_level.0 = (current_level.0 + 1) % levels.0.len();
+    *progress = LevelProgress::default();
+    spawn_queue.0.clear();
+
+    if let Some(level) = levels.get(current_level.0) {
+        scroll_speed.rebase(level.background.scroll_speed);
+        *terrain_speed = TerrainSpeed(level.background.terrain_speed());
+        swap_events.send(SwapBackgroundEvent(level.background.clone()));
+    }
+}
@@ -1,8 +1,36 @@
 pub mod background;
+pub mod cli;
+pub mod level;
 pub mod components;
+pub mod config;
+pub mod damage;
+#[cfg(feature = "dev")]
+pub mod debug;
+pub mod despawn;
+pub mod difficulty;
+pub mod endless;
+pub mod formation;
+pub mod ground;
+pub mod leaderboard;
 pub mod resources;
 pub mod constants;
 pub mod states;
+pub mod sprites;
+pub mod loading;
+pub mod manifest;
+pub mod events;
+pub mod particles;
+pub mod pool;
+pub mod projectiles;
+pub mod feedback;
+pub mod settings;
+pub mod sound;
+pub mod hud;
+pub mod replay;
+pub mod snapshot;
+pub mod netcode;
+pub mod stats;
+pub mod gameover;
 pub mod utils;
 
 pub mod player;
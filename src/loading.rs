@@ -0,0 +1,147 @@
+use bevy::asset::{LoadState, RecursiveDependencyLoadState, UntypedAssetId};
+use bevy::prelude::*;
+
+use crate::components::*;
+use crate::manifest::*;
+use crate::resources::*;
+use crate::states::*;
+
+pub struct LoadingPlugin;
+
+impl Plugin for LoadingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LoadingAssets>()
+            .add_systems(Startup, validate_assets)
+            .add_systems(OnEnter(GameState::Loading), setup_loading_screen)
+            .add_systems(Update, check_loading_progress.run_if(in_state(GameState::Loading)))
+            .add_systems(OnExit(GameState::Loading), teardown_loading_screen);
+    }
+}
+
+fn validate_assets(mut loading_assets: ResMut<LoadingAssets>) {
+    let root = asset_root();
+
+    for manifest_error in validate_manifest(&root) {
+        let message = format!("Asset manifest check failed: {}", manifest_error);
+        error!("{} (asset folder: {})", message, root.display());
+        loading_assets.errors.push(message);
+    }
+}
+
+fn setup_loading_screen(mut commands: Commands) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(12.0),
+                ..default()
+            },
+            ..default()
+        },
+        LoadingScreen
+    ))
+    .with_children(|parent| {
+        parent.spawn(
+            TextBundle::from_section(
+                "Loading",
+                TextStyle {
+                    font_size: 40.0,
+                    color: Color::WHITE,
+                    ..default()
+                }
+            )
+        );
+
+        // Progress bar frame and its fill
+        parent.spawn(NodeBundle {
+            style: Style {
+                width: Val::Px(320.0),
+                height: Val::Px(16.0),
+                padding: UiRect::all(Val::Px(2.0)),
+                ..default()
+            },
+            background_color: Color::DARK_GRAY.into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Percent(0.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    background_color: Color::ORANGE.into(),
+                    ..default()
+                },
+                LoadingProgressBar
+            ));
+        });
+
+        parent.spawn((
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 16.0,
+                    color: Color::RED,
+                    ..default()
+                }
+            ),
+            LoadingErrorText
+        ));
+    });
+}
+
+fn check_loading_progress(
+    mut loading_assets: ResMut<LoadingAssets>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut progress_bar: Query<&mut Style, With<LoadingProgressBar>>,
+    mut error_text: Query<&mut Text, With<LoadingErrorText>>,
+    asset_server: Res<AssetServer>
+) {
+    let ids: Vec<UntypedAssetId> = loading_assets.handles.iter().map(|handle| handle.id()).collect();
+    let mut loaded = 0;
+
+    for id in ids {
+        match asset_server.get_load_states(id) {
+            Some((_, _, RecursiveDependencyLoadState::Loaded)) => loaded += 1,
+            Some((LoadState::Failed, _, _)) | Some((_, _, RecursiveDependencyLoadState::Failed)) => {
+                let path = asset_server.get_path(id)
+                    .map(|path| path.to_string())
+                    .unwrap_or_else(|| format!("{:?}", id));
+                let message = format!("Failed to load asset '{}'", path);
+
+                if !loading_assets.errors.contains(&message) {
+                    error!("{}", message);
+                    loading_assets.errors.push(message);
+                }
+            },
+            _ => {}
+        }
+    }
+
+    if let Ok(mut style) = progress_bar.get_single_mut() {
+        style.width = Val::Percent(loading_assets.progress(loaded) * 100.0);
+    }
+
+    if !loading_assets.errors.is_empty() {
+        if let Ok(mut text) = error_text.get_single_mut() {
+            text.sections[0].value = loading_assets.errors.join("\n");
+        }
+        return;
+    }
+
+    if loaded == loading_assets.handles.len() {
+        next_state.set(GameState::Menu);
+    }
+}
+
+fn teardown_loading_screen(mut commands: Commands, screens: Query<Entity, With<LoadingScreen>>) {
+    for entity in &screens {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -1,22 +1,80 @@
-use bevy::window::{WindowMode, WindowTheme};
+use std::time::Duration;
+
+use bevy::app::ScheduleRunnerPlugin;
+use bevy::audio::AudioPlugin;
+use bevy::gilrs::GilrsPlugin;
+use bevy::render::camera::ScalingMode;
+use bevy::render::settings::WgpuSettings;
+use bevy::render::RenderPlugin;
+use bevy::time::TimeUpdateStrategy;
+use bevy::window::{ExitCondition, WindowMode, WindowTheme};
+use bevy::winit::WinitPlugin;
 use bevy::prelude::*;
 use bevy_parallax::ParallaxCameraComponent;
+use clap::{CommandFactory, Parser};
 
 use space_shooter::background::*;
+use space_shooter::cli::*;
 use space_shooter::components::*;
+use space_shooter::config::*;
+use space_shooter::damage::*;
+#[cfg(feature = "dev")]
+use space_shooter::debug::*;
 use space_shooter::constants::*;
+use space_shooter::despawn::*;
+use space_shooter::difficulty::*;
+use space_shooter::endless::*;
+use space_shooter::feedback::*;
+use space_shooter::formation::*;
+use space_shooter::ground::*;
+use space_shooter::gameover::*;
+use space_shooter::leaderboard::*;
+use space_shooter::level::*;
+use space_shooter::loading::*;
+use space_shooter::netcode::*;
+use space_shooter::particles::*;
+use space_shooter::projectiles::*;
+use space_shooter::replay::*;
+use space_shooter::resources::*;
+use space_shooter::settings::*;
+use space_shooter::sound::*;
+use space_shooter::sprites::*;
 use space_shooter::states::*;
+use space_shooter::stats::*;
 
 use space_shooter::player::*;
 use space_shooter::foe::*;
+use space_shooter::hud::*;
 
 fn setup(mut commands: Commands) {
     // Spawns 2d camera
     commands.insert_resource(ClearColor(Color::BLACK));
-    commands.spawn(Camera2dBundle::default()).insert(ParallaxCameraComponent::default());    
+    // Keeps the same view of the world whatever the window size
+    let mut camera = Camera2dBundle::default();
+    camera.projection.scaling_mode = ScalingMode::AutoMin {
+        min_width: WINDOW_WIDTH,
+        min_height: WINDOW_HEIGHT
+    };
+    commands.spawn(camera).insert(ParallaxCameraComponent::default());
+}
+
+fn run_options_label(start: &StartOptions) -> String {
+    let players = if start.co_op {
+        format!("2P, {} continues", start.continues.name())
+    } else {
+        "1P".to_string()
+    };
+
+    format!(
+        "{}   < {} >   Rank {}   {}",
+        start.mode.name().to_uppercase(),
+        start.difficulty.name().to_uppercase(),
+        if start.rank { "on" } else { "off" },
+        players
+    )
 }
 
-fn setup_gameover(mut commands: Commands) {
+fn setup_menu(mut commands: Commands, start_options: Res<StartOptions>) {
     commands.spawn((
         NodeBundle {
             style: Style {
@@ -34,10 +92,44 @@ fn setup_gameover(mut commands: Commands) {
     .with_children(|parent| {
         parent.spawn(
             TextBundle::from_section(
-                "Game Over",
+                "Space Shooter",
                 TextStyle {
                     font_size: 80.0,
-                    color: Color::PURPLE,
+                    color: Color::ORANGE,
+                    ..default()
+                }
+            )
+        );
+
+        parent.spawn((
+            TextBundle::from_section(
+                run_options_label(&start_options),
+                TextStyle {
+                    font_size: 24.0,
+                    color: Color::ORANGE,
+                    ..default()
+                }
+            ),
+            MenuRunOptionsText
+        ));
+
+        parent.spawn(
+            TextBundle::from_section(
+                "E - Mode   Left/Right - Difficulty   R - Rank   C - Players",
+                TextStyle {
+                    font_size: 18.0,
+                    color: Color::GRAY,
+                    ..default()
+                }
+            )
+        );
+
+        parent.spawn(
+            TextBundle::from_section(
+                "Press Space to start",
+                TextStyle {
+                    font_size: 24.0,
+                    color: Color::WHITE,
                     ..default()
                 }
             )
@@ -45,50 +137,197 @@ fn setup_gameover(mut commands: Commands) {
     });
 }
 
-fn check_to_reset(mut next_state: ResMut<NextState<GameState>>, keyboard_input: Res<Input<KeyCode>>) {
+fn select_run_options(
+    mut start_options: ResMut<StartOptions>,
+    mut text_query: Query<&mut Text, With<MenuRunOptionsText>>,
+    keyboard_input: Res<Input<KeyCode>>
+) {
+    let index = Difficulty::ALL.iter().position(|difficulty| *difficulty == start_options.difficulty).unwrap_or(0);
+    let count = Difficulty::ALL.len();
+
+    if keyboard_input.just_pressed(KeyCode::Left) {
+        start_options.difficulty = Difficulty::ALL[(index + count - 1) % count];
+    } else if keyboard_input.just_pressed(KeyCode::Right) {
+        start_options.difficulty = Difficulty::ALL[(index + 1) % count];
+    } else if keyboard_input.just_pressed(KeyCode::R) {
+        start_options.rank = !start_options.rank;
+    } else if keyboard_input.just_pressed(KeyCode::E) {
+        start_options.mode = match start_options.mode {
+            GameMode::Campaign => GameMode::Endless,
+            GameMode::Endless => GameMode::Campaign
+        };
+    } else if keyboard_input.just_pressed(KeyCode::C) {
+        // One player, then two with separate continues, then two sharing them
+        (start_options.co_op, start_options.continues) = match (start_options.co_op, start_options.continues) {
+            (false, _) => (true, Continues::Separate),
+            (true, Continues::Separate) => (true, Continues::Shared),
+            (true, Continues::Shared) => (false, Continues::Separate)
+        };
+    } else {
+        return;
+    }
+
+    for mut text in &mut text_query {
+        text.sections[0].value = run_options_label(&start_options);
+    }
+}
+
+fn check_to_start(mut next_state: ResMut<NextState<GameState>>, keyboard_input: Res<Input<KeyCode>>) {
     if keyboard_input.just_pressed(KeyCode::Space) {
         next_state.set(GameState::Playing)
     }
 }
 
-fn teardown(mut commands: Commands, entities: Query<Entity, (Without<Camera>, Without<Window>)>) {
+#[allow(clippy::type_complexity)]
+fn teardown(
+    mut commands: Commands,
+    mut projectile_pool: ResMut<ProjectilePool>,
+    entities: Query<Entity, (Without<Camera>, Without<Window>, Without<Particle>, Without<PooledProjectile>, Without<Persistent>)>,
+    projectiles: Query<Entity, With<PooledProjectile>>
+) {
     for entity in &entities {
         commands.entity(entity).despawn();
     }
+
+    // Projectiles go back to the pool for the next run
+    for projectile in &projectiles {
+        projectile_pool.release(&mut commands, projectile);
+    }
 }
 
-struct SpaceShooterPlugin;
+struct SpaceShooterPlugin {
+    launch: Launch
+}
 
 impl Plugin for SpaceShooterPlugin {
     fn build(&self, app: &mut App) {
+        let config = match &self.launch.config {
+            Some(path) => ConfigPlugin { path: path.clone() },
+            None => ConfigPlugin::default()
+        };
+        let sound = if self.launch.options.headless { SoundPlugin::null() } else { SoundPlugin::default() };
+        let background = if self.launch.options.headless { BackgroundPlugin::headless() } else { BackgroundPlugin::default() };
+
         app.add_state::<GameState>()
-            .add_plugins((BackgroundPlugin, PlayerPlugin, FoePlugin))
-            .add_systems(Startup, setup)                        
-            .add_systems(OnEnter(GameState::GameOver), setup_gameover)
-            .add_systems(Update, check_to_reset.run_if(in_state(GameState::GameOver)))
+            .insert_resource(self.launch.start)
+            .add_plugins((
+                SettingsPlugin::default(),
+                config,
+                LoadingPlugin,
+                LevelPlugin,
+                SpritePlugin,
+                sound,
+                ReplayPlugin,
+                ParticlePlugin,
+                FeedbackPlugin,
+                background,
+                PlayerPlugin,
+                FoePlugin,
+                HudPlugin,
+                StatsPlugin,
+                GameOverPlugin
+            ))
+            .add_plugins((
+                DifficultyPlugin,
+                EndlessPlugin,
+                DespawnPlugin,
+                ProjectilePlugin,
+                DamagePlugin,
+                FormationPlugin,
+                GroundPlugin,
+                LeaderboardPlugin::default(),
+                NetPlugin,
+                LaunchPlugin { options: self.launch.options.clone() }
+            ))
+            .add_systems(Startup, setup)
+            .add_systems(OnEnter(GameState::Menu), setup_menu)
+            .add_systems(
+                Update,
+                (select_run_options, check_to_start)
+                    .run_if(in_state(GameState::Menu))
+                    .run_if(not(resource_exists::<NetConnecting>()))
+            )
+            .add_systems(OnExit(GameState::Menu), teardown)
             .add_systems(OnExit(GameState::GameOver), teardown);
+
+        #[cfg(feature = "dev")]
+        app.add_plugins(DebugPlugin);
+    }
+}
+
+fn window_plugin(cli: &Cli) -> WindowPlugin {
+    let resolution = cli.resolution
+        .map(|resolution| (resolution.width as f32, resolution.height as f32))
+        .unwrap_or((WINDOW_WIDTH, WINDOW_HEIGHT));
+
+    WindowPlugin {
+        primary_window: Some(Window {
+            title: "Space shooter".to_string(),
+            resolution: resolution.into(),
+            position: WindowPosition::Centered(MonitorSelection::Primary),
+            mode: if cli.fullscreen { WindowMode::BorderlessFullscreen } else { WindowMode::Windowed },
+            focused: true,
+            resizable: false,
+            visible: true,
+            window_theme: Some(WindowTheme::Dark),
+            ..default()
+        }),
+        ..default()
     }
 }
 
 fn main() {
-    App::new()
-        .add_plugins((
-            DefaultPlugins.set(WindowPlugin {
-                primary_window: Some(Window {
-                    title: "Space shooter".to_string(),
-                    resolution: (WINDOW_WIDTH, WINDOW_HEIGHT).into(),
-                    position: WindowPosition::Centered(MonitorSelection::Primary),
-                    mode: WindowMode::Windowed,
-                    focused: true,
-                    resizable: false,
-                    visible: true,
-                    window_theme: Some(WindowTheme::Dark),                    
-                    ..default()
-                }),
-                ..default()
-            })
-            .set(ImagePlugin::default_nearest()), 
-            SpaceShooterPlugin
+    let cli = Cli::parse();
+    let launch = cli.validate().unwrap_or_else(|error| {
+        Cli::command().error(clap::error::ErrorKind::ValueValidation, error).exit()
+    });
+    let playback = launch.playback.clone();
+    let net = launch.net.map(|net| {
+        let connecting = match net {
+            NetLaunch::Host(port) => UdpTransport::host(port).map(|transport| NetConnecting::new(NetRole::Host, transport)),
+            NetLaunch::Join(address) => UdpTransport::join(address).map(|transport| NetConnecting::new(NetRole::Join, transport))
+        };
+        connecting.unwrap_or_else(|error| {
+            Cli::command().error(clap::error::ErrorKind::Io, format!("could not open a UDP socket: {}", error)).exit()
+        })
+    });
+
+    let mut app = App::new();
+
+    if cli.headless {
+        // An online run cannot go faster than its peer, so it keeps to the tick rate
+        let wait = if net.is_some() { Duration::from_secs_f64(1.0 / 64.0) } else { Duration::ZERO };
+
+        // Fixed ticks advance as fast as the machine allows, without a window, a GPU, audio or gamepads
+        app.add_plugins((
+            DefaultPlugins
+                .set(WindowPlugin {
+                    primary_window: None,
+                    exit_condition: ExitCondition::DontExit,
+                    close_when_requested: false
+                })
+                .set(RenderPlugin {
+                    render_creation: WgpuSettings { backends: None, ..default() }.into()
+                })
+                .disable::<WinitPlugin>()
+                .disable::<AudioPlugin>()
+                .disable::<GilrsPlugin>(),
+            ScheduleRunnerPlugin::run_loop(wait)
         ))
-        .run()
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(1.0 / 64.0)));
+    } else {
+        app.add_plugins(DefaultPlugins.set(window_plugin(&cli)).set(ImagePlugin::default_nearest()));
+    }
+
+    app.add_plugins(SpaceShooterPlugin { launch });
+
+    if let Some(replay) = playback {
+        app.insert_resource(ReplayPlayback::new(replay));
+    }
+
+    if let Some(connecting) = net {
+        app.insert_resource(connecting);
+    }
+
+    app.run()
 }
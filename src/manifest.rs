@@ -0,0 +1,145 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use bevy::asset::io::file::FileAssetReader;
+
+/// Logical names of every file shipped in `assets`, so loaders never spell out paths themselves
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AssetName {
+    BackgroundLayer1,
+    BackgroundLayer2,
+    Backgrounds,
+    ShipSprites,
+    ProjectileSprites,
+    MiscellaneousSprites,
+    UiSprites,
+    CharacterSprites,
+    PlayerShotSound,
+    FoeHitSound,
+    FoeKilledSound,
+    PlayerHitSound,
+    PickupSound,
+    BombSound,
+    WaveStartSound,
+    Stage1Music,
+    Stage2Music,
+    Level1,
+    Level2,
+    GameConfig
+}
+
+impl AssetName {
+    pub const ALL: [AssetName; 20] = [
+        AssetName::BackgroundLayer1,
+        AssetName::BackgroundLayer2,
+        AssetName::Backgrounds,
+        AssetName::ShipSprites,
+        AssetName::ProjectileSprites,
+        AssetName::MiscellaneousSprites,
+        AssetName::UiSprites,
+        AssetName::CharacterSprites,
+        AssetName::PlayerShotSound,
+        AssetName::FoeHitSound,
+        AssetName::FoeKilledSound,
+        AssetName::PlayerHitSound,
+        AssetName::PickupSound,
+        AssetName::BombSound,
+        AssetName::WaveStartSound,
+        AssetName::Stage1Music,
+        AssetName::Stage2Music,
+        AssetName::Level1,
+        AssetName::Level2,
+        AssetName::GameConfig
+    ];
+
+    /// Path relative to the asset folder, always with forward slashes
+    pub fn path(&self) -> &'static str {
+        match self {
+            AssetName::BackgroundLayer1 => "textures/background_layer1.png",
+            AssetName::BackgroundLayer2 => "textures/background_layer2.png",
+            AssetName::Backgrounds => "textures/SpaceShooterAssetPack_BackGrounds.png",
+            AssetName::ShipSprites => "textures/SpaceShooterAssetPack_Ships.png",
+            AssetName::ProjectileSprites => "textures/SpaceShooterAssetPack_Projectiles.png",
+            AssetName::MiscellaneousSprites => "textures/SpaceShooterAssetPack_Miscellaneous.png",
+            AssetName::UiSprites => "textures/SpaceShooterAssetPack_IU.png",
+            AssetName::CharacterSprites => "textures/SpaceShooterAssetPack_Characters.png",
+            AssetName::PlayerShotSound => "audio/player_shot.wav",
+            AssetName::FoeHitSound => "audio/foe_hit.wav",
+            AssetName::FoeKilledSound => "audio/foe_killed.wav",
+            AssetName::PlayerHitSound => "audio/player_hit.wav",
+            AssetName::PickupSound => "audio/pickup.wav",
+            AssetName::BombSound => "audio/bomb.wav",
+            AssetName::WaveStartSound => "audio/wave_start.wav",
+            AssetName::Stage1Music => "audio/stage1.wav",
+            AssetName::Stage2Music => "audio/stage2.wav",
+            AssetName::Level1 => "levels/level1.ron",
+            AssetName::Level2 => "levels/level2.ron",
+            AssetName::GameConfig => "config.ron"
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ManifestError {
+    InvalidPath { name: AssetName, path: &'static str },
+    Missing { name: AssetName, path: &'static str },
+    Misnamed { name: AssetName, path: &'static str, found: String }
+}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ManifestError::InvalidPath { name, path } =>
+                write!(f, "{:?}: '{}' must be relative and use forward slashes", name, path),
+            ManifestError::Missing { name, path } =>
+                write!(f, "{:?}: '{}' does not exist", name, path),
+            ManifestError::Misnamed { name, path, found } =>
+                write!(f, "{:?}: '{}' does not exist, but '{}' does (check the letter case)", name, path, found)
+        }
+    }
+}
+
+/// Folder assets are read from, resolved the same way as Bevy's file asset reader
+pub fn asset_root() -> PathBuf {
+    FileAssetReader::get_base_path().join("assets")
+}
+
+/// Checks every manifest entry against the files under `root`
+pub fn validate_manifest(root: &Path) -> Vec<ManifestError> {
+    AssetName::ALL.iter()
+        .filter_map(|name| validate_entry(root, *name, name.path()))
+        .collect()
+}
+
+/// Checks that `path`, the file of `name`, is written the portable way and exists under `root`
+pub fn validate_entry(root: &Path, name: AssetName, path: &'static str) -> Option<ManifestError> {
+    if path.contains('\\') || path.starts_with('/') {
+        return Some(ManifestError::InvalidPath { name, path });
+    }
+
+    if root.join(path).is_file() {
+        return None;
+    }
+
+    match find_case_insensitive(root, path) {
+        Some(found) => Some(ManifestError::Misnamed { name, path, found }),
+        None => Some(ManifestError::Missing { name, path })
+    }
+}
+
+fn find_case_insensitive(root: &Path, path: &str) -> Option<String> {
+    let mut current = root.to_path_buf();
+    let mut found = Vec::new();
+
+    for part in path.split('/') {
+        let entry = fs::read_dir(&current).ok()?
+            .filter_map(|entry| entry.ok())
+            .find(|entry| entry.file_name().to_string_lossy().eq_ignore_ascii_case(part))?;
+
+        current.push(entry.file_name());
+        found.push(entry.file_name().to_string_lossy().into_owned());
+    }
+
+    Some(found.join("/"))
+}
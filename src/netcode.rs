@@ -0,0 +1,742 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::io;
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::{Arc, Mutex};
+
+use bevy::prelude::*;
+use bevy::time::TimeSystem;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::constants::*;
+use crate::events::*;
+use crate::replay::*;
+use crate::resources::*;
+use crate::snapshot::*;
+use crate::states::*;
+
+/// Online co-op with rollback: the remote input of a tick is predicted until it arrives,
+/// the ticks it was guessed wrong for are then rewound and simulated again
+pub struct NetPlugin;
+
+impl Plugin for NetPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NetReport>()
+            .add_systems(Update, connect.run_if(resource_exists::<NetConnecting>()).run_if(in_state(GameState::Menu)))
+            .add_systems(
+                First,
+                sync_session
+                    .before(TimeSystem)
+                    .run_if(resource_exists::<NetSession>())
+                    .run_if(in_state(GameState::Playing))
+            )
+            .add_systems(
+                FixedUpdate,
+                begin_net_tick
+                    .in_set(PlayerInputSet)
+                    .run_if(resource_exists::<NetSession>())
+                    .run_if(in_state(GameState::Playing))
+            )
+            .add_systems(OnEnter(GameState::GameOver), end_session);
+    }
+
+    fn finish(&self, app: &mut App) {
+        require::<StartOptions, ReplayPlugin>(app);
+        require::<PlayerInputs, ReplayPlugin>(app);
+    }
+}
+
+/// Present while ticks that were already played are simulated again after a rollback
+#[derive(Resource)]
+pub struct Resimulating;
+
+/// Sends and receives the packets of a session, whatever carries them
+pub trait Transport: Send + Sync + 'static {
+    fn send(&mut self, packet: &[u8]);
+
+    /// Packets that arrived since the last call
+    fn receive(&mut self) -> Vec<Vec<u8>>;
+}
+
+/// UDP socket talking to a single peer, the host learns its address from the first packet
+pub struct UdpTransport {
+    socket: UdpSocket,
+    peer: Option<SocketAddr>
+}
+
+impl UdpTransport {
+    pub fn host(port: u16) -> io::Result<Self> {
+        let socket = UdpSocket::bind(("0.0.0.0", port))?;
+        socket.set_nonblocking(true)?;
+        Ok(Self { socket, peer: None })
+    }
+
+    pub fn join(address: SocketAddr) -> io::Result<Self> {
+        let socket = UdpSocket::bind(("0.0.0.0", 0))?;
+        socket.set_nonblocking(true)?;
+        Ok(Self { socket, peer: Some(address) })
+    }
+}
+
+impl Transport for UdpTransport {
+    fn send(&mut self, packet: &[u8]) {
+        let Some(peer) = self.peer else { return };
+
+        // Lost packets are resent by the protocol, so errors are only worth a trace
+        if let Err(error) = self.socket.send_to(packet, peer) {
+            debug!("Could not send a packet to {}: {}", peer, error);
+        }
+    }
+
+    fn receive(&mut self) -> Vec<Vec<u8>> {
+        let mut packets = Vec::new();
+        let mut buffer = [0u8; NET_MAX_PACKET_SIZE];
+
+        while let Ok((size, from)) = self.socket.recv_from(&mut buffer) {
+            let peer = *self.peer.get_or_insert(from);
+            if from == peer {
+                packets.push(buffer[..size].to_vec());
+            }
+        }
+
+        packets
+    }
+}
+
+/// How badly a loopback link treats the packets going through it
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LinkConditions {
+    /// Polls of the receiving side a packet takes to arrive
+    pub latency: u32,
+    /// Extra polls added at random to each packet, which can reorder them
+    pub jitter: u32,
+    /// Chance of each packet being dropped, between 0.0 and 1.0
+    pub loss: f32
+}
+
+struct InFlight {
+    packet: Vec<u8>,
+    polls_left: u32
+}
+
+/// In-process link between two sessions, delaying and dropping packets like a real network would
+pub struct LoopbackTransport {
+    outgoing: Arc<Mutex<Vec<InFlight>>>,
+    incoming: Arc<Mutex<Vec<InFlight>>>,
+    conditions: LinkConditions,
+    rng: StdRng
+}
+
+impl LoopbackTransport {
+    /// Both ends of a link, the seed makes the losses and delays repeatable
+    pub fn pair(conditions: LinkConditions, seed: u64) -> (Self, Self) {
+        let first = Arc::new(Mutex::new(Vec::new()));
+        let second = Arc::new(Mutex::new(Vec::new()));
+
+        (
+            Self { outgoing: first.clone(), incoming: second.clone(), conditions, rng: StdRng::seed_from_u64(seed) },
+            Self { outgoing: second, incoming: first, conditions, rng: StdRng::seed_from_u64(seed.wrapping_add(1)) }
+        )
+    }
+}
+
+impl Transport for LoopbackTransport {
+    fn send(&mut self, packet: &[u8]) {
+        if self.rng.gen::<f32>() < self.conditions.loss { return }
+
+        let jitter = self.rng.gen_range(0..=self.conditions.jitter);
+        self.outgoing.lock().unwrap().push(InFlight {
+            packet: packet.to_vec(),
+            polls_left: self.conditions.latency + jitter
+        });
+    }
+
+    fn receive(&mut self) -> Vec<Vec<u8>> {
+        let mut incoming = self.incoming.lock().unwrap();
+        let mut packets = Vec::new();
+
+        incoming.retain_mut(|in_flight| {
+            if in_flight.polls_left == 0 {
+                packets.push(std::mem::take(&mut in_flight.packet));
+                return false;
+            }
+            in_flight.polls_left -= 1;
+            true
+        });
+
+        packets
+    }
+}
+
+/// Everything two peers tell each other
+#[derive(Debug, Clone, PartialEq)]
+pub enum NetMessage {
+    /// Sent by the joining peer until the run starts
+    Hello,
+    /// Sent by the host in reply, with how the run starts
+    Start(StartOptions),
+    /// Local input of every tick from `first_tick` on, with how much remote input the sender has
+    Inputs {
+        first_tick: u32,
+        inputs: Vec<PlayerInput>,
+        /// Ticks of the receiver input the sender got so far
+        received: u32,
+        /// Gameplay checksum of a confirmed tick, checked against the receiver own
+        checksum: Option<(u32, u64)>
+    }
+}
+
+const MESSAGE_HELLO: u8 = 0;
+const MESSAGE_START: u8 = 1;
+const MESSAGE_INPUTS: u8 = 2;
+
+impl NetMessage {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = vec![NET_PROTOCOL_VERSION];
+
+        match self {
+            NetMessage::Hello => bytes.push(MESSAGE_HELLO),
+            NetMessage::Start(start) => {
+                bytes.push(MESSAGE_START);
+                bytes.extend(start.seed.unwrap_or_default().to_le_bytes());
+                bytes.extend((start.level as u32).to_le_bytes());
+                bytes.extend(start.wave.to_le_bytes());
+                bytes.push(Difficulty::ALL.iter().position(|difficulty| *difficulty == start.difficulty).unwrap_or(0) as u8);
+                bytes.push(start.rank as u8);
+                bytes.push(start.mode as u8);
+                bytes.push(start.continues as u8);
+            },
+            NetMessage::Inputs { first_tick, inputs, received, checksum } => {
+                bytes.push(MESSAGE_INPUTS);
+                bytes.extend(first_tick.to_le_bytes());
+                bytes.extend(received.to_le_bytes());
+                bytes.extend((inputs.len() as u16).to_le_bytes());
+                bytes.extend(inputs.iter().map(input_bits));
+
+                if let Some((tick, checksum)) = checksum {
+                    bytes.extend(tick.to_le_bytes());
+                    bytes.extend(checksum.to_le_bytes());
+                }
+            }
+        }
+
+        bytes
+    }
+
+    /// Reads a packet, `None` for anything that is not a message of this protocol version
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        let mut reader = PacketReader(bytes);
+        if reader.u8()? != NET_PROTOCOL_VERSION { return None }
+
+        let message = match reader.u8()? {
+            MESSAGE_HELLO => NetMessage::Hello,
+            MESSAGE_START => NetMessage::Start(StartOptions {
+                seed: Some(reader.u64()?),
+                level: reader.u32()? as usize,
+                wave: reader.u32()?,
+                difficulty: *Difficulty::ALL.get(reader.u8()? as usize)?,
+                rank: match reader.u8()? {
+                    0 => false,
+                    1 => true,
+                    _ => return None
+                },
+                mode: match reader.u8()? {
+                    0 => GameMode::Campaign,
+                    1 => GameMode::Endless,
+                    _ => return None
+                },
+                co_op: true,
+                continues: match reader.u8()? {
+                    0 => Continues::Separate,
+                    1 => Continues::Shared,
+                    _ => return None
+                }
+            }),
+            MESSAGE_INPUTS => {
+                let first_tick = reader.u32()?;
+                let received = reader.u32()?;
+                let count = reader.u16()? as usize;
+                let inputs = (0..count).map(|_| reader.u8().map(input_from_bits)).collect::<Option<Vec<_>>>()?;
+                let checksum = match reader.0.is_empty() {
+                    true => None,
+                    false => Some((reader.u32()?, reader.u64()?))
+                };
+
+                NetMessage::Inputs { first_tick, inputs, received, checksum }
+            },
+            _ => return None
+        };
+
+        reader.0.is_empty().then_some(message)
+    }
+}
+
+struct PacketReader<'a>(&'a [u8]);
+
+impl PacketReader<'_> {
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        if self.0.len() < N { return None }
+
+        let (bytes, rest) = self.0.split_at(N);
+        self.0 = rest;
+        bytes.try_into().ok()
+    }
+
+    fn u8(&mut self) -> Option<u8> { self.take::<1>().map(|bytes| bytes[0]) }
+    fn u16(&mut self) -> Option<u16> { self.take().map(u16::from_le_bytes) }
+    fn u32(&mut self) -> Option<u32> { self.take().map(u32::from_le_bytes) }
+    fn u64(&mut self) -> Option<u64> { self.take().map(u64::from_le_bytes) }
+}
+
+fn input_bits(input: &PlayerInput) -> u8 {
+    [input.left, input.right, input.up, input.down, input.fire, input.bomb, input.focus]
+        .iter()
+        .enumerate()
+        .fold(0, |bits, (bit, held)| bits | ((*held as u8) << bit))
+}
+
+fn input_from_bits(bits: u8) -> PlayerInput {
+    let held = |bit: u8| bits & (1 << bit) != 0;
+
+    PlayerInput {
+        left: held(0),
+        right: held(1),
+        up: held(2),
+        down: held(3),
+        fire: held(4),
+        bomb: held(5),
+        focus: held(6),
+        bomb_pressed: false
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetRole {
+    /// Picks how the run starts and plays the first ship
+    Host,
+    /// Plays the second ship of the run the host starts
+    Join
+}
+
+/// Present in the menu while waiting for the peer, the run starts once both agree on how
+#[derive(Resource)]
+pub struct NetConnecting {
+    pub role: NetRole,
+    transport: Box<dyn Transport>,
+    hello: Timer
+}
+
+impl NetConnecting {
+    pub fn new(role: NetRole, transport: impl Transport) -> Self {
+        Self {
+            role,
+            transport: Box::new(transport),
+            hello: Timer::from_seconds(NET_HELLO_INTERVAL, TimerMode::Repeating)
+        }
+    }
+}
+
+/// How the sessions of this launch went, kept once they are over
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq)]
+pub struct NetReport {
+    pub rollbacks: u32,
+    /// Ticks simulated again because of rollbacks
+    pub resimulated_ticks: u32,
+    /// Confirmed ticks whose checksum was compared with the peer one
+    pub checked_ticks: u32,
+    pub desyncs: u32
+}
+
+/// Online run being played, with the input of both players and the snapshots to roll back to
+#[derive(Resource)]
+pub struct NetSession {
+    pub local_player: usize,
+    transport: Box<dyn Transport>,
+    /// The host answers a lost start message with the same options
+    start: Option<StartOptions>,
+    /// Next tick to simulate
+    pub tick: u32,
+    local_inputs: Vec<PlayerInput>,
+    /// Remote input of every tick up to the first one that did not arrive yet
+    remote_inputs: Vec<PlayerInput>,
+    /// Remote input every simulated tick was played with, guessed for those it had not arrived for
+    played_remote: Vec<PlayerInput>,
+    /// Ticks of local input the peer confirmed receiving
+    peer_received: u32,
+    /// Earliest tick whose remote input turned out to be guessed wrong
+    rollback_from: Option<u32>,
+    /// Tick the first snapshot was taken at, the next ones follow in order
+    snapshot_base: u32,
+    snapshots: VecDeque<GameSnapshot>,
+    next_check: u32,
+    local_checksums: BTreeMap<u32, u64>,
+    remote_checksums: BTreeMap<u32, u64>,
+    last_checksum: Option<(u32, u64)>,
+    /// Ticks already written to the replay recorder, only confirmed ones are
+    recorded: u32,
+    /// Tick the game ended on, the game over waits until it is confirmed
+    game_over_tick: Option<u32>,
+    stalled: bool,
+    silence: f32
+}
+
+impl NetSession {
+    pub fn new(local_player: usize, transport: impl Transport) -> Self {
+        Self::with_transport(local_player, Box::new(transport))
+    }
+
+    fn with_transport(local_player: usize, transport: Box<dyn Transport>) -> Self {
+        Self {
+            local_player,
+            transport,
+            start: None,
+            tick: 0,
+            local_inputs: Vec::new(),
+            remote_inputs: Vec::new(),
+            played_remote: Vec::new(),
+            peer_received: 0,
+            rollback_from: None,
+            snapshot_base: 0,
+            snapshots: VecDeque::new(),
+            next_check: 0,
+            local_checksums: BTreeMap::new(),
+            remote_checksums: BTreeMap::new(),
+            last_checksum: None,
+            recorded: 0,
+            game_over_tick: None,
+            stalled: false,
+            silence: 0.0
+        }
+    }
+
+    /// Ticks whose input of both players is known
+    pub fn confirmed_ticks(&self) -> u32 {
+        (self.remote_inputs.len() as u32).min(self.tick)
+    }
+
+    fn snapshot(&self, tick: u32) -> Option<&GameSnapshot> {
+        self.snapshots.get(tick.checked_sub(self.snapshot_base)? as usize)
+    }
+
+    fn receive(&mut self, report: &mut NetReport) {
+        for packet in self.transport.receive() {
+            let Some(message) = NetMessage::decode(&packet) else { continue };
+            self.silence = 0.0;
+
+            match message {
+                NetMessage::Hello => {
+                    // The joining peer missed the start message
+                    if let Some(start) = self.start {
+                        self.transport.send(&NetMessage::Start(start).encode());
+                    }
+                },
+                NetMessage::Start(_) => (),
+                NetMessage::Inputs { first_tick, inputs, received, checksum } => {
+                    self.peer_received = self.peer_received.max(received.min(self.local_inputs.len() as u32));
+
+                    for (offset, input) in inputs.into_iter().enumerate() {
+                        let tick = first_tick + offset as u32;
+                        if tick as usize != self.remote_inputs.len() { continue }
+
+                        self.remote_inputs.push(input);
+                        if self.played_remote.get(tick as usize).is_some_and(|played| *played != input) {
+                            self.rollback_from = Some(self.rollback_from.map_or(tick, |from| from.min(tick)));
+                        }
+                    }
+
+                    if let Some((tick, checksum)) = checksum {
+                        self.remote_checksums.insert(tick, checksum);
+                    }
+                }
+            }
+        }
+
+        self.compare_checksums(report);
+    }
+
+    fn compare_checksums(&mut self, report: &mut NetReport) {
+        let common: Vec<u32> = self.remote_checksums.keys()
+            .filter(|tick| self.local_checksums.contains_key(tick))
+            .copied()
+            .collect();
+
+        for tick in common {
+            let local = self.local_checksums.remove(&tick);
+            let remote = self.remote_checksums.remove(&tick);
+            report.checked_ticks += 1;
+
+            if local != remote {
+                report.desyncs += 1;
+                error!("Desync with the peer at tick {}: {:016x} here, {:016x} there", tick, local.unwrap_or_default(), remote.unwrap_or_default());
+            }
+        }
+    }
+
+    fn send_inputs(&mut self) {
+        let first_tick = self.peer_received;
+        let last_tick = self.local_inputs.len().min(first_tick as usize + NET_MAX_PACKET_INPUTS);
+
+        let message = NetMessage::Inputs {
+            first_tick,
+            inputs: self.local_inputs[first_tick as usize..last_tick].to_vec(),
+            received: self.remote_inputs.len() as u32,
+            checksum: self.last_checksum
+        };
+        self.transport.send(&message.encode());
+    }
+}
+
+fn connect(
+    mut commands: Commands,
+    mut connecting: ResMut<NetConnecting>,
+    mut start_options: ResMut<StartOptions>,
+    mut next_state: ResMut<NextState<GameState>>,
+    time: Res<Time<Real>>
+) {
+    let role = connecting.role;
+
+    if role == NetRole::Join && connecting.hello.tick(time.delta()).just_finished() {
+        connecting.transport.send(&NetMessage::Hello.encode());
+    }
+
+    let messages: Vec<NetMessage> = connecting.transport.receive().iter().filter_map(|packet| NetMessage::decode(packet)).collect();
+
+    let start = match role {
+        NetRole::Host => {
+            if !messages.contains(&NetMessage::Hello) { return }
+
+            // Both peers need the same seed, so the host picks it now if it was not given
+            let start = StartOptions {
+                seed: Some(start_options.seed.unwrap_or_else(|| rand::thread_rng().gen())),
+                co_op: true,
+                ..*start_options
+            };
+            connecting.transport.send(&NetMessage::Start(start).encode());
+            start
+        },
+        NetRole::Join => {
+            let Some(start) = messages.into_iter().find_map(|message| match message {
+                NetMessage::Start(start) => Some(start),
+                _ => None
+            }) else { return };
+            start
+        }
+    };
+
+    let local_player = match role {
+        NetRole::Host => 0,
+        NetRole::Join => 1
+    };
+    let transport = std::mem::replace(&mut connecting.transport, Box::new(NullTransport));
+    commands.remove_resource::<NetConnecting>();
+
+    let mut session = NetSession::with_transport(local_player, transport);
+    if role == NetRole::Host {
+        session.start = Some(start);
+    }
+
+    info!("Connected, playing as player {}", local_player + 1);
+    *start_options = start;
+    commands.insert_resource(session);
+    next_state.set(GameState::Playing);
+}
+
+/// Placeholder left behind where a transport was moved out of
+struct NullTransport;
+
+impl Transport for NullTransport {
+    fn send(&mut self, _packet: &[u8]) {}
+
+    fn receive(&mut self) -> Vec<Vec<u8>> { Vec::new() }
+}
+
+/// Saves the state the tick starts from, then plays it with the local input and the best guess of the remote one
+fn begin_net_tick(world: &mut World) {
+    let snapshot = GameSnapshot::capture(world);
+    let keyboard_input = world.get_resource::<Input<KeyCode>>()
+        .map(|keyboard_input| PlayerInput::from_keyboard(keyboard_input, &InputMap::PLAYER_ONE))
+        .unwrap_or_default();
+
+    let mut session = world.resource_mut::<NetSession>();
+    let tick = session.tick as usize;
+
+    // Replayed ticks keep the input they were first played with
+    if tick == session.local_inputs.len() {
+        session.local_inputs.push(keyboard_input);
+    }
+
+    // The remote player is guessed to keep holding what they held last
+    let remote = session.remote_inputs.get(tick)
+        .or(session.remote_inputs.last())
+        .copied()
+        .unwrap_or_default();
+    session.played_remote.truncate(tick);
+    session.played_remote.push(remote);
+
+    if session.snapshots.is_empty() {
+        session.snapshot_base = tick as u32;
+    }
+    let kept = tick - session.snapshot_base as usize;
+    session.snapshots.truncate(kept);
+    session.snapshots.push_back(snapshot);
+
+    let mut held = [PlayerInput::default(); MAX_PLAYERS];
+    held[session.local_player] = session.local_inputs[tick];
+    held[1 - session.local_player] = remote;
+    session.tick += 1;
+
+    let mut inputs = world.resource_mut::<PlayerInputs>();
+    for (input, held) in inputs.0.iter_mut().zip(held) {
+        *input = input.next(held);
+    }
+}
+
+/// Exchanges input with the peer, rolls back to the first tick guessed wrong and checks both peers still agree
+fn sync_session(world: &mut World) {
+    let real_delta = world.resource::<Time<Real>>().delta_seconds();
+
+    world.resource_scope(|world, mut session: Mut<NetSession>| {
+        world.resource_scope(|world, mut report: Mut<NetReport>| {
+            session.receive(&mut report);
+
+            // A game over is only decided once the tick it happened on is confirmed
+            let game_over = world.resource::<NextState<GameState>>().0 == Some(GameState::GameOver);
+            if game_over {
+                world.resource_mut::<NextState<GameState>>().0 = None;
+                let last_tick = session.tick.saturating_sub(1);
+                session.game_over_tick.get_or_insert(last_tick);
+            }
+
+            if let Some(from) = session.rollback_from.take() {
+                roll_back(world, &mut session, &mut report, from);
+            }
+
+            record_confirmed_inputs(world, &mut session);
+            take_checksums(&mut session);
+            session.compare_checksums(&mut report);
+
+            if session.game_over_tick.is_some_and(|tick| tick < session.confirmed_ticks()) {
+                world.resource_mut::<NextState<GameState>>().set(GameState::GameOver);
+            }
+
+            // The peer is gone, the run cannot go on without them
+            session.silence += real_delta;
+            if session.silence > NET_TIMEOUT {
+                error!("Lost the connection to the peer");
+                world.resource_mut::<NextState<GameState>>().set(GameState::GameOver);
+            }
+
+            // Waits for the peer instead of guessing too far ahead of what it sent
+            let ahead = session.tick.saturating_sub(session.remote_inputs.len() as u32);
+            let stall = ahead >= NET_MAX_PREDICTION;
+            if stall != session.stalled {
+                session.stalled = stall;
+                let mut virtual_time = world.resource_mut::<Time<Virtual>>();
+                if stall { virtual_time.pause() } else { virtual_time.unpause() }
+            }
+
+            session.send_inputs();
+        });
+    });
+}
+
+fn roll_back(world: &mut World, session: &mut NetSession, report: &mut NetReport, from: u32) {
+    let Some(snapshot) = session.snapshot(from).cloned() else {
+        error!("Cannot roll back to tick {}, its snapshot is gone", from);
+        return;
+    };
+
+    let last_tick = session.tick;
+    snapshot.restore(world);
+    session.tick = from;
+    session.game_over_tick = session.game_over_tick.filter(|tick| *tick < from);
+
+    report.rollbacks += 1;
+    report.resimulated_ticks += last_tick - from;
+
+    // The session goes back in the world for the ticks played again to reach it
+    world.insert_resource(Resimulating);
+    world.insert_resource(std::mem::replace(session, NetSession::new(0, NullTransport)));
+
+    let fixed_time = world.resource::<Time<Fixed>>().as_generic();
+    *world.resource_mut::<Time>() = fixed_time;
+    let _ = world.try_schedule_scope(FixedUpdate, |world, schedule| {
+        while world.resource::<NetSession>().tick < last_tick {
+            schedule.run(world);
+        }
+    });
+    *world.resource_mut::<Time>() = world.resource::<Time<Virtual>>().as_generic();
+
+    *session = world.remove_resource::<NetSession>().unwrap();
+    world.remove_resource::<Resimulating>();
+
+    if world.resource::<NextState<GameState>>().0 == Some(GameState::GameOver) {
+        world.resource_mut::<NextState<GameState>>().0 = None;
+        session.game_over_tick.get_or_insert(last_tick - 1);
+    }
+
+    // The replayed ticks already showed their effects when they were first played
+    clear_events::<FoeHitEvent>(world);
+    clear_events::<FoeKilledEvent>(world);
+    clear_events::<PlayerHitEvent>(world);
+    clear_events::<PlayerShotEvent>(world);
+    clear_events::<GrazeEvent>(world);
+    clear_events::<SquadronDestroyedEvent>(world);
+    clear_events::<BombEvent>(world);
+    clear_events::<WaveStartEvent>(world);
+    clear_events::<WarningEvent>(world);
+    clear_events::<MilestoneEvent>(world);
+}
+
+fn clear_events<E: Event>(world: &mut World) {
+    if let Some(mut events) = world.get_resource_mut::<Events<E>>() {
+        events.clear();
+    }
+}
+
+/// Writes the ticks whose input of both players is known to the replay being recorded
+fn record_confirmed_inputs(world: &mut World, session: &mut NetSession) {
+    let confirmed = session.confirmed_ticks();
+    let Some(mut recorder) = world.get_resource_mut::<ReplayRecorder>() else { return };
+
+    for tick in session.recorded..confirmed {
+        let local = session.local_inputs[tick as usize];
+        let remote = session.remote_inputs[tick as usize];
+        let (first, second) = if session.local_player == 0 { (local, remote) } else { (remote, local) };
+
+        recorder.0.inputs.push(first);
+        recorder.0.second_inputs.push(second);
+    }
+    session.recorded = confirmed;
+}
+
+/// Checksums the confirmed ticks due for a check and drops the snapshots no rollback can need anymore
+fn take_checksums(session: &mut NetSession) {
+    let confirmed = session.confirmed_ticks();
+
+    // The state a tick starts from is final once the input of every tick before it is known
+    while session.next_check <= confirmed && session.next_check < session.tick {
+        let tick = session.next_check;
+        session.next_check += 1;
+        if !tick.is_multiple_of(NET_CHECK_INTERVAL) { continue }
+
+        if let Some(checksum) = session.snapshot(tick).map(GameSnapshot::checksum) {
+            session.local_checksums.insert(tick, checksum);
+            session.last_checksum = Some((tick, checksum));
+        }
+    }
+
+    let keep_from = confirmed.min(session.next_check);
+    while session.snapshot_base < keep_from && !session.snapshots.is_empty() {
+        session.snapshots.pop_front();
+        session.snapshot_base += 1;
+    }
+}
+
+fn end_session(world: &mut World) {
+    let Some(session) = world.remove_resource::<NetSession>() else { return };
+
+    if session.stalled {
+        world.resource_mut::<Time<Virtual>>().unpause();
+    }
+}
@@ -0,0 +1,338 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::constants::*;
+use crate::events::*;
+use crate::pool::*;
+use crate::resources::*;
+use crate::sprites::*;
+
+pub struct ParticlePlugin;
+
+impl Plugin for ParticlePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ParticlePool>()
+            .add_event::<ParticleBurstEvent>()
+            .add_event::<FoeHitEvent>()
+            .add_event::<FoeKilledEvent>()
+            .add_event::<GrazeEvent>()
+            .add_event::<SquadronDestroyedEvent>()
+            .add_systems(
+                Update,
+                (
+                    burst_on_foe_hit,
+                    burst_on_foe_killed,
+                    burst_on_graze,
+                    burst_on_squadron_destroyed,
+                    spawn_particle_bursts,
+                    update_emitters,
+                    update_particles
+                ).chain()
+            )
+            .add_systems(Last, forget_despawned_particles);
+    }
+}
+
+/// Everything a particle needs to know to live out its lifetime
+#[derive(Debug, Clone)]
+pub struct ParticleSettings {
+    /// Particles per second for continuous emitters
+    pub spawn_rate: f32,
+    /// Particles spawned at once for bursts
+    pub burst: usize,
+    pub lifetime: f32,
+    pub direction: Vec2,
+    /// Half angle of the velocity cone around `direction`, in radians
+    pub spread: f32,
+    pub speed: (f32, f32),
+    pub gravity: Vec2,
+    pub color: (Color, Color),
+    pub size: (f32, f32),
+    /// First sprite and how many following atlas frames play over the lifetime
+    pub sprite: SpriteName,
+    pub frames: usize
+}
+
+/// Built-in effects, tuned for the game sprite scale
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ParticleEffect {
+    Explosion,
+    ImpactSpark,
+    ThrustTrail,
+    PickupGlint
+}
+
+impl ParticleEffect {
+    pub fn settings(&self) -> ParticleSettings {
+        match self {
+            ParticleEffect::Explosion => ParticleSettings {
+                spawn_rate: 0.0,
+                burst: 24,
+                lifetime: 0.45,
+                direction: Vec2::Y,
+                spread: PI,
+                speed: (60.0, 220.0),
+                gravity: Vec2::ZERO,
+                color: (Color::rgb(1.0, 0.9, 0.5), Color::rgba(1.0, 0.2, 0.0, 0.0)),
+                size: (4.0, 1.5),
+                sprite: SpriteName::Explosion,
+                frames: 3
+            },
+            ParticleEffect::ImpactSpark => ParticleSettings {
+                spawn_rate: 0.0,
+                burst: 6,
+                lifetime: 0.18,
+                direction: Vec2::NEG_Y,
+                spread: PI * 0.35,
+                speed: (120.0, 260.0),
+                gravity: Vec2::new(0.0, -400.0),
+                color: (Color::WHITE, Color::rgba(1.0, 0.8, 0.2, 0.0)),
+                size: (2.5, 1.0),
+                sprite: SpriteName::Spark,
+                frames: 1
+            },
+            ParticleEffect::ThrustTrail => ParticleSettings {
+                spawn_rate: 40.0,
+                burst: 1,
+                lifetime: 0.25,
+                direction: Vec2::NEG_Y,
+                spread: PI * 0.1,
+                speed: (80.0, 140.0),
+                gravity: Vec2::ZERO,
+                color: (Color::rgb(1.0, 0.6, 0.1), Color::rgba(1.0, 0.1, 0.0, 0.0)),
+                size: (2.0, 0.5),
+                sprite: SpriteName::Spark,
+                frames: 1
+            },
+            ParticleEffect::PickupGlint => ParticleSettings {
+                spawn_rate: 0.0,
+                burst: 8,
+                lifetime: 0.5,
+                direction: Vec2::Y,
+                spread: PI,
+                speed: (20.0, 60.0),
+                gravity: Vec2::ZERO,
+                color: (Color::rgb(0.7, 1.0, 1.0), Color::rgba(1.0, 1.0, 1.0, 0.0)),
+                size: (2.5, 0.5),
+                sprite: SpriteName::Spark,
+                frames: 4
+            }
+        }
+    }
+}
+
+/// One-off burst of an effect at a world position
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ParticleBurstEvent {
+    pub effect: ParticleEffect,
+    pub position: Vec3
+}
+
+/// Continuously emits particles from the entity it is attached to
+#[derive(Component)]
+pub struct ParticleEmitter {
+    pub settings: ParticleSettings,
+    pub active: bool,
+    accumulator: f32
+}
+
+impl ParticleEmitter {
+    pub fn new(effect: ParticleEffect) -> Self {
+        Self { settings: effect.settings(), active: true, accumulator: 0.0 }
+    }
+}
+
+#[derive(Component)]
+pub struct Particle {
+    settings: ParticleSettings,
+    velocity: Vec2,
+    age: f32,
+    alive: bool
+}
+
+/// Reuses hidden particle entities instead of spawning and despawning one per particle
+#[derive(Resource)]
+pub struct ParticlePool {
+    pub capacity: usize,
+    particles: EntityPool<Particle>
+}
+
+impl Default for ParticlePool {
+    fn default() -> Self {
+        Self { capacity: PARTICLE_CAPACITY, particles: EntityPool::default() }
+    }
+}
+
+impl ParticlePool {
+    /// Particles currently alive
+    pub fn active(&self) -> usize {
+        self.particles.active()
+    }
+
+    /// Entities ever created by the pool, alive or waiting for reuse
+    pub fn spawned(&self) -> usize {
+        self.particles.spawned()
+    }
+
+    pub fn emit(
+        &mut self,
+        commands: &mut Commands,
+        atlas: Handle<TextureAtlas>,
+        settings: &ParticleSettings,
+        position: Vec3,
+        count: usize
+    ) {
+        let mut rng = rand::thread_rng();
+
+        for _ in 0..count {
+
+            let angle = rng.gen_range(-settings.spread..=settings.spread);
+            let speed = rng.gen_range(settings.speed.0..=settings.speed.1);
+            let velocity = Vec2::from_angle(angle).rotate(settings.direction.normalize_or_zero()) * speed;
+
+            let bundle = (
+                SpriteSheetBundle {
+                    texture_atlas: atlas.clone(),
+                    sprite: TextureAtlasSprite {
+                        index: settings.sprite.index(),
+                        color: settings.color.0,
+                        ..default()
+                    },
+                    transform: Transform {
+                        translation: position,
+                        scale: Vec3::splat(settings.size.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+                Particle { settings: settings.clone(), velocity, age: 0.0, alive: true }
+            );
+
+            if self.particles.spawn(commands, self.capacity, bundle).is_none() { return }
+        }
+    }
+
+    fn release(&mut self, entity: Entity) {
+        self.particles.release(entity);
+    }
+}
+
+/// Particles despawned by something else than the pool are no longer counted as alive or reusable
+fn forget_despawned_particles(mut pool: ResMut<ParticlePool>, mut despawned: RemovedComponents<Particle>) {
+    let despawned: Vec<Entity> = despawned.read().collect();
+    if despawned.is_empty() { return }
+
+    pool.particles.forget_despawned(&despawned);
+}
+
+fn burst_on_foe_hit(mut hits: EventReader<FoeHitEvent>, mut bursts: EventWriter<ParticleBurstEvent>) {
+    for hit in hits.read() {
+        bursts.send(ParticleBurstEvent { effect: ParticleEffect::ImpactSpark, position: hit.position });
+    }
+}
+
+fn burst_on_foe_killed(mut kills: EventReader<FoeKilledEvent>, mut bursts: EventWriter<ParticleBurstEvent>) {
+    for kill in kills.read() {
+        bursts.send(ParticleBurstEvent { effect: ParticleEffect::Explosion, position: kill.position });
+    }
+}
+
+fn burst_on_graze(mut grazes: EventReader<GrazeEvent>, mut bursts: EventWriter<ParticleBurstEvent>) {
+    for graze in grazes.read() {
+        bursts.send(ParticleBurstEvent { effect: ParticleEffect::ImpactSpark, position: graze.position });
+    }
+}
+
+fn burst_on_squadron_destroyed(mut squadrons: EventReader<SquadronDestroyedEvent>, mut bursts: EventWriter<ParticleBurstEvent>) {
+    for squadron in squadrons.read() {
+        bursts.send(ParticleBurstEvent { effect: ParticleEffect::PickupGlint, position: squadron.position });
+    }
+}
+
+fn spawn_particle_bursts(
+    mut commands: Commands,
+    mut pool: ResMut<ParticlePool>,
+    mut bursts: EventReader<ParticleBurstEvent>,
+    sprite_catalog: Option<Res<SpriteCatalog>>
+) {
+    for burst in bursts.read() {
+        let settings = burst.effect.settings();
+        let atlas = sprite_catalog.as_ref()
+            .map(|catalog| catalog.atlas(settings.sprite.sheet()))
+            .unwrap_or_default();
+
+        pool.emit(&mut commands, atlas, &settings, burst.position, settings.burst);
+    }
+}
+
+fn update_emitters(
+    mut commands: Commands,
+    mut pool: ResMut<ParticlePool>,
+    mut emitters: Query<(&GlobalTransform, &mut ParticleEmitter)>,
+    sprite_catalog: Option<Res<SpriteCatalog>>,
+    time: Res<Time>
+) {
+    for (transform, mut emitter) in &mut emitters {
+        if !emitter.active { continue }
+
+        emitter.accumulator += emitter.settings.spawn_rate * time.delta_seconds();
+        let count = emitter.accumulator.floor();
+        if count < 1.0 { continue }
+
+        emitter.accumulator -= count;
+
+        let atlas = sprite_catalog.as_ref()
+            .map(|catalog| catalog.atlas(emitter.settings.sprite.sheet()))
+            .unwrap_or_default();
+
+        pool.emit(&mut commands, atlas, &emitter.settings, transform.translation(), count as usize);
+    }
+}
+
+fn update_particles(
+    mut pool: ResMut<ParticlePool>,
+    mut particles: Query<(Entity, &mut Particle, &mut Transform, &mut TextureAtlasSprite, &mut Visibility)>,
+    time: Res<Time>
+) {
+    let delta = time.delta_seconds();
+
+    for (entity, mut particle, mut transform, mut sprite, mut visibility) in &mut particles {
+        if !particle.alive { continue }
+
+        particle.age += delta;
+
+        if particle.age >= particle.settings.lifetime {
+            particle.alive = false;
+            *visibility = Visibility::Hidden;
+            pool.release(entity);
+            continue;
+        }
+
+        let gravity = particle.settings.gravity;
+        particle.velocity += gravity * delta;
+        transform.translation += (particle.velocity * delta).extend(0.0);
+
+        let settings = &particle.settings;
+        let progress = particle.age / settings.lifetime;
+        let (start_size, end_size) = settings.size;
+
+        transform.scale = Vec3::splat(start_size + (end_size - start_size) * progress);
+        sprite.color = lerp_color(settings.color.0, settings.color.1, progress);
+        sprite.index = settings.sprite.index() + ((progress * settings.frames as f32) as usize).min(settings.frames - 1);
+        *visibility = Visibility::Visible;
+    }
+}
+
+fn lerp_color(start: Color, end: Color, progress: f32) -> Color {
+    let [r1, g1, b1, a1] = start.as_rgba_f32();
+    let [r2, g2, b2, a2] = end.as_rgba_f32();
+
+    Color::rgba(
+        r1 + (r2 - r1) * progress,
+        g1 + (g2 - g1) * progress,
+        b1 + (b2 - b1) * progress,
+        a1 + (a2 - a1) * progress
+    )
+}
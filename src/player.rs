@@ -1,313 +1,579 @@
+use bevy::ecs::system::SystemParam;
 use bevy::sprite::collide_aabb::collide;
 use bevy::prelude::*;
 
+use crate::background::*;
 use crate::components::*;
+use crate::config::*;
 use crate::constants::*;
+use crate::damage::*;
+use crate::despawn::*;
+use crate::events::*;
+use crate::particles::*;
+use crate::projectiles::*;
+use crate::replay::*;
+use crate::resources::*;
+use crate::sprites::*;
 use crate::states::*;
 
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::Playing), setup_ship)
+        app.add_event::<PlayerHitEvent>()
+            .add_event::<PlayerShotEvent>()
+            .add_event::<BombEvent>()
+            .add_event::<ProjectileHitEvent>()
+            .add_event::<GrazeEvent>()
+            .init_resource::<HiScore>()
+            .init_resource::<Players>()
+            .add_systems(OnEnter(GameState::Playing), setup_ship.after(RunSetupSet))
             .add_systems(
                 FixedUpdate, 
                 (
-                    ship_movement,
-                    ship_shooting,
-                    update_bullets,
-                    //check_if_hit_by_bullet,                                      
-                    //check_if_hit_by_foe,
+                    // Ships are where they end up this tick before anything hits them
+                    (ship_movement, follow_scroll.after(ScrollSet), ship_shooting, ship_bombing, respawn_ship).chain().before(HitSet),
+                    check_if_hit_by_bullet.in_set(HitSet).after(FoeHitSet).before(DamageSet::Ships),
+                    check_if_hit_by_foe.in_set(HitSet).after(check_if_hit_by_bullet).before(DamageSet::Ships),
+                    destroy_ships.in_set(HitSet).after(DamageSet::Ships),
                     update_pumper,
+                    update_focus_core.after(follow_scroll),
                     ship_swerving,
-                    pumper_animation,
-                    test_system                
-                ).run_if(in_state(GameState::Playing))
+                    pumper_animation
+                ).after(PlayerInputSet).before(DespawnSet).run_if(in_state(GameState::Playing))
             );
     }
+
+    fn finish(&self, app: &mut App) {
+        require::<GameConfig, ConfigPlugin>(app);
+        require::<RunStart, ReplayPlugin>(app);
+        require::<PlayerInputs, ReplayPlugin>(app);
+        require::<ScrollPosition, BackgroundPlugin>(app);
+        require::<ScrollSpeed, BackgroundPlugin>(app);
+        require::<ProjectilePool, ProjectilePlugin>(app);
+        require::<Events<DamageEvent>, DamagePlugin>(app);
+    }
+}
+
+/// Run score, hi score and the score of every player, raised together
+#[derive(SystemParam)]
+pub struct Scoring<'w> {
+    pub score: ResMut<'w, Score>,
+    pub hi_score: ResMut<'w, HiScore>,
+    pub players: ResMut<'w, Players>
 }
 
-fn test_system(
-    mut ship_query: Query<&mut Transform, With<SpaceShip>>,    
+impl Scoring<'_> {
+    /// Adds `points` to the run and to `player`, the hi score follows the run score
+    pub fn award(&mut self, player: usize, points: u32) {
+        if let Some(state) = self.players.0.get_mut(player) {
+            state.score += points;
+        }
+        self.score.0 += points;
+        self.hi_score.0 = self.hi_score.0.max(self.score.0);
+    }
+}
+
+/// Carries the space ships along with the scroll so they keep their place on screen
+fn follow_scroll(
+    mut ship_query: Query<&mut Transform, With<SpaceShip>>,
+    scroll_speed: Res<ScrollSpeed>,
     time: Res<Time>
 ) {
-    if let Ok(mut transform) = ship_query.get_single_mut() {
-        transform.translation.y += 80.0 * time.delta_seconds();
+    for mut transform in &mut ship_query {
+        transform.translation.y += scroll_speed.current * time.delta_seconds();
     }
 }
 
 fn setup_ship(
     mut commands: Commands,
-    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
-    asset_server: Res<AssetServer>
+    sprite_catalog: Res<SpriteCatalog>,
+    config: Res<GameConfig>,
+    run_start: Res<RunStart>
 ) {
-    let ship_texture_handle = asset_server.load(SHIP_SPRITES);
-    let pumper_texture_handle = asset_server.load(MISCELLANEOUS_SPRITES);
+    let players = run_start.0.players();
+    let player_state = PlayerState { lives: config.ship.lives, bombs: config.ship.bombs, score: 0, graze: 0, respawn: None };
+
+    commands.insert_resource(Players(vec![player_state; players]));
+    commands.insert_resource(Score(0));
+
+    for player in 0..players {
+        spawn_ship(&mut commands, &sprite_catalog, &config, player, ship_start(player, players, 0.0));
+    }
+}
 
-    let ship_texture_atlas = 
-        TextureAtlas::from_grid(ship_texture_handle, Vec2::new(7.9, 7.5), 3, 1, Some(Vec2::new(0.2, 0.2)), None);
-    let pumper_texture_atlas =
-        TextureAtlas::from_grid(pumper_texture_handle, Vec2::new(8.0, 7.5), 10, 4, None, None);
+/// Where a ship starts, side by side with the other ones in co-op
+fn ship_start(player: usize, players: usize, y: f32) -> Vec2 {
+    let x = (player as f32 - (players - 1) as f32 * 0.5) * SHIP_CO_OP_SPACING;
+    Vec2::new(x, y)
+}
 
-    let ship_texture_atlas_handle = texture_atlases.add(ship_texture_atlas);
-    let pumper_texture_atlas_handle = texture_atlases.add(pumper_texture_atlas);
+/// Spawns the ship of a player along with its fire pumper, returning the ship
+pub fn spawn_ship(
+    commands: &mut Commands,
+    sprite_catalog: &SpriteCatalog,
+    config: &GameConfig,
+    player: usize,
+    position: Vec2
+) -> Entity {
+    let color = SHIP_COLORS[player % MAX_PLAYERS];
 
     // Spawns space ship
-    commands.spawn((
-        SpriteSheetBundle {
-            texture_atlas: ship_texture_atlas_handle,
-            sprite: TextureAtlasSprite::new(1),
-            transform: Transform::from_scale(Vec3::splat(6.0)),
+    let mut ship = sprite_catalog.sprite_sheet(
+        SpriteName::Ship,
+        Transform {
+            translation: position.extend(0.0),
+            scale: Vec3::splat(SHIP_SCALE),
             ..default()
-        },        
-        ShootingDelay(Timer::from_seconds(SHIP_SHOOTING_DELAY, TimerMode::Repeating)),
-        Health(SHIP_HEALTH),
-        Speed(SHIP_SPEED),
+        }
+    );
+    ship.sprite.color = color;
+
+    let ship = commands.spawn((
+        ship,
+        ShootingDelay(Timer::from_seconds(config.ship.shooting_delay, TimerMode::Repeating)),
+        Health(config.ship.health),
+        config.ship.armor,
+        Speed(config.ship.speed),
+        Hitbox(SHIP_CORE_HITBOX),
+        Graze(config.ship.graze_radius),
+        Player(player),
         SpaceShip
-    ));
+    )).id();
+    if let Some(shield) = config.ship.shield.shield() {
+        commands.entity(ship).insert(shield);
+    }
 
-    // Spawn fire pumper
+    // Spawns the core, hidden until the player holds focus
     commands.spawn((
-        SpriteSheetBundle {
-            texture_atlas: pumper_texture_atlas_handle,
-            sprite: TextureAtlasSprite::new(15),
-            transform: Transform {
-                translation: Vec3::new(0.0, -36.0, 5.0),
-                scale: Vec3::new(6.0, 6.0, 0.0),
+        SpriteBundle {
+            sprite: Sprite {
+                color: SHIP_CORE_COLOR,
+                custom_size: Some(SHIP_CORE_HITBOX),
                 ..default()
             },
+            transform: Transform::from_translation(position.extend(10.0)),
+            visibility: Visibility::Hidden,
             ..default()
         },
-        AnimationIndices::new(15, 18),
+        Player(player),
+        FocusCore
+    ));
+
+    // Spawn fire pumper
+    let pumper_index = SpriteName::Pumper.index();
+    let mut pumper = sprite_catalog.sprite_sheet(
+        SpriteName::Pumper,
+        Transform {
+            translation: Vec3::new(position.x, position.y - 36.0, 5.0),
+            scale: Vec3::new(SHIP_SCALE, SHIP_SCALE, 0.0),
+            ..default()
+        }
+    );
+    pumper.sprite.color = color;
+
+    commands.spawn((
+        pumper,
+        AnimationIndices::new(pumper_index, pumper_index + 3),
         AnimationTimer(Timer::from_seconds(SHIP_PUMPER_ANIMATION_TIME, TimerMode::Repeating)),
+        ParticleEmitter::new(ParticleEffect::ThrustTrail),
+        Player(player),
         Pumper
     ));
-}
 
-fn ship_movement(
-    mut ship_query: Query<(&mut Transform, &Speed), With<SpaceShip>>,
-    camera_query: Query<(&Camera, &GlobalTransform)>,
-    keyboard_input: Res<Input<KeyCode>>, 
-    time: Res<Time>
-) {
-    if ship_query.is_empty() { return; }
+    ship
+}
 
-    let (mut ship_transform, speed) = ship_query.single_mut();
-    let (camera, camera_transform) = camera_query.single();
+/// Fires a bullet straight up from the projectile pool with the configured behaviours,
+/// nothing is fired once the pool is full
+pub fn spawn_bullet(
+    commands: &mut Commands,
+    projectile_pool: &mut ProjectilePool,
+    sprite_catalog: &SpriteCatalog,
+    config: &GameConfig,
+    player: usize,
+    position: Vec2
+) -> Option<Entity> {
+    let bullet = projectile_pool.spawn(commands, ProjectileKind::Bullet, config.projectiles.bullets, (
+        sprite_catalog.sprite_sheet(
+            SpriteName::ShipBullet,
+            Transform {
+                translation: position.extend(0.0),
+                scale: Vec3::splat(SHIP_BULLET_SCALE),
+                ..default()
+            }
+        ),
+        GDirection::new(0.0, 1.0),
+        Speed(config.ship.bullet_speed),
+        Damage::new(config.ship.damage, config.ship.damage_type),
+        Hitbox(SHIP_BULLET_HITBOX),
+        Player(player),
+        Bullet
+    ))?;
+
+    config.projectiles.bullet_behaviours.insert(&mut commands.entity(bullet));
+    Some(bullet)
+}
 
-    let Some(viewport_transform) = camera.world_to_viewport(camera_transform, ship_transform.translation) else { return };
-    
-    let current_x = viewport_transform.x;
-    let current_y = viewport_transform.y;
+/// Takes a life away from a player, who may take one from a teammate with shared continues,
+/// returns whether the game is over because nobody has any left
+pub fn lose_life(players: &mut Players, player: usize, continues: Continues, respawn_delay: f32) -> bool {
+    players.0[player].lives = players.0[player].lives.saturating_sub(1);
 
-    let mut horizontal = 0.0;
-    let mut vertical = 0.0;
+    // Teammates keep the life of the ship they are flying
+    if players.0[player].lives == 0 && continues == Continues::Shared {
+        let donor = (0..players.0.len())
+            .filter(|other| players.0[*other].lives > 1)
+            .max_by_key(|other| players.0[*other].lives);
 
-    if keyboard_input.pressed(KeyCode::Left) && !(current_x < 0.0) {
-        horizontal -= 1.0;
-    } else if keyboard_input.pressed(KeyCode::Right) && !(current_x >= WINDOW_WIDTH) {
-        horizontal += 1.0;
+        if let Some(donor) = donor {
+            players.0[donor].lives -= 1;
+            players.0[player].lives += 1;
+        }
     }
 
-    if keyboard_input.pressed(KeyCode::Up) && !(current_y < 0.0) {
-        vertical += 1.0;
-    } else if keyboard_input.pressed(KeyCode::Down) && !(current_y >= WINDOW_HEIGHT) {
-        vertical -= 1.0;
+    if players.0[player].lives > 0 {
+        players.0[player].respawn = Some(Timer::from_seconds(respawn_delay, TimerMode::Once));
     }
 
-    ship_transform.translation.x += horizontal * speed.0 * time.delta_seconds();
-    ship_transform.translation.y += vertical * speed.0 * time.delta_seconds();    
+    players.all_out()
 }
 
-fn update_pumper(
-    mut pumper_query: Query<&mut Transform, (With<Pumper>, Without<Speed>)>,
-    ship_query: Query<&Transform, (With<SpaceShip>, Without<Pumper>)>,
-    keyboard_input: Res<Input<KeyCode>>
+/// Marks a destroyed ship `Dead` along with its pumper and core
+#[allow(clippy::too_many_arguments)]
+fn destroy_ship(
+    commands: &mut Commands,
+    ship: Entity,
+    player: Player,
+    attached: &Query<(Entity, &Player), ShipAttachment>,
+    players: &mut Players,
+    next_state: &mut NextState<GameState>,
+    run_start: &RunStart,
+    config: &GameConfig
 ) {
-    if pumper_query.is_empty() { return }
+    commands.entity(ship).insert(Dead);
+    for (entity, _) in attached.iter().filter(|(_, owner)| **owner == player) {
+        commands.entity(entity).insert(Dead);
+    }
+
+    if lose_life(players, player.0, run_start.0.continues, config.ship.respawn_delay) {
+        next_state.set(GameState::GameOver);
+    }
+}
 
-    let mut pumper_transform = pumper_query.single_mut();
-    let ship_transform = ship_query.single();
+fn respawn_ship(
+    mut commands: Commands,
+    mut players: ResMut<Players>,
+    scroll_position: Res<ScrollPosition>,
+    sprite_catalog: Res<SpriteCatalog>,
+    config: Res<GameConfig>,
+    time: Res<Time>
+) {
+    let count = players.0.len();
 
-    let mut horizontal_tip = 0.0;
-    let mut vertical_tip = 0.0;
+    for (player, state) in players.0.iter_mut().enumerate() {
+        let Some(respawn) = state.respawn.as_mut() else { continue };
+        if !respawn.tick(time.delta()).finished() { continue }
 
-    if keyboard_input.pressed(KeyCode::Left) {
-        horizontal_tip -= 13.0;
-    } else if keyboard_input.pressed(KeyCode::Right) {
-        horizontal_tip += 13.0;
+        // Comes back at the bottom of the screen, wherever the background scrolled to
+        let position = ship_start(player, count, scroll_position.0 - WINDOW_HEIGHT * 0.3);
+        spawn_ship(&mut commands, &sprite_catalog, &config, player, position);
+        state.respawn = None;
     }
+}
 
-    if keyboard_input.pressed(KeyCode::Up) {
-        vertical_tip += 10.0;
-    } else if keyboard_input.pressed(KeyCode::Down) {
-        vertical_tip -= 10.0;
-    }
+#[allow(clippy::too_many_arguments)]
+fn ship_bombing(
+    mut commands: Commands,
+    mut players: ResMut<Players>,
+    mut foes: Query<(Entity, &Transform, &mut LastHitBy), LiveFoe>,
+    mut bomb_events: EventWriter<BombEvent>,
+    mut damage_events: EventWriter<DamageEvent>,
+    ship_query: Query<(&Transform, &Player), With<SpaceShip>>,
+    foe_projectiles: Query<Entity, With<FoeProjectile>>,
+    inputs: Res<PlayerInputs>,
+    config: Res<GameConfig>
+) {
+    let mut cleared = false;
+
+    for (ship_transform, player) in &ship_query {
+        let state = &mut players.0[player.0];
+        if !inputs.0[player.0].bomb_pressed || state.bombs == 0 { continue }
+
+        state.bombs -= 1;
+
+        // Clears the screen of projectiles, foe deaths are handled by the usual hit check
+        if !cleared {
+            for projectile in &foe_projectiles {
+                commands.entity(projectile).insert(Dead);
+            }
+            cleared = true;
+        }
 
-    pumper_transform.translation.x = ship_transform.translation.x + horizontal_tip;
-    pumper_transform.translation.y = (ship_transform.translation.y - 36.0) + vertical_tip;
+        let damage = Damage::new(config.ship.bomb_damage, DamageType::Explosive);
+        for (foe, foe_transform, mut last_hit_by) in &mut foes {
+            damage_events.send(DamageEvent {
+                target: foe,
+                damage,
+                source: DamageSource::Bomb,
+                position: foe_transform.translation
+            });
+            last_hit_by.0 = player.0;
+        }
+
+        bomb_events.send(BombEvent { position: ship_transform.translation });
+    }
 }
 
-fn ship_shooting(
-    mut commands: Commands, 
-    mut ship_transform: Query<(&Transform, &mut ShootingDelay), With<SpaceShip>>,
-    keyboard_input: Res<Input<KeyCode>>,
+fn ship_movement(
+    mut ship_query: Query<(&mut Transform, &Speed, &Player), With<SpaceShip>>,
+    scroll_position: Res<ScrollPosition>,
+    inputs: Res<PlayerInputs>,
+    config: Res<GameConfig>,
     time: Res<Time>
 ) {
-    if ship_transform.is_empty() { return }
-
-    let (ship_transform, mut shooting_delay) = ship_transform.single_mut();
-    let ship_x = ship_transform.translation.x;
-    let ship_y = ship_transform.translation.y;
-
-    if keyboard_input.pressed(KeyCode::Z) {
-        if shooting_delay.0.tick(time.delta()).just_finished() {
-            commands.spawn((
-                SpriteBundle {
-                    transform: Transform {
-                        translation: Vec3::new(ship_x - 10.0, ship_y + 15.0, 0.0),
-                        scale: Vec3::new(10.0, 20.0, 0.0),
-                        ..default()
-                    },
-                    sprite: Sprite {
-                        color: Color::ORANGE,
-                        ..default()
-                    },
-                    ..default()
-                },            
-                Speed(SHIP_BULLET_SPEED),
-                Damage(SHIP_DAMAGE),
-                Bullet
-            ));
-
-            commands.spawn((
-                SpriteBundle {
-                    transform: Transform {
-                        translation: Vec3::new(ship_x + 10.0, ship_y + 15.0, 0.0),
-                        scale: Vec3::new(10.0, 20.0, 0.0),
-                        ..default()
-                    },
-                    sprite: Sprite {
-                        color: Color::ORANGE,
-                        ..default()
-                    },
-                    ..default()
-                },            
-                Speed(SHIP_BULLET_SPEED),
-                Damage(SHIP_DAMAGE),
-                Bullet
-            ));
+    for (mut ship_transform, speed, player) in &mut ship_query {
+        let input = &inputs.0[player.0];
+        let speed = if input.focus { speed.0 * config.ship.focus_speed } else { speed.0 };
+
+        // Position on screen, from the top left corner like viewport coordinates
+        let current_x = ship_transform.translation.x + WINDOW_WIDTH * 0.5;
+        let current_y = WINDOW_HEIGHT * 0.5 - (ship_transform.translation.y - scroll_position.0);
+
+        let mut horizontal = 0.0;
+        let mut vertical = 0.0;
+
+        if input.left && current_x >= 0.0 {
+            horizontal -= 1.0;
+        } else if input.right && current_x < WINDOW_WIDTH {
+            horizontal += 1.0;
+        }
+
+        if input.up && current_y >= 0.0 {
+            vertical += 1.0;
+        } else if input.down && current_y < WINDOW_HEIGHT {
+            vertical -= 1.0;
         }
+
+        ship_transform.translation.x += horizontal * speed * time.delta_seconds();
+        ship_transform.translation.y += vertical * speed * time.delta_seconds();
     }
 }
 
-fn update_bullets(
-    mut commands: Commands,
-    mut bullets_query: Query<(Entity, &mut Transform, &Speed), With<Bullet>>,     
-    camera_query: Query<(&Camera, &GlobalTransform)>,
-    time: Res<Time>
+#[allow(clippy::type_complexity)]
+fn update_focus_core(
+    mut core_query: Query<(&mut Transform, &mut Visibility, &Player), (With<FocusCore>, Without<SpaceShip>)>,
+    ship_query: Query<(&Transform, &Player), With<SpaceShip>>,
+    inputs: Res<PlayerInputs>
 ) {
-    if camera_query.is_empty() { return }
+    for (mut core_transform, mut visibility, player) in &mut core_query {
+        let Some((ship_transform, _)) = ship_query.iter().find(|(_, owner)| *owner == player) else { continue };
 
-    let (camera, camera_transform) = camera_query.single();
+        core_transform.translation.x = ship_transform.translation.x;
+        core_transform.translation.y = ship_transform.translation.y;
+        *visibility = if inputs.0[player.0].focus { Visibility::Visible } else { Visibility::Hidden };
+    }
+}
 
-    for (bullet_entity, mut transform, speed) in &mut bullets_query {
-        transform.translation.y += speed.0 * time.delta_seconds();
+#[allow(clippy::type_complexity)]
+fn update_pumper(
+    mut pumper_query: Query<(&mut Transform, &Player), (With<Pumper>, Without<Speed>)>,
+    ship_query: Query<(&Transform, &Player), (With<SpaceShip>, Without<Pumper>)>,
+    inputs: Res<PlayerInputs>
+) {
+    for (mut pumper_transform, player) in &mut pumper_query {
+        let Some((ship_transform, _)) = ship_query.iter().find(|(_, owner)| *owner == player) else { continue };
+        let input = &inputs.0[player.0];
 
-        if let Some(bullet_transform) = camera.world_to_viewport(camera_transform, transform.translation) {
-            if bullet_transform.y > WINDOW_HEIGHT * 1.5 {
-                commands.entity(bullet_entity).despawn();            
-            }
-        }        
+        let mut horizontal_tip = 0.0;
+        let mut vertical_tip = 0.0;
+
+        if input.left {
+            horizontal_tip -= 13.0;
+        } else if input.right {
+            horizontal_tip += 13.0;
+        }
+
+        if input.up {
+            vertical_tip += 10.0;
+        } else if input.down {
+            vertical_tip -= 10.0;
+        }
+
+        pumper_transform.translation.x = ship_transform.translation.x + horizontal_tip;
+        pumper_transform.translation.y = (ship_transform.translation.y - 36.0) + vertical_tip;
     }
 }
 
-fn check_if_hit_by_foe(
+#[allow(clippy::too_many_arguments)]
+fn ship_shooting(
     mut commands: Commands,
-    mut next_state: ResMut<NextState<GameState>>,
-    ship_query: Query<(Entity, &Transform), With<SpaceShip>>,
-    pumper_query: Query<Entity, With<Pumper>>,
-    foes_query: Query<&Transform, With<Foe>>
+    mut projectile_pool: ResMut<ProjectilePool>,
+    mut ship_query: Query<(&Transform, &mut ShootingDelay, &Player), With<SpaceShip>>,
+    mut player_shot_events: EventWriter<PlayerShotEvent>,
+    sprite_catalog: Res<SpriteCatalog>,
+    inputs: Res<PlayerInputs>,
+    config: Res<GameConfig>,
+    time: Res<Time>
 ) {
-    if ship_query.is_empty() || foes_query.is_empty() { return; }
-
-    let (ship_entity, ship_transform) = ship_query.single();
-    let pumper_entity = pumper_query.single();
+    for (ship_transform, mut shooting_delay, player) in &mut ship_query {
+        let ship_x = ship_transform.translation.x;
+        let ship_y = ship_transform.translation.y;
 
-    let ship_translation = ship_transform.translation;
-    let ship_scale = ship_transform.scale.truncate();
+        if !inputs.0[player.0].fire || !shooting_delay.0.tick(time.delta()).just_finished() { continue }
 
-    for f_transform in &foes_query {
-        let f_translation = f_transform.translation;
-        let f_scale = f_transform.scale.truncate();
+        let offsets = [-10.0, 10.0];
+        for offset_x in offsets {
+            let position = Vec2::new(ship_x + offset_x, ship_y + 15.0);
+            spawn_bullet(&mut commands, &mut projectile_pool, &sprite_catalog, &config, player.0, position);
+        }
 
-        let collision = collide(
-            ship_translation,
-            ship_scale,
-            f_translation,
-            f_scale
-        );
+        player_shot_events.send(PlayerShotEvent {
+            position: ship_transform.translation,
+            bullets: offsets.len() as u32
+        });
+    }
+}
 
-        if let Some(_) = collision {
-            commands.entity(ship_entity).despawn();
-            commands.entity(pumper_entity).despawn();
-            next_state.set(GameState::GameOver);
+/// Foes flying into a ship ram it once each, for the configured damage
+#[allow(clippy::type_complexity)]
+fn check_if_hit_by_foe(
+    mut commands: Commands,
+    ship_query: Query<(Entity, &Transform), HittableShip>,
+    foes_query: Query<(Entity, &Transform, &Hitbox), (With<Foe>, Without<Grounded>, Without<Dead>, Without<Rammed>)>,
+    mut damage_events: EventWriter<DamageEvent>,
+    config: Res<GameConfig>
+) {
+    // A foe overlapping both ships only rams the first one
+    let mut rammed = Vec::new();
+
+    for (ship_entity, ship_transform) in &ship_query {
+        let ship_translation = ship_transform.translation;
+
+        // Foe bodies collide with the whole ship, only projectiles have to reach its core
+        for (foe_entity, f_transform, f_hitbox) in &foes_query {
+            if rammed.contains(&foe_entity) { continue }
+            if collide(ship_translation, SHIP_HITBOX, f_transform.translation, f_hitbox.0).is_none() { continue }
+
+            commands.entity(foe_entity).insert(Rammed);
+            rammed.push(foe_entity);
+            damage_events.send(DamageEvent {
+                target: ship_entity,
+                damage: Damage::new(config.ship.ram_damage, DamageType::Kinetic),
+                source: DamageSource::Ram,
+                position: ship_translation
+            });
         }
     }
 }
 
+/// Foe projectiles hurt a ship by hitting its core, or are grazed once when they pass it inside its graze area
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 fn check_if_hit_by_bullet(
-    mut commands: Commands, 
-    mut next_state: ResMut<NextState<GameState>>,
-    mut ship_query: Query<(Entity, &Transform, &mut Health), With<SpaceShip>>,
-    foe_bullets_query: Query<(Entity, &Transform, &Damage), With<FoeProjectile>>,
-    pumper_query: Query<Entity, With<Pumper>>
+    mut commands: Commands,
+    mut scoring: Scoring,
+    ship_query: Query<(Entity, &Transform, &Hitbox, &Graze, &Player), HittableShip>,
+    mut foe_bullets_query: Query<(Entity, &Transform, &GDirection, &Hitbox, &Damage, Option<&mut Pierce>, Has<Grazed>), (With<FoeProjectile>, Without<Dead>)>,
+    mut projectile_hit_events: EventWriter<ProjectileHitEvent>,
+    mut damage_events: EventWriter<DamageEvent>,
+    mut graze_events: EventWriter<GrazeEvent>,
+    config: Res<GameConfig>
 ) {
-    if ship_query.is_empty() { return }
+    // A projectile overlapping both ships only hits the first one, and is only grazed by the first one
+    let mut spent = Vec::new();
+    let mut grazed = Vec::new();
 
-    let (ship_entity, ship_transform, mut health) = ship_query.single_mut();
-    let pumper_entity = pumper_query.single();
+    for (ship_entity, ship_transform, ship_hitbox, graze, player) in &ship_query {
+        let ship_translation = ship_transform.translation;
 
-    let ship_translation = ship_transform.translation;
-    let ship_scale = ship_transform.scale.truncate();
+        for (bullet_entity, bullet_transform, bullet_direction, bullet_hitbox, bullet_damage, mut pierce, was_grazed) in &mut foe_bullets_query {
+            if spent.contains(&bullet_entity) { continue }
+            if pierce.as_ref().is_some_and(|pierce| pierce.has_hit(ship_entity)) { continue }
 
-    for (bullet_entity, bullet_transform, bullet_damage) in &foe_bullets_query {
-        let b_translation = bullet_transform.translation;
-        let b_scale = bullet_transform.scale.truncate();
+            let b_translation = bullet_transform.translation;
+            let offset = (b_translation - ship_translation).truncate();
 
-        let collision = collide(
-            ship_translation,
-            ship_scale,
-            b_translation,
-            b_scale
-        );
+            let collision = collide(
+                ship_translation,
+                ship_hitbox.0,
+                b_translation,
+                bullet_hitbox.0
+            );
 
-        if let Some(_) = collision {
-            commands.entity(bullet_entity).despawn();
-            health.0 -= bullet_damage.0;
+            if collision.is_some() {
+                let bullet_spent = pierce.as_mut().is_none_or(|pierce| pierce.strike(ship_entity));
+                if bullet_spent {
+                    commands.entity(bullet_entity).insert(Dead);
+                    spent.push(bullet_entity);
+                }
+                projectile_hit_events.send(ProjectileHitEvent {
+                    projectile: bullet_entity,
+                    target: ship_entity,
+                    position: b_translation,
+                    spent: bullet_spent
+                });
+                damage_events.send(DamageEvent {
+                    target: ship_entity,
+                    damage: *bullet_damage,
+                    source: DamageSource::Projectile,
+                    position: b_translation
+                });
+            } else if !was_grazed
+                && !grazed.contains(&bullet_entity)
+                && offset.length() <= graze.0
+                && offset.dot(Vec2::new(bullet_direction.x, bullet_direction.y)) > 0.0
+            {
+                // Only counted once past the core and flying away from it, so projectiles about to hit do not count
+                commands.entity(bullet_entity).insert(Grazed);
+                grazed.push(bullet_entity);
+
+                if let Some(state) = scoring.players.0.get_mut(player.0) {
+                    state.graze += 1;
+                }
+                scoring.award(player.0, config.ship.graze_score);
+
+                graze_events.send(GrazeEvent {
+                    ship: ship_entity,
+                    projectile: bullet_entity,
+                    position: b_translation
+                });
+            }
         }
     }
+}
 
-    if health.0 <= 0 {
-        commands.entity(ship_entity).despawn();
-        commands.entity(pumper_entity).despawn();
-        next_state.set(GameState::GameOver);
+/// Marks the ships destroyed by the hits of the tick `Dead`
+#[allow(clippy::type_complexity)]
+fn destroy_ships(
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut players: ResMut<Players>,
+    ship_query: Query<(Entity, &Health, &Player), (With<SpaceShip>, Without<Dead>)>,
+    attached_query: Query<(Entity, &Player), ShipAttachment>,
+    run_start: Res<RunStart>,
+    config: Res<GameConfig>
+) {
+    for (ship_entity, health, player) in &ship_query {
+        if health.0 > 0 { continue }
+
+        destroy_ship(&mut commands, ship_entity, *player, &attached_query, &mut players, &mut next_state, &run_start, &config);
     }
 }
 
 fn ship_swerving(
-    mut ship_query: Query<&mut TextureAtlasSprite, With<SpaceShip>>,    
-    keyboard_input: Res<Input<KeyCode>>
+    mut ship_query: Query<(&mut TextureAtlasSprite, &Player), With<SpaceShip>>,
+    inputs: Res<PlayerInputs>
 ) {
-    if ship_query.is_empty() { return }
-
-    let mut texture_atlas = ship_query.single_mut();
-    if keyboard_input.pressed(KeyCode::Left) {        
-        texture_atlas.index = 0;
-    } else if keyboard_input.pressed(KeyCode::Right) {        
-        texture_atlas.index = 2;
-    } else {
-        texture_atlas.index = 1;
+    for (mut texture_atlas, player) in &mut ship_query {
+        let input = &inputs.0[player.0];
+
+        texture_atlas.index = if input.left {
+            SpriteName::ShipLeft.index()
+        } else if input.right {
+            SpriteName::ShipRight.index()
+        } else {
+            SpriteName::Ship.index()
+        };
     }
 }
 
@@ -315,14 +581,13 @@ fn pumper_animation(
     mut pumper_query: Query<(&mut TextureAtlasSprite, &mut AnimationTimer, &AnimationIndices), With<Pumper>>,
     time: Res<Time>
 ) {
-    if pumper_query.is_empty() { return }
-
-    let (mut texture_atlas, mut timer, indices) = pumper_query.single_mut();
-    if timer.0.tick(time.delta()).just_finished() {
-        texture_atlas.index = if texture_atlas.index != indices.last {
-            texture_atlas.index + 1        
-        } else {
-            indices.first
-        };
+    for (mut texture_atlas, mut timer, indices) in &mut pumper_query {
+        if timer.0.tick(time.delta()).just_finished() {
+            texture_atlas.index = if texture_atlas.index != indices.last {
+                texture_atlas.index + 1        
+            } else {
+                indices.first
+            };
+        }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,83 @@
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+
+/// Reuses entities instead of spawning and despawning one each time they are needed,
+/// every entity of the pool carries a `T` so despawns from outside the pool can be noticed
+pub struct EntityPool<T: Component> {
+    free: Vec<Entity>,
+    active: HashSet<Entity>,
+    spawned: usize,
+    marker: PhantomData<T>
+}
+
+impl<T: Component> Default for EntityPool<T> {
+    fn default() -> Self {
+        Self { free: Vec::new(), active: HashSet::default(), spawned: 0, marker: PhantomData }
+    }
+}
+
+impl<T: Component> EntityPool<T> {
+    /// Entities currently in use
+    pub fn active(&self) -> usize {
+        self.active.len()
+    }
+
+    /// Entities ever created by the pool and not despawned since, in use or waiting for reuse
+    pub fn spawned(&self) -> usize {
+        self.spawned
+    }
+
+    pub fn is_active(&self, entity: Entity) -> bool {
+        self.active.contains(&entity)
+    }
+
+    /// Puts an entity to use with `bundle`, reusing a released one when there is one,
+    /// nothing is spawned once `capacity` entities are in use
+    pub fn spawn(&mut self, commands: &mut Commands, capacity: usize, bundle: impl Bundle) -> Option<Entity> {
+        if self.active.len() >= capacity { return None }
+
+        // Released entities could have been despawned before `forget_despawned` got to them
+        let reused = loop {
+            let Some(entity) = self.free.pop() else { break None };
+            if commands.get_entity(entity).is_some() { break Some(entity) }
+            self.spawned -= 1;
+        };
+
+        let entity = match reused {
+            Some(entity) => {
+                commands.entity(entity).insert(bundle);
+                entity
+            }
+            None => {
+                self.spawned += 1;
+                commands.spawn(bundle).id()
+            }
+        };
+
+        self.active.insert(entity);
+        Some(entity)
+    }
+
+    /// Takes an entity out of use and keeps it for reuse, returns whether it was in use
+    pub fn release(&mut self, entity: Entity) -> bool {
+        if !self.active.remove(&entity) { return false }
+
+        self.free.push(entity);
+        true
+    }
+
+    /// Stops counting pooled entities despawned from outside the pool, in use or not
+    pub fn forget_despawned(&mut self, despawned: &[Entity]) {
+        for entity in despawned {
+            let was_active = self.active.remove(entity);
+            let free = self.free.len();
+            self.free.retain(|free| free != entity);
+
+            if was_active || self.free.len() < free {
+                self.spawned -= 1;
+            }
+        }
+    }
+}
@@ -0,0 +1,361 @@
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::background::*;
+use crate::components::*;
+use crate::config::*;
+use crate::constants::*;
+use crate::despawn::*;
+use crate::events::*;
+use crate::foe::*;
+use crate::level::*;
+use crate::player::*;
+use crate::pool::*;
+use crate::resources::*;
+use crate::states::*;
+
+/// Moves player bullets and foe projectiles alike, along with whatever behaviours they were fired with
+pub struct ProjectilePlugin;
+
+impl Plugin for ProjectilePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ProjectilePool>()
+            .add_event::<ProjectileHitEvent>()
+            .add_systems(
+                FixedUpdate,
+                (
+                    update_projectiles.after(TimelineSet).before(HitSet),
+                    split_projectiles.after(HitSet)
+                ).before(DespawnSet).run_if(in_state(GameState::Playing))
+            )
+            // After every schedule of the frame, whatever despawned the projectiles
+            .add_systems(Last, forget_despawned_projectiles);
+    }
+
+    fn finish(&self, app: &mut App) {
+        require::<GameConfig, ConfigPlugin>(app);
+        require::<ScrollPosition, BackgroundPlugin>(app);
+    }
+}
+
+/// Which pool a projectile entity comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProjectileKind {
+    Bullet,
+    FoeProjectile
+}
+
+/// Projectile entity owned by the pool, kept across screens and hidden while it waits for reuse
+#[derive(Component)]
+pub struct PooledProjectile;
+
+/// Goes through this many more targets before it is spent, never hitting the same one twice
+#[derive(Component, Debug, Clone, Default)]
+pub struct Pierce {
+    pub remaining: u32,
+    pub hit: Vec<Entity>
+}
+
+impl Pierce {
+    pub fn new(remaining: u32) -> Self {
+        Self { remaining, hit: Vec::new() }
+    }
+
+    pub fn has_hit(&self, target: Entity) -> bool {
+        self.hit.contains(&target)
+    }
+
+    /// Counts a hit on `target`, returns whether the projectile is spent by it
+    pub fn strike(&mut self, target: Entity) -> bool {
+        if self.remaining == 0 { return true }
+
+        self.remaining -= 1;
+        self.hit.push(target);
+        false
+    }
+}
+
+/// Bounces off the playfield edges this many more times instead of flying out
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Bounce(pub u32);
+
+/// Steers towards the nearest target, foes for bullets and ships for foe projectiles
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Homing {
+    /// Radians per second
+    pub turn_rate: f32
+}
+
+/// Gone once the timer finishes, wherever the projectile is
+#[derive(Component, Debug, Clone)]
+pub struct Lifetime(pub Timer);
+
+impl Lifetime {
+    pub fn new(seconds: f32) -> Self {
+        Self(Timer::from_seconds(seconds, TimerMode::Once))
+    }
+}
+
+/// Breaks into `count` projectiles fanning out over `spread` radians when it hits, those do not split again
+#[derive(Component, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Split {
+    pub count: u32,
+    pub spread: f32
+}
+
+/// Every behaviour component, taken off a projectile going back to the pool
+pub type ProjectileBehaviourComponents = (Pierce, Bounce, Homing, Lifetime, Split);
+
+/// Behaviours a kind of projectile is fired with, anything left out is not used
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectileBehaviours {
+    /// Targets gone through before the projectile is spent
+    pub pierce: Option<u32>,
+    pub bounce: Option<u32>,
+    /// Turn rate in radians per second
+    pub homing: Option<f32>,
+    /// Seconds before the projectile expires
+    pub lifetime: Option<f32>,
+    pub split: Option<Split>
+}
+
+impl ProjectileBehaviours {
+    pub fn insert(&self, projectile: &mut EntityCommands) {
+        if let Some(pierce) = self.pierce {
+            projectile.insert(Pierce::new(pierce));
+        }
+        if let Some(bounce) = self.bounce {
+            projectile.insert(Bounce(bounce));
+        }
+        if let Some(turn_rate) = self.homing {
+            projectile.insert(Homing { turn_rate });
+        }
+        if let Some(lifetime) = self.lifetime {
+            projectile.insert(Lifetime::new(lifetime));
+        }
+        if let Some(split) = self.split {
+            projectile.insert(split);
+        }
+    }
+}
+
+/// Reuses hidden projectile entities instead of spawning and despawning one per shot,
+/// a released projectile only loses its `Bullet` or `FoeProjectile` marker, its behaviours and whether it was grazed
+#[derive(Resource, Default)]
+pub struct ProjectilePool {
+    bullets: EntityPool<PooledProjectile>,
+    foe_projectiles: EntityPool<PooledProjectile>
+}
+
+impl ProjectilePool {
+    /// Projectiles of a kind currently in flight
+    pub fn active(&self, kind: ProjectileKind) -> usize {
+        self.pool(kind).active()
+    }
+
+    /// Entities ever created by the pool, in flight or waiting for reuse
+    pub fn spawned(&self) -> usize {
+        self.bullets.spawned() + self.foe_projectiles.spawned()
+    }
+
+    fn pool(&self, kind: ProjectileKind) -> &EntityPool<PooledProjectile> {
+        match kind {
+            ProjectileKind::Bullet => &self.bullets,
+            ProjectileKind::FoeProjectile => &self.foe_projectiles
+        }
+    }
+
+    fn pool_mut(&mut self, kind: ProjectileKind) -> &mut EntityPool<PooledProjectile> {
+        match kind {
+            ProjectileKind::Bullet => &mut self.bullets,
+            ProjectileKind::FoeProjectile => &mut self.foe_projectiles
+        }
+    }
+
+    /// Puts a projectile in flight, reusing a released entity of the same kind when there is one,
+    /// nothing is spawned once `capacity` projectiles of that kind are in flight
+    pub fn spawn(
+        &mut self,
+        commands: &mut Commands,
+        kind: ProjectileKind,
+        capacity: usize,
+        bundle: impl Bundle
+    ) -> Option<Entity> {
+        self.pool_mut(kind).spawn(commands, capacity, (bundle, PooledProjectile))
+    }
+
+    /// Hides a projectile until it is reused, releasing it again or releasing an entity the pool does not own does nothing
+    pub fn release(&mut self, commands: &mut Commands, entity: Entity) {
+        if !self.bullets.release(entity) && !self.foe_projectiles.release(entity) { return }
+
+        commands.entity(entity)
+            .remove::<(Bullet, FoeProjectile, Grazed)>()
+            .remove::<ProjectileBehaviourComponents>()
+            .insert(Visibility::Hidden);
+    }
+}
+
+/// Projectiles despawned by something else than the pool are no longer counted as in flight or reusable
+fn forget_despawned_projectiles(mut projectile_pool: ResMut<ProjectilePool>, mut despawned: RemovedComponents<PooledProjectile>) {
+    let despawned: Vec<Entity> = despawned.read().collect();
+    if despawned.is_empty() { return }
+
+    projectile_pool.bullets.forget_despawned(&despawned);
+    projectile_pool.foe_projectiles.forget_despawned(&despawned);
+}
+
+/// Turns `direction` towards `wanted` by at most `max_angle` radians
+fn steer(direction: Vec2, wanted: Vec2, max_angle: f32) -> Vec2 {
+    let angle = direction.angle_between(wanted);
+    if !angle.is_finite() { return direction }
+
+    Vec2::from_angle(angle.clamp(-max_angle, max_angle)).rotate(direction)
+}
+
+#[allow(clippy::type_complexity)]
+fn update_projectiles(
+    mut commands: Commands,
+    mut projectiles: Query<
+        (
+            Entity,
+            &mut Transform,
+            &mut GDirection,
+            &Speed,
+            Has<Bullet>,
+            Option<&Homing>,
+            Option<&mut Bounce>,
+            Option<&mut Lifetime>
+        ),
+        (With<PooledProjectile>, Or<(With<Bullet>, With<FoeProjectile>)>)
+    >,
+    foes: Query<&Transform, (With<Foe>, Without<PooledProjectile>, Without<Dead>)>,
+    ships: Query<&Transform, (With<SpaceShip>, Without<PooledProjectile>, Without<Dead>)>,
+    scroll_position: Res<ScrollPosition>,
+    time: Res<Time>
+) {
+    let delta = time.delta_seconds();
+    let foes: Vec<Vec2> = foes.iter().map(|transform| transform.translation.truncate()).collect();
+    let ships: Vec<Vec2> = ships.iter().map(|transform| transform.translation.truncate()).collect();
+
+    let left = -WINDOW_WIDTH * 0.5;
+    let right = WINDOW_WIDTH * 0.5;
+    let bottom = scroll_position.0 - WINDOW_HEIGHT * 0.5;
+    let top = scroll_position.0 + WINDOW_HEIGHT * 0.5;
+
+    for (entity, mut transform, mut gdirection, speed, bullet, homing, bounce, lifetime) in &mut projectiles {
+        if let Some(mut lifetime) = lifetime {
+            if lifetime.0.tick(time.delta()).finished() {
+                commands.entity(entity).insert(Dead);
+                continue;
+            }
+        }
+
+        let mut position = transform.translation.truncate();
+        let mut direction = Vec2::new(gdirection.x, gdirection.y);
+
+        if let Some(homing) = homing {
+            let targets = if bullet { &foes } else { &ships };
+            let nearest = targets.iter().min_by(|a, b| {
+                a.distance_squared(position).total_cmp(&b.distance_squared(position))
+            });
+
+            if let Some(target) = nearest {
+                direction = steer(direction, *target - position, homing.turn_rate * delta);
+            }
+        }
+
+        position += direction * speed.0 * delta;
+
+        // Mirrored back into the playfield, one bounce per edge crossed
+        if let Some(mut bounce) = bounce {
+            if bounce.0 > 0 && (position.x < left || position.x > right) {
+                position.x = if position.x < left { 2.0 * left - position.x } else { 2.0 * right - position.x };
+                direction.x = -direction.x;
+                bounce.0 -= 1;
+            }
+            if bounce.0 > 0 && (position.y < bottom || position.y > top) {
+                position.y = if position.y < bottom { 2.0 * bottom - position.y } else { 2.0 * top - position.y };
+                direction.y = -direction.y;
+                bounce.0 -= 1;
+            }
+        }
+
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+        *gdirection = GDirection::new(direction.x, direction.y);
+
+        // Every projectile despawns past the sides or the bottom of the view, or half a screen above its top
+        let gone = position.x < left
+            || position.x > right
+            || position.y < bottom
+            || position.y > scroll_position.0 + WINDOW_HEIGHT;
+
+        if gone {
+            commands.entity(entity).insert(Dead);
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn split_projectiles(
+    mut commands: Commands,
+    mut projectile_pool: ResMut<ProjectilePool>,
+    mut projectile_hit_events: EventReader<ProjectileHitEvent>,
+    projectiles: Query<(&GDirection, &Speed, &Damage, &Split, Option<&Player>, Option<&Homing>, Option<&Bounce>, Option<&Lifetime>)>,
+    sprite_catalog: Res<SpriteCatalog>,
+    config: Res<GameConfig>
+) {
+    for hit in projectile_hit_events.read() {
+        // Piercing projectiles split once, where they are finally spent
+        if !hit.spent { continue }
+        let Ok((gdirection, speed, damage, split, player, homing, bounce, lifetime)) = projectiles.get(hit.projectile) else { continue };
+
+        let direction = Vec2::new(gdirection.x, gdirection.y);
+        let position = hit.position.truncate();
+
+        for index in 0..split.count {
+            // Evenly over the spread, centred on the way the projectile was going
+            let angle = if split.count > 1 {
+                split.spread * (index as f32 / (split.count - 1) as f32 - 0.5)
+            } else {
+                0.0
+            };
+            let direction = Vec2::from_angle(angle).rotate(direction);
+
+            let fragment = match player {
+                Some(player) => spawn_bullet(&mut commands, &mut projectile_pool, &sprite_catalog, &config, player.0, position),
+                None => spawn_foe_projectile(
+                    &mut commands,
+                    &mut projectile_pool,
+                    &sprite_catalog,
+                    &config,
+                    position,
+                    direction,
+                    speed.0,
+                    *damage
+                )
+            };
+            let Some(fragment) = fragment else { continue };
+
+            // Fragments keep how the projectile flew, but not what it does on a hit
+            let mut fragment = commands.entity(fragment);
+            fragment.remove::<ProjectileBehaviourComponents>().insert((
+                GDirection::new(direction.x, direction.y),
+                Speed(speed.0),
+                *damage,
+                Pierce { remaining: 0, hit: vec![hit.target] }
+            ));
+            if let Some(homing) = homing {
+                fragment.insert(*homing);
+            }
+            if let Some(bounce) = bounce {
+                fragment.insert(*bounce);
+            }
+            if let Some(lifetime) = lifetime {
+                fragment.insert(Lifetime::new(lifetime.0.duration().as_secs_f32()));
+            }
+        }
+    }
+}
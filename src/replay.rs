@@ -0,0 +1,217 @@
+use std::fs;
+use std::path::Path;
+
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::constants::*;
+use crate::netcode::*;
+use crate::resources::*;
+use crate::states::*;
+
+/// Feeds `PlayerInputs` every fixed tick, from the keyboard while recording or from a replay
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlayerInputs>()
+            .init_resource::<StartOptions>()
+            .init_resource::<RunStart>()
+            .add_systems(OnEnter(GameState::Playing), start_run.in_set(RunSetupSet))
+            .add_systems(OnEnter(GameState::GameOver), finish_run)
+            .add_systems(
+                FixedUpdate,
+                capture_input
+                    .in_set(PlayerInputSet)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(not(resource_exists::<NetSession>()))
+            );
+    }
+}
+
+/// Systems setting up a run from `RunStart` run after this set
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RunSetupSet;
+
+/// Systems reading `PlayerInputs` during a fixed tick run after this set
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PlayerInputSet;
+
+/// Everything needed to play a run again: how it started and the input of every fixed tick
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    #[serde(default)]
+    pub level: usize,
+    #[serde(default = "first_wave")]
+    pub wave: u32,
+    #[serde(default)]
+    pub difficulty: Difficulty,
+    #[serde(default)]
+    pub rank: bool,
+    #[serde(default)]
+    pub mode: GameMode,
+    #[serde(default)]
+    pub co_op: bool,
+    #[serde(default)]
+    pub continues: Continues,
+    /// Input of player one
+    pub inputs: Vec<PlayerInput>,
+    /// Input of player two, only recorded in co-op
+    #[serde(default)]
+    pub second_inputs: Vec<PlayerInput>
+}
+
+fn first_wave() -> u32 { 1 }
+
+impl Replay {
+    pub fn new(start: StartOptions, seed: u64) -> Self {
+        Self {
+            seed,
+            level: start.level,
+            wave: start.wave,
+            difficulty: start.difficulty,
+            rank: start.rank,
+            mode: start.mode,
+            co_op: start.co_op,
+            continues: start.continues,
+            inputs: Vec::new(),
+            second_inputs: Vec::new()
+        }
+    }
+
+    pub fn start_options(&self) -> StartOptions {
+        StartOptions {
+            seed: Some(self.seed),
+            level: self.level,
+            wave: self.wave,
+            difficulty: self.difficulty,
+            rank: self.rank,
+            mode: self.mode,
+            co_op: self.co_op,
+            continues: self.continues
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path).map_err(|error| error.to_string())?;
+        ron::from_str(&contents).map_err(|error| error.to_string())
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let contents = ron::to_string(self).map_err(|error| error.to_string())?;
+        fs::write(path, contents).map_err(|error| error.to_string())
+    }
+
+    /// Recorded input of a player, empty for players that were not in the run
+    pub fn player_inputs(&self, player: usize) -> &[PlayerInput] {
+        match player {
+            0 => &self.inputs,
+            1 => &self.second_inputs,
+            _ => &[]
+        }
+    }
+
+    fn record(&mut self, player: usize, input: PlayerInput) {
+        match player {
+            0 => self.inputs.push(input),
+            1 => self.second_inputs.push(input),
+            _ => {}
+        }
+    }
+}
+
+/// Inputs of the run being played, moved to `LastReplay` on game over
+#[derive(Resource, Default)]
+pub struct ReplayRecorder(pub Replay);
+
+/// The most recently finished run
+#[derive(Resource)]
+pub struct LastReplay(pub Replay);
+
+/// Present while a replay drives the space ship instead of the keyboard
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    pub replay: Replay,
+    pub tick: usize
+}
+
+impl ReplayPlayback {
+    pub fn new(replay: Replay) -> Self {
+        Self { replay, tick: 0 }
+    }
+}
+
+fn start_run(
+    mut commands: Commands,
+    mut run_start: ResMut<RunStart>,
+    playback: Option<Res<ReplayPlayback>>,
+    start_options: Res<StartOptions>
+) {
+    let mut start = match &playback {
+        Some(playback) => playback.replay.start_options(),
+        None => *start_options
+    };
+    let seed = start.seed.unwrap_or_else(|| rand::thread_rng().gen());
+    start.seed = Some(seed);
+
+    if playback.is_none() {
+        commands.insert_resource(ReplayRecorder(Replay::new(start, seed)));
+    }
+
+    *run_start = RunStart(start);
+    commands.insert_resource(GameRng::new(seed));
+    commands.insert_resource(start.difficulty);
+    commands.insert_resource(PlayerInputs::default());
+}
+
+fn finish_run(mut commands: Commands, recorder: Option<ResMut<ReplayRecorder>>) {
+    commands.remove_resource::<ReplayPlayback>();
+
+    if let Some(mut recorder) = recorder {
+        commands.insert_resource(LastReplay(std::mem::take(&mut recorder.0)));
+        commands.remove_resource::<ReplayRecorder>();
+    }
+}
+
+fn capture_input(
+    mut inputs: ResMut<PlayerInputs>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut recorder: Option<ResMut<ReplayRecorder>>,
+    playback: Option<ResMut<ReplayPlayback>>,
+    run_start: Res<RunStart>,
+    keyboard_input: Res<Input<KeyCode>>
+) {
+    let players = run_start.0.players();
+    let mut held = [PlayerInput::default(); MAX_PLAYERS];
+
+    match playback {
+        Some(mut playback) => {
+            // The replay ends with the input of player one
+            if playback.tick >= playback.replay.inputs.len() {
+                next_state.set(GameState::GameOver);
+                return;
+            }
+            for (player, held) in held.iter_mut().enumerate().take(players) {
+                *held = playback.replay.player_inputs(player).get(playback.tick).copied().unwrap_or_default();
+            }
+            playback.tick += 1;
+        },
+        None => {
+            for (player, held) in held.iter_mut().enumerate().take(players) {
+                *held = PlayerInput::from_keyboard(&keyboard_input, InputMap::of(player));
+            }
+        }
+    }
+
+    for (input, held) in inputs.0.iter_mut().zip(held) {
+        *input = input.next(held);
+    }
+
+    if let Some(recorder) = recorder.as_mut() {
+        for (player, held) in held.into_iter().enumerate().take(players) {
+            recorder.0.record(player, held);
+        }
+    }
+}
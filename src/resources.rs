@@ -1,9 +1,323 @@
-use bevy::ecs::system::Resource;
-use bevy::sprite::TextureAtlas;
+use bevy::app::{App, Plugin};
+use bevy::asset::{Asset, Handle, UntypedHandle};
+use bevy::ecs::system::{Res, Resource};
+use bevy::input::Input;
+use bevy::input::keyboard::KeyCode;
+use bevy::sprite::{SpriteSheetBundle, TextureAtlas, TextureAtlasSprite};
+use bevy::ui::node_bundles::AtlasImageBundle;
+use bevy::ui::UiTextureAtlasImage;
+use bevy::transform::components::Transform;
 use bevy::time::Timer;
+use bevy::utils::HashMap;
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
 
-#[derive(Resource)]
-pub struct SpriteAtlas(pub TextureAtlas);
+use crate::constants::MAX_PLAYERS;
+use crate::sprites::{SpriteName, SpriteSheet};
 
-#[derive(Resource)]
-pub struct EnemySpawnDelay(pub Timer);
\ No newline at end of file
+/// Texture atlases of every sprite sheet, built once at startup
+#[derive(Resource, Default)]
+pub struct SpriteCatalog {
+    pub atlases: HashMap<SpriteSheet, Handle<TextureAtlas>>
+}
+
+impl SpriteCatalog {
+    pub fn atlas(&self, sheet: SpriteSheet) -> Handle<TextureAtlas> {
+        self.atlases.get(&sheet).cloned().unwrap_or_default()
+    }
+
+    pub fn sprite_sheet(&self, name: SpriteName, transform: Transform) -> SpriteSheetBundle {
+        SpriteSheetBundle {
+            texture_atlas: self.atlas(name.sheet()),
+            sprite: TextureAtlasSprite::new(name.index()),
+            transform,
+            ..Default::default()
+        }
+    }
+
+    pub fn atlas_image(&self, name: SpriteName) -> AtlasImageBundle {
+        AtlasImageBundle {
+            texture_atlas: self.atlas(name.sheet()),
+            texture_atlas_image: UiTextureAtlasImage { index: name.index(), ..Default::default() },
+            ..Default::default()
+        }
+    }
+}
+
+/// Handles that have to finish loading before leaving `GameState::Loading`
+#[derive(Resource, Default)]
+pub struct LoadingAssets {
+    pub handles: Vec<UntypedHandle>,
+    pub errors: Vec<String>
+}
+
+impl LoadingAssets {
+    pub fn add<A: Asset>(&mut self, handle: &Handle<A>) {
+        self.handles.push(handle.clone().untyped());
+    }
+
+    pub fn progress(&self, loaded: usize) -> f32 {
+        if self.handles.is_empty() {
+            return 1.0;
+        }
+
+        loaded as f32 / self.handles.len() as f32
+    }
+}
+
+/// Scroll distances at which the foes of the current waves still have to appear
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FoeSpawnQueue(pub Vec<f32>);
+
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Score(pub u32);
+
+/// Best score since the game was launched
+#[derive(Resource, Default)]
+pub struct HiScore(pub u32);
+
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+pub struct Wave(pub u32);
+
+/// What a player keeps from one of their ships to the next
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlayerState {
+    /// Ships left, including the one currently flying
+    pub lives: u32,
+    pub bombs: u32,
+    /// Share of the team `Score` this player earned
+    pub score: u32,
+    /// Foe projectiles grazed this run, the meter in the HUD
+    #[serde(default)]
+    pub graze: u32,
+    /// Running while waiting to bring the ship back after losing a life
+    pub respawn: Option<Timer>
+}
+
+/// State of every player in the run, indexed by their `Player` number
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Players(pub Vec<PlayerState>);
+
+impl Players {
+    /// Players that still have a ship flying or coming back
+    pub fn in_game_mut(&mut self) -> impl Iterator<Item = &mut PlayerState> {
+        self.0.iter_mut().filter(|player| player.lives > 0)
+    }
+
+    /// Nobody has a life left, the game is over
+    pub fn all_out(&self) -> bool {
+        self.0.iter().all(|player| player.lives == 0)
+    }
+}
+
+/// Seeded generator for everything that affects gameplay, so runs can be replayed
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+pub struct GameRng(pub ChaCha12Rng);
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self(ChaCha12Rng::seed_from_u64(seed))
+    }
+}
+
+/// Controls of one player held during the current fixed tick
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerInput {
+    pub left: bool,
+    pub right: bool,
+    pub up: bool,
+    pub down: bool,
+    pub fire: bool,
+    pub bomb: bool,
+    /// Slows the ship down and shows its core, defaults to false when absent from a replay
+    #[serde(default)]
+    pub focus: bool,
+    /// Bomb went down this tick, derived from the previous tick instead of being recorded
+    #[serde(skip)]
+    pub bomb_pressed: bool
+}
+
+impl PlayerInput {
+    pub fn from_keyboard(keyboard_input: &Input<KeyCode>, keys: &InputMap) -> Self {
+        Self {
+            left: keyboard_input.pressed(keys.left),
+            right: keyboard_input.pressed(keys.right),
+            up: keyboard_input.pressed(keys.up),
+            down: keyboard_input.pressed(keys.down),
+            fire: keyboard_input.pressed(keys.fire),
+            bomb: keyboard_input.pressed(keys.bomb),
+            focus: keyboard_input.pressed(keys.focus),
+            bomb_pressed: false
+        }
+    }
+
+    /// Input of the following tick, given the controls held during it
+    pub fn next(&self, held: PlayerInput) -> Self {
+        Self { bomb_pressed: held.bomb && !self.bomb, ..held }
+    }
+}
+
+/// Input of every player during the current fixed tick, indexed by their `Player` number
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerInputs(pub [PlayerInput; MAX_PLAYERS]);
+
+/// Keys flying the ship of one player
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputMap {
+    pub left: KeyCode,
+    pub right: KeyCode,
+    pub up: KeyCode,
+    pub down: KeyCode,
+    pub fire: KeyCode,
+    pub bomb: KeyCode,
+    pub focus: KeyCode
+}
+
+impl InputMap {
+    pub const PLAYER_ONE: InputMap = InputMap {
+        left: KeyCode::Left,
+        right: KeyCode::Right,
+        up: KeyCode::Up,
+        down: KeyCode::Down,
+        fire: KeyCode::Z,
+        bomb: KeyCode::X,
+        focus: KeyCode::ShiftLeft
+    };
+
+    pub const PLAYER_TWO: InputMap = InputMap {
+        left: KeyCode::A,
+        right: KeyCode::D,
+        up: KeyCode::W,
+        down: KeyCode::S,
+        fire: KeyCode::F,
+        bomb: KeyCode::G,
+        focus: KeyCode::H
+    };
+
+    pub fn of(player: usize) -> &'static InputMap {
+        if player == 0 { &Self::PLAYER_ONE } else { &Self::PLAYER_TWO }
+    }
+}
+
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Insane
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard, Difficulty::Insane];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+            Difficulty::Insane => "insane"
+        }
+    }
+}
+
+impl std::str::FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Difficulty::ALL.into_iter()
+            .find(|difficulty| difficulty.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("unknown difficulty '{}', expected easy, normal, hard or insane", name))
+    }
+}
+
+/// Campaign plays the levels and their bosses, endless spawns ever more foes until the player runs out of lives
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GameMode {
+    #[default]
+    Campaign,
+    Endless
+}
+
+impl GameMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Campaign => "campaign",
+            GameMode::Endless => "endless"
+        }
+    }
+}
+
+/// Whether a co-op player out of lives may take one from their teammate
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Continues {
+    #[default]
+    Separate,
+    Shared
+}
+
+impl Continues {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Continues::Separate => "separate",
+            Continues::Shared => "shared"
+        }
+    }
+}
+
+/// How the next run starts, set from the command line or by a replay being played back
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StartOptions {
+    /// Fixed RNG seed, a random one is picked for every run otherwise
+    pub seed: Option<u64>,
+    /// Index of the first level played
+    pub level: usize,
+    /// First wave of that level, starting at 1
+    pub wave: u32,
+    pub difficulty: Difficulty,
+    /// Lets the rank raise or lower the intensity with how well the player does
+    pub rank: bool,
+    pub mode: GameMode,
+    /// Two ships on the same keyboard
+    pub co_op: bool,
+    pub continues: Continues
+}
+
+impl Default for StartOptions {
+    fn default() -> Self {
+        Self {
+            seed: None,
+            level: 0,
+            wave: 1,
+            difficulty: Difficulty::Normal,
+            rank: false,
+            mode: GameMode::Campaign,
+            co_op: false,
+            continues: Continues::Separate
+        }
+    }
+}
+
+impl StartOptions {
+    pub fn players(&self) -> usize {
+        if self.co_op { MAX_PLAYERS } else { 1 }
+    }
+}
+
+/// Options the current run actually started with, including the seed that was picked
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunStart(pub StartOptions);
+
+/// Run condition for systems that only apply to one game mode
+pub fn in_mode(mode: GameMode) -> impl FnMut(Res<RunStart>) -> bool + Clone {
+    move |run_start: Res<RunStart>| run_start.0.mode == mode
+}
+
+/// Resources are registered by the one plugin owning them, plugins reading one call this from `Plugin::finish`,
+/// once every plugin is built, to fail early when `P` was left out and nothing else provided `R`
+pub fn require<R: Resource, P: Plugin>(app: &App) {
+    if app.world.contains_resource::<R>() { return }
+
+    panic!("{} is missing, add {}", std::any::type_name::<R>(), std::any::type_name::<P>());
+}
@@ -0,0 +1,90 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::constants::*;
+
+/// Loads player settings at startup and writes them back whenever they change
+pub struct SettingsPlugin {
+    pub path: PathBuf
+}
+
+impl Default for SettingsPlugin {
+    fn default() -> Self {
+        Self { path: PathBuf::from(SETTINGS_PATH) }
+    }
+}
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Settings::load(&self.path))
+            .insert_resource(SettingsPath(self.path.clone()))
+            .add_systems(Last, save_settings);
+    }
+}
+
+/// Player preferences that survive restarts, missing fields fall back to their defaults
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub sfx_volume: f32
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self { master_volume: 1.0, music_volume: 0.6, sfx_volume: 0.8 }
+    }
+}
+
+impl Settings {
+    pub fn load(path: &Path) -> Self {
+        let Ok(contents) = fs::read_to_string(path) else { return Settings::default() };
+
+        match ron::from_str::<Settings>(&contents) {
+            Ok(settings) => settings.clamped(),
+            Err(error) => {
+                warn!("Ignoring invalid settings file {}: {}", path.display(), error);
+                Settings::default()
+            }
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|error| error.to_string())?;
+
+        fs::write(path, contents).map_err(|error| error.to_string())
+    }
+
+    /// Volumes are kept between 0.0 (muted) and 1.0 (full)
+    pub fn clamped(self) -> Self {
+        Self {
+            master_volume: self.master_volume.clamp(0.0, 1.0),
+            music_volume: self.music_volume.clamp(0.0, 1.0),
+            sfx_volume: self.sfx_volume.clamp(0.0, 1.0)
+        }
+    }
+
+    pub fn effective_music_volume(&self) -> f32 {
+        self.master_volume * self.music_volume
+    }
+
+    pub fn effective_sfx_volume(&self) -> f32 {
+        self.master_volume * self.sfx_volume
+    }
+}
+
+#[derive(Resource)]
+pub struct SettingsPath(pub PathBuf);
+
+fn save_settings(settings: Res<Settings>, path: Res<SettingsPath>) {
+    if !settings.is_changed() || settings.is_added() { return }
+
+    if let Err(error) = settings.save(&path.0) {
+        warn!("Could not save settings to {}: {}", path.0.display(), error);
+    }
+}
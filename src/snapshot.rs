@@ -0,0 +1,647 @@
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+
+use bevy::ecs::system::{CommandQueue, EntityCommands};
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::background::*;
+use crate::components::*;
+use crate::config::*;
+use crate::damage::*;
+use crate::difficulty::*;
+use crate::endless::*;
+use crate::foe::*;
+use crate::formation::*;
+use crate::ground::*;
+use crate::level::*;
+use crate::player::*;
+use crate::projectiles::*;
+use crate::resources::*;
+use crate::states::*;
+use crate::stats::*;
+
+/// Gameplay state between two fixed ticks, enough to rewind the simulation to it or pick it up later
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GameSnapshot {
+    pub ships: Vec<ShipSnapshot>,
+    pub foes: Vec<FoeSnapshot>,
+    pub bullets: Vec<BulletSnapshot>,
+    pub foe_projectiles: Vec<FoeProjectileSnapshot>,
+    #[serde(default)]
+    pub squadrons: Vec<Squadron>,
+    #[serde(default)]
+    pub wrecks: Vec<WreckSnapshot>,
+    pub run_start: Option<RunStart>,
+    pub difficulty: Option<Difficulty>,
+    pub rng: Option<GameRng>,
+    pub score: Option<Score>,
+    pub players: Option<Players>,
+    pub inputs: Option<PlayerInputs>,
+    pub wave: Option<Wave>,
+    pub spawn_queue: Option<FoeSpawnQueue>,
+    pub current_level: Option<CurrentLevel>,
+    pub level_progress: Option<LevelProgress>,
+    pub scroll_position: Option<ScrollPosition>,
+    pub scroll_speed: Option<ScrollSpeed>,
+    #[serde(default)]
+    pub terrain_speed: Option<TerrainSpeed>,
+    pub endless_run: Option<EndlessRun>,
+    pub rank: Option<Rank>,
+    pub scaling: Option<DifficultyScaling>,
+    #[serde(default)]
+    pub stats: Option<RunStats>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShipSnapshot {
+    pub player: usize,
+    pub transform: Transform,
+    pub health: i32,
+    pub speed: f32,
+    pub shooting_delay: Timer,
+    pub invulnerable: bool,
+    #[serde(default)]
+    pub shield: Option<Shield>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FoeSnapshot {
+    pub kind: FoeKind,
+    pub transform: Transform,
+    pub health: i32,
+    pub speed: f32,
+    /// Left out for foes that never shoot
+    pub shooting_delay: Option<Timer>,
+    pub last_hit_by: usize,
+    #[serde(default)]
+    pub shield: Option<Shield>,
+    /// Index into the saved squadrons of the one it flies with, and its offset in the formation
+    #[serde(default)]
+    pub squadron: Option<(usize, Vec2)>,
+    /// Velocity once broken off its formation
+    #[serde(default)]
+    pub diving: Option<Vec2>,
+    /// Parallax speed of the terrain it sits on, for ground foes
+    #[serde(default)]
+    pub grounded: Option<f32>,
+    /// Angle of the gun, for turrets
+    #[serde(default)]
+    pub turret: Option<f32>,
+    /// Already rammed a ship, it does not hurt again
+    #[serde(default)]
+    pub rammed: bool
+}
+
+/// Destroyed ground foe left on the terrain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WreckSnapshot {
+    pub kind: FoeKind,
+    pub transform: Transform,
+    pub terrain_speed: f32
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulletSnapshot {
+    pub player: usize,
+    pub transform: Transform,
+    pub direction: Vec2,
+    pub speed: f32,
+    pub damage: i32,
+    #[serde(default)]
+    pub damage_type: DamageType,
+    pub behaviours: BehaviourSnapshot
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FoeProjectileSnapshot {
+    pub transform: Transform,
+    pub direction: Vec2,
+    pub speed: f32,
+    pub damage: i32,
+    #[serde(default)]
+    pub damage_type: DamageType,
+    pub behaviours: BehaviourSnapshot,
+    /// Already grazed by a ship, it does not score again
+    #[serde(default)]
+    pub grazed: bool
+}
+
+/// Behaviours of a projectile in flight, what it pierced saved as indices into the saved foes or ships
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BehaviourSnapshot {
+    pub pierce: Option<(u32, Vec<usize>)>,
+    pub bounce: Option<u32>,
+    pub homing: Option<f32>,
+    pub lifetime: Option<Timer>,
+    pub split: Option<Split>
+}
+
+impl BehaviourSnapshot {
+    fn capture(world: &World, projectile: Entity, targets: &[Entity]) -> Self {
+        Self {
+            pierce: world.get::<Pierce>(projectile).map(|pierce| {
+                let hit = pierce.hit.iter()
+                    .filter_map(|hit| targets.iter().position(|target| target == hit))
+                    .collect();
+                (pierce.remaining, hit)
+            }),
+            bounce: world.get::<Bounce>(projectile).map(|bounce| bounce.0),
+            homing: world.get::<Homing>(projectile).map(|homing| homing.turn_rate),
+            lifetime: world.get::<Lifetime>(projectile).map(|lifetime| lifetime.0.clone()),
+            split: world.get::<Split>(projectile).copied()
+        }
+    }
+
+    /// Replaces the behaviours the projectile was just fired with
+    fn insert(&self, projectile: &mut EntityCommands, targets: &[Entity]) {
+        projectile.remove::<ProjectileBehaviourComponents>();
+
+        if let Some((remaining, hit)) = &self.pierce {
+            let hit = hit.iter().filter_map(|index| targets.get(*index).copied()).collect();
+            projectile.insert(Pierce { remaining: *remaining, hit });
+        }
+        if let Some(bounce) = self.bounce {
+            projectile.insert(Bounce(bounce));
+        }
+        if let Some(turn_rate) = self.homing {
+            projectile.insert(Homing { turn_rate });
+        }
+        if let Some(lifetime) = &self.lifetime {
+            projectile.insert(Lifetime(lifetime.clone()));
+        }
+        if let Some(split) = self.split {
+            projectile.insert(split);
+        }
+    }
+
+    fn hash(&self, hasher: &mut Fnv1a) {
+        self.pierce.hash(hasher);
+        self.bounce.hash(hasher);
+        self.homing.map(f32::to_bits).hash(hasher);
+        self.lifetime.as_ref().map(Timer::elapsed).hash(hasher);
+        self.split.map(|split| (split.count, split.spread.to_bits())).hash(hasher);
+    }
+}
+
+fn save_resource<R: Resource + Clone>(world: &World) -> Option<R> {
+    world.get_resource::<R>().cloned()
+}
+
+fn restore_resource<R: Resource + Clone>(world: &mut World, value: &Option<R>) {
+    if let Some(value) = value {
+        world.insert_resource(value.clone());
+    }
+}
+
+impl GameSnapshot {
+    /// Copies the gameplay state out of the world
+    pub fn capture(world: &mut World) -> Self {
+        let mut ship_entities = Vec::new();
+        let ships = world.query_filtered::<(Entity, &Player, &Transform, &Health, &Speed, &ShootingDelay, Has<Invulnerable>, Option<&Shield>), With<SpaceShip>>()
+            .iter(world)
+            .map(|(entity, player, transform, health, speed, shooting_delay, invulnerable, shield)| {
+                ship_entities.push(entity);
+                ShipSnapshot {
+                    player: player.0,
+                    transform: *transform,
+                    health: health.0,
+                    speed: speed.0,
+                    shooting_delay: shooting_delay.0.clone(),
+                    invulnerable,
+                    shield: shield.cloned()
+                }
+            })
+            .collect();
+
+        let mut squadron_entities = Vec::new();
+        let squadrons = world.query::<(Entity, &Squadron)>()
+            .iter(world)
+            .map(|(entity, squadron)| {
+                squadron_entities.push(entity);
+                squadron.clone()
+            })
+            .collect();
+
+        let mut foe_entities = Vec::new();
+        let foes = world.query_filtered::<(Entity, &FoeKind, &Transform, &Health, &Speed, Option<&ShootingDelay>, &LastHitBy, Option<&Shield>, Option<&SquadronMember>, Option<&Diving>, Option<&Grounded>, Option<&Turret>, Has<Rammed>), With<Foe>>()
+            .iter(world)
+            .map(|(entity, kind, transform, health, speed, shooting_delay, last_hit_by, shield, member, diving, grounded, turret, rammed)| {
+                foe_entities.push(entity);
+                FoeSnapshot {
+                    kind: *kind,
+                    transform: *transform,
+                    health: health.0,
+                    speed: speed.0,
+                    shooting_delay: shooting_delay.map(|delay| delay.0.clone()),
+                    last_hit_by: last_hit_by.0,
+                    shield: shield.cloned(),
+                    squadron: member.and_then(|member| {
+                        let squadron = squadron_entities.iter().position(|entity| *entity == member.squadron)?;
+                        Some((squadron, member.offset))
+                    }),
+                    diving: diving.map(|diving| diving.0),
+                    grounded: grounded.map(|grounded| grounded.0),
+                    turret: turret.map(|turret| turret.angle),
+                    rammed
+                }
+            })
+            .collect();
+
+        let wrecks = world.query::<(&Wreck, &Transform, &Grounded)>()
+            .iter(world)
+            .map(|(wreck, transform, grounded)| WreckSnapshot {
+                kind: wreck.0,
+                transform: *transform,
+                terrain_speed: grounded.0
+            })
+            .collect();
+
+        let bullets = world.query_filtered::<(Entity, &Player, &Transform, &GDirection, &Speed, &Damage), With<Bullet>>()
+            .iter(world)
+            .map(|(entity, player, transform, direction, speed, damage)| BulletSnapshot {
+                player: player.0,
+                transform: *transform,
+                direction: Vec2::new(direction.x, direction.y),
+                speed: speed.0,
+                damage: damage.amount,
+                damage_type: damage.kind,
+                behaviours: BehaviourSnapshot::capture(world, entity, &foe_entities)
+            })
+            .collect();
+
+        let foe_projectiles = world.query_filtered::<(Entity, &Transform, &GDirection, &Speed, &Damage, Has<Grazed>), With<FoeProjectile>>()
+            .iter(world)
+            .map(|(entity, transform, direction, speed, damage, grazed)| FoeProjectileSnapshot {
+                transform: *transform,
+                direction: Vec2::new(direction.x, direction.y),
+                speed: speed.0,
+                damage: damage.amount,
+                damage_type: damage.kind,
+                behaviours: BehaviourSnapshot::capture(world, entity, &ship_entities),
+                grazed
+            })
+            .collect();
+
+        Self {
+            ships,
+            foes,
+            bullets,
+            foe_projectiles,
+            squadrons,
+            wrecks,
+            run_start: save_resource(world),
+            difficulty: save_resource(world),
+            rng: save_resource(world),
+            score: save_resource(world),
+            players: save_resource(world),
+            inputs: save_resource(world),
+            wave: save_resource(world),
+            spawn_queue: save_resource(world),
+            current_level: save_resource(world),
+            level_progress: save_resource(world),
+            scroll_position: save_resource(world),
+            scroll_speed: save_resource(world),
+            terrain_speed: save_resource(world),
+            endless_run: save_resource(world),
+            rank: save_resource(world),
+            scaling: save_resource(world),
+            stats: save_resource(world)
+        }
+    }
+
+    /// Puts the world back in the saved state, gameplay entities are spawned again from scratch
+    /// and projectiles in flight go back to the pool before the saved ones are fired again
+    pub fn restore(&self, world: &mut World) {
+        let mut stale = world.query_filtered::<Entity, Or<(With<SpaceShip>, With<Pumper>, With<FocusCore>, With<Foe>, With<Squadron>, With<Wreck>)>>();
+        let stale: Vec<Entity> = stale.iter(world).collect();
+        for entity in stale {
+            world.despawn(entity);
+        }
+
+        // The camera and background follow the scroll without being part of the snapshot
+        if let (Some(saved), Some(current)) = (self.scroll_position, world.get_resource::<ScrollPosition>()) {
+            let distance = saved.0 - current.0;
+            shift_view(world, distance);
+        }
+
+        world.init_resource::<ProjectilePool>();
+        world.resource_scope(|world, mut projectile_pool: Mut<ProjectilePool>| {
+            let mut in_flight = world.query_filtered::<Entity, With<PooledProjectile>>();
+            let in_flight: Vec<Entity> = in_flight.iter(world).collect();
+
+            let mut queue = CommandQueue::default();
+            let mut commands = Commands::new(&mut queue, world);
+            let sprite_catalog = world.resource::<SpriteCatalog>();
+            let config = world.resource::<GameConfig>();
+            let scaling = DifficultyScaling::default();
+
+            for projectile in in_flight {
+                projectile_pool.release(&mut commands, projectile);
+            }
+
+            let mut ship_entities = Vec::new();
+            for ship in &self.ships {
+                let entity = spawn_ship(&mut commands, sprite_catalog, config, ship.player, ship.transform.translation.truncate());
+                ship_entities.push(entity);
+                let mut entity = commands.entity(entity);
+                entity.insert((
+                    ship.transform,
+                    Health(ship.health),
+                    Speed(ship.speed),
+                    ShootingDelay(ship.shooting_delay.clone())
+                )).remove::<Shield>();
+                if ship.invulnerable {
+                    entity.insert(Invulnerable);
+                }
+                if let Some(shield) = &ship.shield {
+                    entity.insert(shield.clone());
+                }
+            }
+
+            let squadron_entities: Vec<Entity> = self.squadrons.iter()
+                .map(|squadron| commands.spawn(squadron.clone()).id())
+                .collect();
+
+            let mut foe_entities = Vec::new();
+            for foe in &self.foes {
+                let entity = spawn_foe(&mut commands, sprite_catalog, config, &scaling, foe.kind, foe.transform.translation.truncate());
+                foe_entities.push(entity);
+                let mut entity = commands.entity(entity);
+                entity.insert((
+                    foe.transform,
+                    Health(foe.health),
+                    Speed(foe.speed),
+                    LastHitBy(foe.last_hit_by)
+                )).remove::<Shield>();
+                if let Some(shooting_delay) = &foe.shooting_delay {
+                    entity.insert(ShootingDelay(shooting_delay.clone()));
+                }
+                if let Some(shield) = &foe.shield {
+                    entity.insert(shield.clone());
+                }
+                if let Some(squadron) = foe.squadron.and_then(|(index, offset)| {
+                    Some(SquadronMember { squadron: *squadron_entities.get(index)?, offset })
+                }) {
+                    entity.insert(squadron);
+                }
+                if let Some(velocity) = foe.diving {
+                    entity.insert(Diving(velocity));
+                }
+                if let Some(terrain_speed) = foe.grounded {
+                    entity.insert(Grounded(terrain_speed));
+                }
+                if let Some(angle) = foe.turret {
+                    entity.insert(Turret { angle });
+                }
+                if foe.rammed {
+                    entity.insert(Rammed);
+                }
+            }
+
+            for wreck in &self.wrecks {
+                spawn_wreck(&mut commands, sprite_catalog, wreck.kind, wreck.transform, wreck.terrain_speed);
+            }
+
+            for bullet in &self.bullets {
+                let position = bullet.transform.translation.truncate();
+                let entity = spawn_bullet(&mut commands, &mut projectile_pool, sprite_catalog, config, bullet.player, position);
+                let Some(entity) = entity else { continue };
+                let mut entity = commands.entity(entity);
+                entity.insert((
+                    bullet.transform,
+                    GDirection::new(bullet.direction.x, bullet.direction.y),
+                    Speed(bullet.speed),
+                    Damage::new(bullet.damage, bullet.damage_type)
+                ));
+                bullet.behaviours.insert(&mut entity, &foe_entities);
+            }
+
+            for projectile in &self.foe_projectiles {
+                let entity = spawn_foe_projectile(
+                    &mut commands,
+                    &mut projectile_pool,
+                    sprite_catalog,
+                    config,
+                    projectile.transform.translation.truncate(),
+                    projectile.direction,
+                    projectile.speed,
+                    Damage::new(projectile.damage, projectile.damage_type)
+                );
+                let Some(entity) = entity else { continue };
+                let mut entity = commands.entity(entity);
+                entity.insert(projectile.transform);
+                projectile.behaviours.insert(&mut entity, &ship_entities);
+                if projectile.grazed {
+                    entity.insert(Grazed);
+                }
+            }
+
+            queue.apply(world);
+        });
+
+        restore_resource(world, &self.run_start);
+        restore_resource(world, &self.difficulty);
+        restore_resource(world, &self.rng);
+        restore_resource(world, &self.score);
+        restore_resource(world, &self.players);
+        restore_resource(world, &self.inputs);
+        restore_resource(world, &self.wave);
+        restore_resource(world, &self.spawn_queue);
+        restore_resource(world, &self.current_level);
+        restore_resource(world, &self.level_progress);
+        restore_resource(world, &self.scroll_position);
+        restore_resource(world, &self.scroll_speed);
+        restore_resource(world, &self.terrain_speed);
+        restore_resource(world, &self.endless_run);
+        restore_resource(world, &self.rank);
+        restore_resource(world, &self.scaling);
+        restore_resource(world, &self.stats);
+
+        // A game over decided after the snapshot was taken may not happen anymore
+        if let Some(mut next_state) = world.get_resource_mut::<NextState<GameState>>() {
+            next_state.0 = None;
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path).map_err(|error| error.to_string())?;
+        ron::from_str(&contents).map_err(|error| error.to_string())
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let contents = ron::to_string(self).map_err(|error| error.to_string())?;
+        fs::write(path, contents).map_err(|error| error.to_string())
+    }
+
+    /// Fingerprint of the gameplay state, equal on two machines that simulated the same ticks the same way
+    pub fn checksum(&self) -> u64 {
+        // Entities are summed up so the order queries return them in does not matter
+        let mut entities: u64 = 0;
+        let mut add_entity = |hash: &dyn Fn(&mut Fnv1a)| {
+            let mut hasher = Fnv1a::default();
+            hash(&mut hasher);
+            entities = entities.wrapping_add(hasher.finish());
+        };
+
+        for ship in &self.ships {
+            add_entity(&|hasher| {
+                0u8.hash(hasher);
+                ship.player.hash(hasher);
+                hash_transform(hasher, &ship.transform);
+                ship.health.hash(hasher);
+                ship.speed.to_bits().hash(hasher);
+                ship.shooting_delay.elapsed().hash(hasher);
+                hash_shield(hasher, &ship.shield);
+            });
+        }
+
+        for foe in &self.foes {
+            add_entity(&|hasher| {
+                1u8.hash(hasher);
+                foe.kind.hash(hasher);
+                hash_transform(hasher, &foe.transform);
+                foe.health.hash(hasher);
+                foe.speed.to_bits().hash(hasher);
+                foe.shooting_delay.as_ref().map(Timer::elapsed).hash(hasher);
+                foe.last_hit_by.hash(hasher);
+                hash_shield(hasher, &foe.shield);
+                foe.squadron.map(|(index, offset)| (index, offset.x.to_bits(), offset.y.to_bits())).hash(hasher);
+                foe.diving.map(|velocity| (velocity.x.to_bits(), velocity.y.to_bits())).hash(hasher);
+                foe.grounded.map(f32::to_bits).hash(hasher);
+                foe.turret.map(f32::to_bits).hash(hasher);
+                foe.rammed.hash(hasher);
+            });
+        }
+
+        for wreck in &self.wrecks {
+            add_entity(&|hasher| {
+                5u8.hash(hasher);
+                wreck.kind.hash(hasher);
+                hash_transform(hasher, &wreck.transform);
+            });
+        }
+
+        for squadron in &self.squadrons {
+            add_entity(&|hasher| {
+                4u8.hash(hasher);
+                (squadron.origin.x.to_bits(), squadron.origin.y.to_bits(), squadron.elapsed.to_bits()).hash(hasher);
+                (squadron.dive.elapsed(), squadron.alive, squadron.escaped).hash(hasher);
+            });
+        }
+
+        for bullet in &self.bullets {
+            add_entity(&|hasher| {
+                2u8.hash(hasher);
+                bullet.player.hash(hasher);
+                hash_transform(hasher, &bullet.transform);
+                bullet.direction.x.to_bits().hash(hasher);
+                bullet.direction.y.to_bits().hash(hasher);
+                bullet.damage.hash(hasher);
+                bullet.damage_type.hash(hasher);
+                bullet.behaviours.hash(hasher);
+            });
+        }
+
+        for projectile in &self.foe_projectiles {
+            add_entity(&|hasher| {
+                3u8.hash(hasher);
+                hash_transform(hasher, &projectile.transform);
+                projectile.direction.x.to_bits().hash(hasher);
+                projectile.direction.y.to_bits().hash(hasher);
+                projectile.damage.hash(hasher);
+                projectile.damage_type.hash(hasher);
+                projectile.behaviours.hash(hasher);
+                projectile.grazed.hash(hasher);
+            });
+        }
+
+        let mut hasher = Fnv1a::default();
+        entities.hash(&mut hasher);
+
+        // Drawing from a copy of the generator tells its state apart without exposing it
+        if let Some(rng) = &self.rng {
+            rng.0.clone().gen::<u64>().hash(&mut hasher);
+        }
+        if let Some(score) = &self.score {
+            score.0.hash(&mut hasher);
+        }
+        if let Some(players) = &self.players {
+            for player in &players.0 {
+                (player.lives, player.bombs, player.score, player.graze).hash(&mut hasher);
+            }
+        }
+        if let Some(wave) = &self.wave {
+            wave.0.hash(&mut hasher);
+        }
+        if let Some(progress) = &self.level_progress {
+            (progress.distance.to_bits(), progress.next_marker).hash(&mut hasher);
+        }
+        if let Some(scroll_position) = &self.scroll_position {
+            scroll_position.0.to_bits().hash(&mut hasher);
+        }
+
+        hasher.finish()
+    }
+}
+
+/// 64 bit FNV-1a, unlike `DefaultHasher` it gives the same checksum on every build and machine
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    // Integers are hashed little endian and `usize` as 64 bits whatever the machine
+    fn write_u16(&mut self, value: u16) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, value: u128) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, value: usize) {
+        self.write_u64(value as u64);
+    }
+}
+
+fn hash_transform(hasher: &mut Fnv1a, transform: &Transform) {
+    let values = transform.translation.to_array().into_iter()
+        .chain(transform.rotation.to_array())
+        .chain(transform.scale.to_array());
+
+    for value in values {
+        value.to_bits().hash(hasher);
+    }
+}
+
+fn hash_shield(hasher: &mut Fnv1a, shield: &Option<Shield>) {
+    if let Some(shield) = shield {
+        (shield.points, shield.delay.elapsed(), shield.recharge.elapsed()).hash(hasher);
+    }
+}
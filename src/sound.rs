@@ -0,0 +1,343 @@
+use bevy::audio::Volume;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::events::*;
+use crate::loading::*;
+use crate::manifest::*;
+use crate::resources::*;
+use crate::settings::*;
+use crate::states::*;
+
+/// Where audio cues end up once they made it through voice limiting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioBackend {
+    /// Plays through Bevy's audio output
+    Bevy,
+    /// Only records cues in `PlayedCues`, for tests and machines without an audio device
+    Null
+}
+
+pub struct SoundPlugin {
+    pub backend: AudioBackend
+}
+
+impl SoundPlugin {
+    pub fn null() -> Self {
+        Self { backend: AudioBackend::Null }
+    }
+}
+
+impl Default for SoundPlugin {
+    fn default() -> Self {
+        Self { backend: AudioBackend::Bevy }
+    }
+}
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AudioCues>()
+            .init_resource::<Voices>()
+            .add_event::<PlayMusicEvent>()
+            .add_event::<PlayerShotEvent>()
+            .add_event::<FoeHitEvent>()
+            .add_event::<FoeKilledEvent>()
+            .add_event::<PlayerHitEvent>()
+            .add_event::<PickupEvent>()
+            .add_event::<BombEvent>()
+            .add_event::<WaveStartEvent>()
+            .add_systems(OnExit(GameState::Playing), stop_music)
+            .add_systems(Update, (queue_gameplay_sounds, queue_music).in_set(SoundSystems::Queue));
+
+        match self.backend {
+            AudioBackend::Bevy => {
+                app.add_systems(Startup, load_sounds)
+                    .add_systems(Update, (play_cues, update_music_volume).in_set(SoundSystems::Play));
+            },
+            AudioBackend::Null => {
+                app.init_resource::<PlayedCues>()
+                    .add_systems(Update, record_cues.in_set(SoundSystems::Play));
+            }
+        }
+
+        app.configure_sets(Update, SoundSystems::Queue.before(SoundSystems::Play));
+    }
+
+    fn finish(&self, app: &mut App) {
+        require::<Settings, SettingsPlugin>(app);
+        require::<LoadingAssets, LoadingPlugin>(app);
+    }
+}
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SoundSystems {
+    Queue,
+    Play
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SoundEffect {
+    PlayerShot,
+    FoeHit,
+    FoeKilled,
+    PlayerHit,
+    Pickup,
+    Bomb,
+    WaveStart
+}
+
+impl SoundEffect {
+    pub const ALL: [SoundEffect; 7] = [
+        SoundEffect::PlayerShot,
+        SoundEffect::FoeHit,
+        SoundEffect::FoeKilled,
+        SoundEffect::PlayerHit,
+        SoundEffect::Pickup,
+        SoundEffect::Bomb,
+        SoundEffect::WaveStart
+    ];
+
+    pub fn asset(&self) -> AssetName {
+        match self {
+            SoundEffect::PlayerShot => AssetName::PlayerShotSound,
+            SoundEffect::FoeHit => AssetName::FoeHitSound,
+            SoundEffect::FoeKilled => AssetName::FoeKilledSound,
+            SoundEffect::PlayerHit => AssetName::PlayerHitSound,
+            SoundEffect::Pickup => AssetName::PickupSound,
+            SoundEffect::Bomb => AssetName::BombSound,
+            SoundEffect::WaveStart => AssetName::WaveStartSound
+        }
+    }
+
+    /// How many copies of the sound may overlap before new ones are dropped
+    pub fn max_voices(&self) -> usize {
+        match self {
+            SoundEffect::PlayerShot => 3,
+            SoundEffect::FoeHit => 4,
+            SoundEffect::FoeKilled => 4,
+            SoundEffect::PlayerHit => 2,
+            SoundEffect::Pickup => 2,
+            SoundEffect::Bomb => 1,
+            SoundEffect::WaveStart => 1
+        }
+    }
+
+    /// Length of the sound, used to know when a voice frees up
+    pub fn duration(&self) -> f32 {
+        match self {
+            SoundEffect::PlayerShot => 0.07,
+            SoundEffect::FoeHit => 0.06,
+            SoundEffect::FoeKilled => 0.35,
+            SoundEffect::PlayerHit => 0.25,
+            SoundEffect::Pickup => 0.18,
+            SoundEffect::Bomb => 0.8,
+            SoundEffect::WaveStart => 0.49
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MusicTrack {
+    Stage1,
+    Stage2
+}
+
+impl MusicTrack {
+    pub const ALL: [MusicTrack; 2] = [MusicTrack::Stage1, MusicTrack::Stage2];
+
+    pub fn asset(&self) -> AssetName {
+        match self {
+            MusicTrack::Stage1 => AssetName::Stage1Music,
+            MusicTrack::Stage2 => AssetName::Stage2Music
+        }
+    }
+}
+
+/// Switches the background music, `None` stops it
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PlayMusicEvent(pub Option<MusicTrack>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioCue {
+    Effect(SoundEffect),
+    Music(MusicTrack),
+    StopMusic
+}
+
+/// Cues that passed voice limiting this frame, waiting for the backend
+#[derive(Resource, Default)]
+pub struct AudioCues(pub Vec<AudioCue>);
+
+/// Everything the null backend was asked to play, in order
+#[derive(Resource, Default)]
+pub struct PlayedCues(pub Vec<(AudioCue, f32)>);
+
+impl PlayedCues {
+    pub fn count(&self, cue: AudioCue) -> usize {
+        self.0.iter().filter(|(played, _)| *played == cue).count()
+    }
+}
+
+/// Remaining play time of every sound effect voice still sounding
+#[derive(Resource, Default)]
+pub struct Voices(HashMap<SoundEffect, Vec<f32>>);
+
+impl Voices {
+    pub fn active(&self, effect: SoundEffect) -> usize {
+        self.0.get(&effect).map_or(0, |voices| voices.len())
+    }
+
+    fn try_play(&mut self, effect: SoundEffect) -> bool {
+        let voices = self.0.entry(effect).or_default();
+        if voices.len() >= effect.max_voices() {
+            return false;
+        }
+
+        voices.push(effect.duration());
+        true
+    }
+
+    fn tick(&mut self, delta: f32) {
+        for voices in self.0.values_mut() {
+            voices.retain_mut(|remaining| {
+                *remaining -= delta;
+                *remaining > 0.0
+            });
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+struct SoundHandles {
+    effects: HashMap<SoundEffect, Handle<AudioSource>>,
+    music: HashMap<MusicTrack, Handle<AudioSource>>
+}
+
+#[derive(Component)]
+struct MusicPlayer;
+
+fn load_sounds(
+    mut commands: Commands,
+    mut loading_assets: ResMut<LoadingAssets>,
+    asset_server: Res<AssetServer>
+) {
+    let mut handles = SoundHandles::default();
+
+    for effect in SoundEffect::ALL {
+        let handle = asset_server.load(effect.asset().path());
+        loading_assets.add(&handle);
+        handles.effects.insert(effect, handle);
+    }
+
+    for track in MusicTrack::ALL {
+        let handle = asset_server.load(track.asset().path());
+        loading_assets.add(&handle);
+        handles.music.insert(track, handle);
+    }
+
+    commands.insert_resource(handles);
+}
+
+fn stop_music(mut music_events: EventWriter<PlayMusicEvent>) {
+    music_events.send(PlayMusicEvent(None));
+}
+
+#[allow(clippy::too_many_arguments)]
+fn queue_gameplay_sounds(
+    mut cues: ResMut<AudioCues>,
+    mut voices: ResMut<Voices>,
+    mut player_shot_events: EventReader<PlayerShotEvent>,
+    mut foe_hit_events: EventReader<FoeHitEvent>,
+    mut foe_killed_events: EventReader<FoeKilledEvent>,
+    mut player_hit_events: EventReader<PlayerHitEvent>,
+    mut pickup_events: EventReader<PickupEvent>,
+    mut bomb_events: EventReader<BombEvent>,
+    mut wave_start_events: EventReader<WaveStartEvent>,
+    time: Res<Time<Real>>
+) {
+    voices.tick(time.delta_seconds());
+
+    let effects = player_shot_events.read().map(|_| SoundEffect::PlayerShot)
+        .chain(foe_hit_events.read().map(|_| SoundEffect::FoeHit))
+        .chain(foe_killed_events.read().map(|_| SoundEffect::FoeKilled))
+        .chain(player_hit_events.read().map(|_| SoundEffect::PlayerHit))
+        .chain(pickup_events.read().map(|_| SoundEffect::Pickup))
+        .chain(bomb_events.read().map(|_| SoundEffect::Bomb))
+        .chain(wave_start_events.read().map(|_| SoundEffect::WaveStart));
+
+    for effect in effects {
+        if voices.try_play(effect) {
+            cues.0.push(AudioCue::Effect(effect));
+        }
+    }
+}
+
+fn queue_music(mut cues: ResMut<AudioCues>, mut music_events: EventReader<PlayMusicEvent>) {
+    for PlayMusicEvent(track) in music_events.read() {
+        cues.0.push(track.map_or(AudioCue::StopMusic, AudioCue::Music));
+    }
+}
+
+fn play_cues(
+    mut commands: Commands,
+    mut cues: ResMut<AudioCues>,
+    music_players: Query<Entity, With<MusicPlayer>>,
+    handles: Res<SoundHandles>,
+    settings: Res<Settings>
+) {
+    for cue in cues.0.drain(..) {
+        match cue {
+            AudioCue::Effect(effect) => {
+                let Some(source) = handles.effects.get(&effect) else { continue };
+
+                commands.spawn(AudioBundle {
+                    source: source.clone(),
+                    settings: PlaybackSettings::DESPAWN
+                        .with_volume(Volume::new_relative(settings.effective_sfx_volume()))
+                });
+            },
+            AudioCue::Music(track) => {
+                for entity in &music_players {
+                    commands.entity(entity).despawn();
+                }
+
+                let Some(source) = handles.music.get(&track) else { continue };
+
+                commands.spawn((
+                    AudioBundle {
+                        source: source.clone(),
+                        settings: PlaybackSettings::LOOP
+                            .with_volume(Volume::new_relative(settings.effective_music_volume()))
+                    },
+                    MusicPlayer
+                ));
+            },
+            AudioCue::StopMusic => {
+                for entity in &music_players {
+                    commands.entity(entity).despawn();
+                }
+            }
+        }
+    }
+}
+
+fn update_music_volume(music_sinks: Query<&AudioSink, With<MusicPlayer>>, settings: Res<Settings>) {
+    if !settings.is_changed() { return }
+
+    for sink in &music_sinks {
+        sink.set_volume(settings.effective_music_volume());
+    }
+}
+
+fn record_cues(mut cues: ResMut<AudioCues>, mut played: ResMut<PlayedCues>, settings: Res<Settings>) {
+    for cue in cues.0.drain(..) {
+        let volume = match cue {
+            AudioCue::Effect(_) => settings.effective_sfx_volume(),
+            AudioCue::Music(_) => settings.effective_music_volume(),
+            AudioCue::StopMusic => 0.0
+        };
+
+        played.0.push((cue, volume));
+    }
+}
@@ -0,0 +1,128 @@
+use bevy::prelude::*;
+
+use crate::constants::*;
+use crate::manifest::*;
+use crate::resources::*;
+
+pub struct SpritePlugin;
+
+impl Plugin for SpritePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_sprite_catalog);
+    }
+}
+
+/// Sprite sheets bundled in `assets/textures`, all laid out on an 8x8 pixel grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SpriteSheet {
+    Ships,
+    Projectiles,
+    Miscellaneous,
+    Ui
+}
+
+impl SpriteSheet {
+    pub const ALL: [SpriteSheet; 4] = [
+        SpriteSheet::Ships,
+        SpriteSheet::Projectiles,
+        SpriteSheet::Miscellaneous,
+        SpriteSheet::Ui
+    ];
+
+    pub fn asset(&self) -> AssetName {
+        match self {
+            SpriteSheet::Ships => AssetName::ShipSprites,
+            SpriteSheet::Projectiles => AssetName::ProjectileSprites,
+            SpriteSheet::Miscellaneous => AssetName::MiscellaneousSprites,
+            SpriteSheet::Ui => AssetName::UiSprites
+        }
+    }
+
+    /// Columns and rows of the sheet grid
+    pub fn grid(&self) -> (usize, usize) {
+        match self {
+            SpriteSheet::Ships => (10, 10),
+            SpriteSheet::Projectiles => (6, 10),
+            SpriteSheet::Miscellaneous => (13, 8),
+            SpriteSheet::Ui => (24, 14)
+        }
+    }
+}
+
+/// Named entries of the sprite sheets, so gameplay code never deals with raw atlas indices
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SpriteName {
+    ShipLeft,
+    Ship,
+    ShipRight,
+    Pumper,
+    Foe,
+    ShipBullet,
+    FoeProjectile,
+    Spark,
+    Explosion,
+    HealthSegment,
+    BombIcon
+}
+
+impl SpriteName {
+    pub fn sheet(&self) -> SpriteSheet {
+        match self {
+            SpriteName::ShipLeft
+            | SpriteName::Ship
+            | SpriteName::ShipRight
+            | SpriteName::Foe => SpriteSheet::Ships,
+            SpriteName::ShipBullet
+            | SpriteName::FoeProjectile => SpriteSheet::Projectiles,
+            SpriteName::Pumper
+            | SpriteName::Spark
+            | SpriteName::Explosion => SpriteSheet::Miscellaneous,
+            SpriteName::HealthSegment
+            | SpriteName::BombIcon => SpriteSheet::Ui
+        }
+    }
+
+    pub fn index(&self) -> usize {
+        match self {
+            SpriteName::ShipLeft => 0,
+            SpriteName::Ship => 1,
+            SpriteName::ShipRight => 2,
+            SpriteName::Foe => 4,
+            SpriteName::ShipBullet => 3,
+            SpriteName::FoeProjectile => 9,
+            SpriteName::Pumper => 18,
+            SpriteName::Spark => 83,
+            SpriteName::Explosion => 101,
+            SpriteName::HealthSegment => 5,
+            SpriteName::BombIcon => 54
+        }
+    }
+}
+
+fn setup_sprite_catalog(
+    mut commands: Commands,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    mut loading_assets: ResMut<LoadingAssets>,
+    asset_server: Res<AssetServer>
+) {
+    let mut catalog = SpriteCatalog::default();
+
+    for sheet in SpriteSheet::ALL {
+        let texture_handle: Handle<Image> = asset_server.load(sheet.asset().path());
+        loading_assets.add(&texture_handle);
+
+        let (columns, rows) = sheet.grid();
+        let texture_atlas = TextureAtlas::from_grid(
+            texture_handle,
+            Vec2::splat(SPRITE_TILE_SIZE),
+            columns,
+            rows,
+            None,
+            None
+        );
+
+        catalog.atlases.insert(sheet, texture_atlases.add(texture_atlas));
+    }
+
+    commands.insert_resource(catalog);
+}
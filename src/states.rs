@@ -2,7 +2,9 @@ use bevy::ecs::prelude::States;
 
 #[derive(States, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum GameState {
-    #[default]    
+    #[default]
+    Loading,
+    Menu,
     Playing,
     GameOver
 }
\ No newline at end of file
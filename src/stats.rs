@@ -0,0 +1,106 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::components::*;
+use crate::constants::*;
+use crate::despawn::*;
+use crate::events::*;
+use crate::resources::*;
+use crate::states::*;
+
+/// Keeps `RunStats` up to date from gameplay events while a run is being played
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunStats>()
+            .add_event::<PlayerShotEvent>()
+            .add_event::<FoeHitEvent>()
+            .add_event::<FoeKilledEvent>()
+            .add_event::<PlayerHitEvent>()
+            .add_event::<WaveStartEvent>()
+            .add_systems(OnEnter(GameState::Playing), reset_stats)
+            // Counted within the tick and saved in snapshots, ticks played again after a rollback count once
+            .add_systems(FixedUpdate, track_stats.after(DespawnSet).run_if(in_state(GameState::Playing)));
+    }
+}
+
+/// Statistics of the current run, or of the last one once the game is over
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RunStats {
+    pub score: u32,
+    pub time_survived: f32,
+    pub kills: HashMap<FoeKind, u32>,
+    pub shots_fired: u32,
+    pub hits: u32,
+    pub damage_taken: i32,
+    pub combo: u32,
+    pub max_combo: u32,
+    pub wave_reached: u32,
+    /// Time left before the current combo breaks
+    pub combo_timer: f32
+}
+
+impl RunStats {
+    pub fn total_kills(&self) -> u32 {
+        self.kills.values().sum()
+    }
+
+    /// Share of fired bullets that hit a foe, between 0.0 and 1.0
+    pub fn accuracy(&self) -> f32 {
+        if self.shots_fired == 0 { return 0.0 }
+
+        (self.hits as f32 / self.shots_fired as f32).min(1.0)
+    }
+}
+
+fn reset_stats(mut stats: ResMut<RunStats>) {
+    *stats = RunStats::default();
+}
+
+#[allow(clippy::too_many_arguments)]
+fn track_stats(
+    mut stats: ResMut<RunStats>,
+    mut player_shot_events: EventReader<PlayerShotEvent>,
+    mut foe_hit_events: EventReader<FoeHitEvent>,
+    mut foe_killed_events: EventReader<FoeKilledEvent>,
+    mut player_hit_events: EventReader<PlayerHitEvent>,
+    mut wave_start_events: EventReader<WaveStartEvent>,
+    score: Option<Res<Score>>,
+    time: Res<Time>
+) {
+    stats.time_survived += time.delta_seconds();
+
+    stats.combo_timer -= time.delta_seconds();
+    if stats.combo_timer <= 0.0 {
+        stats.combo = 0;
+    }
+
+    for event in player_shot_events.read() {
+        stats.shots_fired += event.bullets;
+    }
+
+    stats.hits += foe_hit_events.read().count() as u32;
+
+    for event in foe_killed_events.read() {
+        *stats.kills.entry(event.kind).or_default() += 1;
+
+        stats.combo += 1;
+        stats.combo_timer = STATS_COMBO_WINDOW;
+        stats.max_combo = stats.max_combo.max(stats.combo);
+    }
+
+    for event in player_hit_events.read() {
+        stats.damage_taken += event.damage;
+        stats.combo = 0;
+    }
+
+    for event in wave_start_events.read() {
+        stats.wave_reached = stats.wave_reached.max(event.wave);
+    }
+
+    if let Some(score) = score {
+        stats.score = score.0;
+    }
+}
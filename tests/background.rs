@@ -0,0 +1,64 @@
+use bevy::prelude::*;
+use bevy_parallax::{LayerComponent, LayerTextureComponent};
+
+use space_shooter::background::*;
+use space_shooter::level::*;
+use space_shooter::resources::*;
+use space_shooter::states::*;
+
+fn background_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, AssetPlugin::default(), BackgroundPlugin::headless()))
+        .add_state::<GameState>()
+        .init_asset::<Image>()
+        .init_asset::<TextureAtlas>()
+        .init_resource::<LoadingAssets>()
+        .init_resource::<LevelProgress>();
+    app.finish();
+    app.cleanup();
+    app.update();
+    app
+}
+
+fn theme(layers: &[(BackgroundImage, f32)]) -> BackgroundTheme {
+    BackgroundTheme {
+        layers: layers.iter()
+            .map(|(image, speed)| BackgroundLayer { image: *image, speed: *speed, scale: 1.0, tint: Color::WHITE })
+            .collect(),
+        scroll_speed: 100.0
+    }
+}
+
+/// Speeds of the layers on screen, and how many tiles they hold altogether
+fn layers(app: &mut App) -> (Vec<f32>, usize) {
+    let mut speeds: Vec<f32> = app.world.query::<&LayerComponent>()
+        .iter(&app.world)
+        .map(|layer| layer.speed.y)
+        .collect();
+    speeds.sort_by(f32::total_cmp);
+    let tiles = app.world.query::<&LayerTextureComponent>().iter(&app.world).count();
+    (speeds, tiles)
+}
+
+#[test]
+fn swaps_replace_every_layer_on_screen() {
+    let mut app = background_app();
+    let camera = app.world.spawn((Camera::default(), Transform::from_xyz(0.0, 300.0, 0.0))).id();
+
+    app.world.send_event(SwapBackgroundEvent(theme(&[(BackgroundImage::Navy, 0.9), (BackgroundImage::Stars, 0.5)])));
+    app.update();
+    let (speeds, tiles) = layers(&mut app);
+    assert_eq!(speeds, [0.5, 0.9]);
+    assert!(tiles > 0);
+    for (layer, transform) in app.world.query::<(&LayerComponent, &Transform)>().iter(&app.world) {
+        assert_eq!(layer.camera, camera);
+        assert_eq!(transform.translation.y, 300.0);
+    }
+
+    // Tiles go with the layers they belong to, the same number for every layer of the same scale
+    app.world.send_event(SwapBackgroundEvent(theme(&[(BackgroundImage::Layer1, 0.8)])));
+    app.update();
+    let (speeds, single_tiles) = layers(&mut app);
+    assert_eq!(speeds, [0.8]);
+    assert_eq!(single_tiles * 2, tiles);
+}
@@ -0,0 +1,103 @@
+use clap::Parser;
+
+use space_shooter::cli::*;
+use space_shooter::resources::*;
+
+fn parse(args: &[&str]) -> Result<Cli, clap::Error> {
+    Cli::try_parse_from(std::iter::once("space_shooter").chain(args.iter().copied()))
+}
+
+#[test]
+fn flags_become_start_options() {
+    let cli = parse(&["--seed", "42", "--level", "2", "--wave", "3", "--difficulty", "hard", "--rank"]).unwrap();
+    let launch = cli.validate().unwrap();
+
+    assert_eq!(launch.start, StartOptions {
+        seed: Some(42),
+        level: 1,
+        wave: 3,
+        difficulty: Difficulty::Hard,
+        rank: true,
+        mode: GameMode::Campaign,
+        co_op: false,
+        continues: Continues::Separate
+    });
+    assert!(!launch.options.auto_start);
+    assert!(launch.playback.is_none());
+}
+
+#[test]
+fn no_flags_start_a_normal_run() {
+    let launch = parse(&[]).unwrap().validate().unwrap();
+
+    assert_eq!(launch.start, StartOptions::default());
+    assert_eq!(launch.options, LaunchOptions::default());
+}
+
+#[test]
+fn resolutions_are_parsed_and_range_checked() {
+    assert_eq!(parse_resolution("1280x960"), Ok(Resolution { width: 1280, height: 960 }));
+    assert!(parse_resolution("1280").is_err());
+    assert!(parse_resolution("widexhigh").is_err());
+    assert!(parse_resolution("100x100").is_err());
+}
+
+#[test]
+fn conflicting_flags_are_rejected() {
+    assert!(parse(&["--ticks", "100"]).is_err());
+    assert!(parse(&["--fullscreen", "--headless"]).is_err());
+    assert!(parse(&["--replay", "a.ron", "--record", "b.ron"]).is_err());
+    assert!(parse(&["--headless", "--ticks", "0"]).is_err());
+    assert!(parse(&["--difficulty", "nightmare"]).is_err());
+    assert!(parse(&["--endless", "--wave", "2"]).is_err());
+
+    let cli = parse(&["--headless", "--ticks", "100"]).unwrap();
+    let launch = cli.validate().unwrap();
+    assert!(launch.options.auto_start);
+    assert_eq!(launch.options.ticks, Some(100));
+}
+
+#[test]
+fn online_runs_are_co_op_and_wait_for_the_peer() {
+    let launch = parse(&["--headless", "--host", "7000", "--shared-continues"]).unwrap().validate().unwrap();
+    assert_eq!(launch.net, Some(NetLaunch::Host(7000)));
+    assert!(launch.start.co_op);
+    assert_eq!(launch.start.continues, Continues::Shared);
+    assert!(!launch.options.auto_start);
+
+    // The host picks whether the run is ranked for both peers
+    let launch = parse(&["--host", "7000", "--rank"]).unwrap().validate().unwrap();
+    assert!(launch.start.rank);
+
+    let launch = parse(&["--join", "127.0.0.1:7000"]).unwrap().validate().unwrap();
+    assert_eq!(launch.net, Some(NetLaunch::Join("127.0.0.1:7000".parse().unwrap())));
+
+    assert!(parse(&["--shared-continues"]).is_err());
+    assert!(parse(&["--host", "7000", "--join", "127.0.0.1:7000"]).is_err());
+    assert!(parse(&["--join", "127.0.0.1:7000", "--seed", "3"]).is_err());
+    assert!(parse(&["--join", "nowhere"]).is_err());
+}
+
+#[test]
+fn out_of_range_starts_are_reported() {
+    let error = parse(&["--level", "9"]).unwrap().validate().unwrap_err();
+    assert_eq!(error, "level 9 does not exist, there are 2 levels");
+
+    let error = parse(&["--wave", "0"]).unwrap().validate().unwrap_err();
+    assert_eq!(error, "waves start at 1");
+
+    let error = parse(&["--level", "1", "--wave", "50"]).unwrap().validate().unwrap_err();
+    assert_eq!(error, "level 1 only has 4 waves, wave 50 does not exist");
+}
+
+#[test]
+fn missing_files_are_reported() {
+    let cli = parse(&["--replay", "does/not/exist.ron"]).unwrap();
+    assert!(cli.validate().unwrap_err().starts_with("could not load replay"));
+
+    let cli = parse(&["--config", "does/not/exist.ron"]).unwrap();
+    assert!(cli.validate().unwrap_err().starts_with("invalid game config"));
+
+    let cli = parse(&["--record", "does/not/replay.ron"]).unwrap();
+    assert!(cli.validate().unwrap_err().starts_with("cannot record to"));
+}
@@ -0,0 +1,52 @@
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+
+use space_shooter::background::*;
+use space_shooter::config::*;
+use space_shooter::damage::*;
+use space_shooter::despawn::*;
+use space_shooter::difficulty::*;
+use space_shooter::foe::*;
+use space_shooter::level::*;
+use space_shooter::player::*;
+use space_shooter::projectiles::*;
+use space_shooter::replay::*;
+use space_shooter::resources::*;
+use space_shooter::states::*;
+
+/// Headless app with ships, foes and projectiles, stepping one fixed tick per update,
+/// more plugins and resources can be added before `start_playing`
+pub fn gameplay_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((
+            MinimalPlugins,
+            ConfigPlugin { path: PathBuf::from("assets/config.ron") },
+            ReplayPlugin,
+            PlayerPlugin,
+            FoePlugin,
+            DespawnPlugin,
+            ProjectilePlugin,
+            DamagePlugin,
+            DifficultyPlugin
+        ))
+        .add_state::<GameState>()
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Time::<Fixed>::default().timestep()))
+        .insert_resource(SpriteCatalog::default())
+        .insert_resource(Wave(0))
+        .insert_resource(ScrollSpeed::new(0.0))
+        .init_resource::<ScrollPosition>()
+        .init_resource::<LevelProgress>()
+        .init_resource::<Input<KeyCode>>();
+    app
+}
+
+/// Checks every plugin got the resources it needs, then enters `GameState::Playing`, spawning the ships
+pub fn start_playing(mut app: App) -> App {
+    app.finish();
+    app.cleanup();
+    app.world.resource_mut::<NextState<GameState>>().set(GameState::Playing);
+    app.update();
+    app
+}
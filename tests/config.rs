@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+
+use space_shooter::config::*;
+
+#[test]
+fn shipped_config_matches_defaults() {
+    let config = GameConfig::load(std::path::Path::new("assets/config.ron")).unwrap();
+    assert_eq!(config, GameConfig::default());
+}
+
+#[test]
+fn missing_fields_keep_defaults() {
+    let config = GameConfig::parse("(ship: (speed: 600.0))").unwrap();
+
+    assert_eq!(config.ship.speed, 600.0);
+    assert_eq!(config.ship.health, ShipConfig::default().health);
+    assert_eq!(config.foe, FoeConfig::default());
+}
+
+#[test]
+fn out_of_range_values_are_reported() {
+    let errors = GameConfig::parse("(ship: (speed: 0.0), foe: (health: -5))").unwrap_err();
+
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].to_string(), "ship.speed is 0, it must be between 1 and 5000");
+    assert_eq!(errors[1].to_string(), "foe.health is -5, it must be between 1 and 100000");
+}
+
+#[test]
+fn config_reloads_when_file_changes() {
+    let path = std::env::temp_dir().join("space_shooter_config_test.ron");
+    std::fs::write(&path, "(foe: (speed: 100.0))").unwrap();
+
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, ConfigPlugin { path: path.clone() }))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(100)));
+    assert_eq!(app.world.resource::<GameConfig>().foe.speed, 100.0);
+
+    // Invalid edits are ignored, valid ones replace the running config
+    app.world.resource_mut::<ConfigWatcher>().modified = None;
+    std::fs::write(&path, "(foe: (speed: -1.0))").unwrap();
+    for _ in 0..10 {
+        app.update();
+    }
+    assert_eq!(app.world.resource::<GameConfig>().foe.speed, 100.0);
+
+    app.world.resource_mut::<ConfigWatcher>().modified = None;
+    std::fs::write(&path, "(foe: (speed: 300.0))").unwrap();
+    for _ in 0..10 {
+        app.update();
+    }
+    assert_eq!(app.world.resource::<GameConfig>().foe.speed, 300.0);
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,135 @@
+use std::time::Duration;
+
+use bevy::ecs::system::RunSystemOnce;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+
+use space_shooter::components::*;
+use space_shooter::config::*;
+use space_shooter::foe::*;
+use space_shooter::player::*;
+use space_shooter::replay::*;
+use space_shooter::resources::*;
+
+mod common;
+use common::*;
+
+fn co_op_app(replay: Replay) -> App {
+    let mut app = gameplay_app();
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(50)))
+        .insert_resource(ReplayPlayback::new(replay));
+    start_playing(app)
+}
+
+fn co_op_replay(ticks: usize, first: PlayerInput, second: PlayerInput) -> Replay {
+    let start = StartOptions { seed: Some(1), co_op: true, ..default() };
+    let mut replay = Replay::new(start, 1);
+    replay.inputs = vec![first; ticks];
+    replay.second_inputs = vec![second; ticks];
+    replay
+}
+
+fn ships(app: &mut App) -> Vec<(usize, Vec3)> {
+    let mut ships: Vec<_> = app.world.query_filtered::<(&Player, &Transform), With<SpaceShip>>()
+        .iter(&app.world)
+        .map(|(player, transform)| (player.0, transform.translation))
+        .collect();
+    ships.sort_by_key(|(player, _)| *player);
+    ships
+}
+
+fn players_with(lives: [u32; 2]) -> Players {
+    Players(lives.iter().map(|lives| PlayerState { lives: *lives, ..default() }).collect())
+}
+
+#[test]
+fn each_ship_follows_its_own_input() {
+    let left = PlayerInput { left: true, ..default() };
+    let right = PlayerInput { right: true, ..default() };
+    let mut app = co_op_app(co_op_replay(100, left, right));
+
+    let start = ships(&mut app);
+    assert_eq!(start.len(), 2);
+    assert!(start[0].1.x < start[1].1.x);
+
+    for _ in 0..10 {
+        app.update();
+    }
+
+    let moved = ships(&mut app);
+    assert!(moved[0].1.x < start[0].1.x);
+    assert!(moved[1].1.x > start[1].1.x);
+    assert_eq!(app.world.resource::<Players>().0.len(), 2);
+}
+
+#[test]
+fn bombs_go_off_from_the_recorded_input_once_per_press() {
+    let bomb = PlayerInput { bomb: true, ..default() };
+    let mut app = co_op_app(co_op_replay(100, bomb, PlayerInput::default()));
+    let bombs: Vec<u32> = app.world.resource::<Players>().0.iter().map(|player| player.bombs).collect();
+
+    // The bomb key does nothing while a replay drives the ships
+    app.world.resource_mut::<Input<KeyCode>>().press(KeyCode::X);
+    for _ in 0..10 {
+        app.update();
+    }
+
+    let players = &app.world.resource::<Players>().0;
+    assert_eq!((players[0].bombs, players[1].bombs), (bombs[0] - 1, bombs[1]));
+}
+
+#[test]
+fn separate_continues_end_the_game_only_when_both_are_out() {
+    let mut players = players_with([1, 2]);
+
+    assert!(!lose_life(&mut players, 0, Continues::Separate, 1.0));
+    assert_eq!(players.0[0].lives, 0);
+    assert!(players.0[0].respawn.is_none());
+
+    assert!(!lose_life(&mut players, 1, Continues::Separate, 1.0));
+    assert!(players.0[1].respawn.is_some());
+    assert!(lose_life(&mut players, 1, Continues::Separate, 1.0));
+}
+
+#[test]
+fn shared_continues_take_a_life_from_the_teammate() {
+    let mut players = players_with([1, 3]);
+
+    assert!(!lose_life(&mut players, 0, Continues::Shared, 1.0));
+    assert_eq!((players.0[0].lives, players.0[1].lives), (1, 2));
+    assert!(players.0[0].respawn.is_some());
+
+    // The teammate keeps the life of the ship they are flying
+    let mut players = players_with([1, 1]);
+    assert!(!lose_life(&mut players, 0, Continues::Shared, 1.0));
+    assert_eq!((players.0[0].lives, players.0[1].lives), (0, 1));
+}
+
+#[test]
+fn foes_aim_at_the_nearest_ship() {
+    let idle = PlayerInput::default();
+    let mut app = co_op_app(co_op_replay(100, idle, idle));
+    let second_ship = ships(&mut app)[1].1;
+
+    app.world.run_system_once(move |mut commands: Commands, config: Res<GameConfig>| {
+        let mut config = config.clone();
+        config.foe.shoot_delay = 0.01;
+        config.foe.speed = 0.0;
+
+        let position = Vec2::new(second_ship.x + 40.0, 200.0);
+        spawn_foe(&mut commands, &SpriteCatalog::default(), &config, &default(), FoeKind::Fighter, position);
+    });
+    app.update();
+
+    let directions: Vec<_> = app.world.query_filtered::<&GDirection, With<FoeProjectile>>()
+        .iter(&app.world)
+        .map(|direction| (direction.x, direction.y))
+        .collect();
+
+    assert!(!directions.is_empty());
+    for (x, y) in directions {
+        // Down and slightly left, towards the ship on the right rather than the one far to the left
+        assert!(y < 0.0);
+        assert!(x < 0.0 && x > -0.5);
+    }
+}
@@ -0,0 +1,190 @@
+use bevy::ecs::system::RunSystemOnce;
+use bevy::prelude::*;
+
+use space_shooter::components::*;
+use space_shooter::config::*;
+use space_shooter::damage::*;
+use space_shooter::difficulty::*;
+use space_shooter::events::*;
+use space_shooter::foe::*;
+use space_shooter::player::*;
+use space_shooter::projectiles::*;
+use space_shooter::resources::*;
+
+mod common;
+use common::*;
+
+const TARGET: Vec2 = Vec2::new(0.0, 300.0);
+
+fn damage_app() -> App {
+    start_playing(gameplay_app())
+}
+
+/// A fighter with plenty of health at `TARGET`, fitted with `defences`
+fn spawn_target(app: &mut App, defences: impl Bundle) -> Entity {
+    let foe = app.world.run_system_once(|mut commands: Commands, sprite_catalog: Res<SpriteCatalog>, config: Res<GameConfig>| {
+        spawn_foe(&mut commands, &sprite_catalog, &config, &DifficultyScaling::default(), FoeKind::Fighter, TARGET)
+    });
+    app.world.entity_mut(foe).insert((Health(100), defences));
+    foe
+}
+
+/// Fires a bullet of `damage` into `TARGET` and plays the tick it hits in, returning what it dealt
+fn hit(app: &mut App, damage: Damage) -> Vec<i32> {
+    app.world.run_system_once(move |
+        mut commands: Commands,
+        mut projectile_pool: ResMut<ProjectilePool>,
+        sprite_catalog: Res<SpriteCatalog>,
+        config: Res<GameConfig>
+    | {
+        let bullet = spawn_bullet(&mut commands, &mut projectile_pool, &sprite_catalog, &config, 0, TARGET).unwrap();
+        commands.entity(bullet).insert(damage);
+    });
+    app.update();
+
+    let events = app.world.resource::<Events<FoeHitEvent>>();
+    events.get_reader().read(events).map(|event| event.damage).collect()
+}
+
+fn shield(time: f32) -> Shield {
+    ShieldConfig { points: 3, recharge_delay: time, recharge_time: time }.shield().unwrap()
+}
+
+#[test]
+fn armor_only_reduces_the_damage_types_it_is_made_for() {
+    let mut app = damage_app();
+    let foe = spawn_target(&mut app, Armor { kinetic: 0.5, ..default() });
+
+    hit(&mut app, Damage::new(6, DamageType::Kinetic));
+    assert_eq!(app.world.get::<Health>(foe).unwrap().0, 97);
+
+    hit(&mut app, Damage::new(6, DamageType::Energy));
+    assert_eq!(app.world.get::<Health>(foe).unwrap().0, 91);
+}
+
+#[test]
+fn the_shield_absorbs_before_armor() {
+    let mut app = damage_app();
+    let foe = spawn_target(&mut app, (Armor { kinetic: 0.5, ..default() }, shield(10.0)));
+
+    let taken = app.world.run_system_once(move |mut defences: Query<Defences>| {
+        defences.get_mut(foe).unwrap().take(Damage::new(10, DamageType::Kinetic))
+    });
+    assert_eq!(taken.kind, DamageType::Kinetic);
+    assert_eq!((taken.raw, taken.absorbed, taken.resisted, taken.dealt), (10, 3, 3, 4));
+    assert_eq!(app.world.get::<Health>(foe).unwrap().0, 96);
+    assert_eq!(app.world.get::<Shield>(foe).unwrap().points, 0);
+
+    // Hits on a foe report what reached its health
+    assert_eq!(hit(&mut app, Damage::new(4, DamageType::Kinetic)), vec![2]);
+    assert_eq!(app.world.get::<Health>(foe).unwrap().0, 94);
+}
+
+#[test]
+fn damage_events_hurt_ships_and_foes_alike() {
+    let mut app = damage_app();
+    let defences = (Armor { energy: 0.5, ..default() }, shield(10.0));
+    let foe = spawn_target(&mut app, defences.clone());
+    let ship = app.world.query_filtered::<Entity, With<SpaceShip>>().single(&app.world);
+    let health = app.world.get::<Health>(ship).unwrap().0;
+    app.world.entity_mut(ship).insert(defences);
+
+    for target in [foe, ship] {
+        app.world.send_event(DamageEvent {
+            target,
+            damage: Damage::new(7, DamageType::Energy),
+            source: DamageSource::Projectile,
+            position: Vec3::ZERO
+        });
+    }
+    app.update();
+
+    // The shield absorbs 3, the armor resists half of the other 4
+    assert_eq!(app.world.get::<Health>(foe).unwrap().0, 98);
+    assert_eq!(app.world.get::<Health>(ship).unwrap().0, health - 2);
+    let foe_hits = app.world.resource::<Events<FoeHitEvent>>();
+    assert_eq!(foe_hits.get_reader().read(foe_hits).map(|hit| hit.damage).collect::<Vec<_>>(), vec![2]);
+    let ship_hits = app.world.resource::<Events<PlayerHitEvent>>();
+    assert_eq!(ship_hits.get_reader().read(ship_hits).map(|hit| hit.damage).collect::<Vec<_>>(), vec![2]);
+}
+
+#[test]
+fn shields_recharge_once_left_alone() {
+    let tick = Time::<Fixed>::default().timestep().as_secs_f32();
+    let mut app = damage_app();
+    let foe = spawn_target(&mut app, shield(tick * 4.0));
+
+    hit(&mut app, Damage::new(2, DamageType::Kinetic));
+    assert_eq!(app.world.get::<Health>(foe).unwrap().0, 100);
+    assert_eq!(app.world.get::<Shield>(foe).unwrap().points, 1);
+
+    // Waits out the delay before recharging a point at a time
+    for _ in 0..4 {
+        app.update();
+    }
+    assert_eq!(app.world.get::<Shield>(foe).unwrap().points, 1);
+
+    for _ in 0..4 {
+        app.update();
+    }
+    assert_eq!(app.world.get::<Shield>(foe).unwrap().points, 2);
+
+    for _ in 0..8 {
+        app.update();
+    }
+    assert_eq!(app.world.get::<Shield>(foe).unwrap().points, 3);
+}
+
+#[test]
+fn ship_shields_absorb_foe_projectiles() {
+    let mut app = damage_app();
+    let config = app.world.resource::<GameConfig>().ship.clone();
+    let ship = app.world.query_filtered::<Entity, With<SpaceShip>>().single(&app.world);
+    app.world.entity_mut(ship).insert(shield(10.0));
+
+    app.world.run_system_once(|
+        mut commands: Commands,
+        mut projectile_pool: ResMut<ProjectilePool>,
+        sprite_catalog: Res<SpriteCatalog>,
+        config: Res<GameConfig>
+    | {
+        let damage = Damage::new(3, DamageType::Energy);
+        spawn_foe_projectile(&mut commands, &mut projectile_pool, &sprite_catalog, &config, Vec2::ZERO, Vec2::NEG_Y, 1.0, damage);
+    });
+    app.update();
+
+    assert_eq!(app.world.get::<Health>(ship).unwrap().0, config.health);
+    assert_eq!(app.world.get::<Shield>(ship).unwrap().points, 0);
+    let hits = app.world.resource::<Events<PlayerHitEvent>>();
+    let hits: Vec<PlayerHitEvent> = hits.get_reader().read(hits).copied().collect();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].damage, 0);
+}
+
+#[test]
+fn rams_go_through_the_ship_defences_once_per_foe() {
+    let mut app = damage_app();
+    let health = app.world.resource::<GameConfig>().ship.health;
+    app.world.resource_mut::<GameConfig>().ship.ram_damage = 5;
+    let (ship, position) = app.world.query_filtered::<(Entity, &Transform), With<SpaceShip>>().single(&app.world);
+    let position = position.translation.truncate();
+    app.world.entity_mut(ship).insert((Armor { kinetic: 0.5, ..default() }, shield(10.0)));
+
+    let foe = app.world.run_system_once(move |mut commands: Commands, sprite_catalog: Res<SpriteCatalog>, config: Res<GameConfig>| {
+        spawn_foe(&mut commands, &sprite_catalog, &config, &DifficultyScaling::default(), FoeKind::Fighter, position)
+    });
+    app.update();
+
+    // The shield takes 3 of the 5 points before the armor halves the rest
+    assert_eq!(app.world.get::<Health>(ship).unwrap().0, health - 1);
+    assert_eq!(app.world.get::<Shield>(ship).unwrap().points, 0);
+    assert!(app.world.get::<Rammed>(foe).is_some());
+    let hits = app.world.resource::<Events<PlayerHitEvent>>();
+    let hits: Vec<PlayerHitEvent> = hits.get_reader().read(hits).copied().collect();
+    assert_eq!(hits.len(), 1);
+    assert_eq!((hits[0].damage, hits[0].fatal), (1, false));
+
+    app.world.entity_mut(foe).insert(Transform::from_translation(position.extend(0.0)));
+    app.update();
+    assert_eq!(app.world.get::<Health>(ship).unwrap().0, health - 1);
+}
@@ -0,0 +1,27 @@
+#![cfg(feature = "dev")]
+
+use space_shooter::components::*;
+use space_shooter::debug::*;
+
+#[test]
+fn console_commands_are_parsed() {
+    assert_eq!("god".parse(), Ok(ConsoleCommand::God));
+    assert_eq!("spawn heavy -120".parse(), Ok(ConsoleCommand::Spawn { kind: FoeKind::Heavy, x: -120.0 }));
+    assert_eq!("spawn turret 40".parse(), Ok(ConsoleCommand::Spawn { kind: FoeKind::Turret, x: 40.0 }));
+    assert_eq!("  wave 3 ".parse(), Ok(ConsoleCommand::Wave(3)));
+    assert_eq!("give bomb".parse(), Ok(ConsoleCommand::Give(Pickup::Bomb)));
+    assert_eq!("timescale 0.5".parse(), Ok(ConsoleCommand::TimeScale(0.5)));
+    assert_eq!("kill_all".parse(), Ok(ConsoleCommand::KillAll));
+    assert_eq!("save run.ron".parse(), Ok(ConsoleCommand::Save("run.ron".into())));
+}
+
+#[test]
+fn bad_console_commands_explain_themselves() {
+    let error = |line: &str| line.parse::<ConsoleCommand>().unwrap_err();
+
+    assert_eq!(error("spawn dragon 0"), "unknown foe 'dragon', expected fighter, heavy, mini_boss, boss, bunker or turret");
+    assert_eq!(error("spawn fighter 900"), "x must be between -320 and 320");
+    assert_eq!(error("wave 0"), "'0' is not a wave, waves start at 1");
+    assert_eq!(error("timescale 0"), "'0' is not a time scale between 0.05 and 10");
+    assert_eq!(error("fly"), "unknown command 'fly', type help to list the commands");
+}
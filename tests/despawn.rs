@@ -0,0 +1,82 @@
+use bevy::ecs::system::RunSystemOnce;
+use bevy::prelude::*;
+
+use space_shooter::components::*;
+use space_shooter::config::*;
+use space_shooter::difficulty::*;
+use space_shooter::events::*;
+use space_shooter::foe::*;
+use space_shooter::player::*;
+use space_shooter::projectiles::*;
+use space_shooter::resources::*;
+
+mod common;
+use common::*;
+
+const STACK: Vec2 = Vec2::new(0.0, 300.0);
+
+fn despawn_app() -> App {
+    start_playing(gameplay_app())
+}
+
+/// Foes all on top of each other, each one going down to a single bullet or a bomb
+fn stack_foes(app: &mut App, count: usize) {
+    app.world.run_system_once(move |mut commands: Commands, sprite_catalog: Res<SpriteCatalog>, config: Res<GameConfig>| {
+        for _ in 0..count {
+            let foe = spawn_foe(&mut commands, &sprite_catalog, &config, &DifficultyScaling::default(), FoeKind::Fighter, STACK);
+            commands.entity(foe).insert(Health(1));
+        }
+    });
+}
+
+fn fire_into_stack(app: &mut App, count: usize) {
+    app.world.run_system_once(move |
+        mut commands: Commands,
+        mut projectile_pool: ResMut<ProjectilePool>,
+        sprite_catalog: Res<SpriteCatalog>,
+        config: Res<GameConfig>
+    | {
+        for _ in 0..count {
+            spawn_bullet(&mut commands, &mut projectile_pool, &sprite_catalog, &config, 0, STACK);
+        }
+    });
+}
+
+fn count<F: bevy::ecs::query::ReadOnlyWorldQuery>(app: &mut App) -> usize {
+    app.world.query_filtered::<(), F>().iter(&app.world).count()
+}
+
+#[test]
+fn a_bullet_hits_one_of_the_stacked_foes() {
+    let mut app = despawn_app();
+    stack_foes(&mut app, 3);
+    fire_into_stack(&mut app, 2);
+
+    app.update();
+
+    assert_eq!(count::<With<Foe>>(&mut app), 1);
+    assert_eq!(count::<With<Bullet>>(&mut app), 0);
+    assert_eq!(count::<With<Dead>>(&mut app), 0);
+    assert_eq!(app.world.resource::<Events<FoeKilledEvent>>().len(), 2);
+    assert_eq!(app.world.resource::<Events<FoeHitEvent>>().len(), 2);
+    assert_eq!(app.world.resource::<Score>().0, 2 * app.world.resource::<GameConfig>().foe.score);
+    assert_eq!(app.world.resource::<ProjectilePool>().active(ProjectileKind::Bullet), 0);
+}
+
+#[test]
+fn bullets_fly_through_foes_bombed_in_the_same_tick() {
+    let mut app = despawn_app();
+    stack_foes(&mut app, 3);
+    fire_into_stack(&mut app, 2);
+
+    app.world.resource_mut::<Input<KeyCode>>().press(KeyCode::X);
+    app.update();
+
+    // Every foe dies once, to the bomb
+    assert_eq!(count::<With<Foe>>(&mut app), 0);
+    assert_eq!(app.world.resource::<Events<FoeKilledEvent>>().len(), 3);
+    assert_eq!(app.world.resource::<Events<FoeHitEvent>>().len(), 0);
+    assert_eq!(count::<With<Bullet>>(&mut app), 2);
+    assert_eq!(count::<With<Dead>>(&mut app), 0);
+}
+
@@ -0,0 +1,87 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+
+use space_shooter::components::*;
+use space_shooter::config::*;
+use space_shooter::difficulty::*;
+use space_shooter::events::*;
+use space_shooter::resources::*;
+use space_shooter::states::*;
+
+fn playing_app(difficulty: Difficulty, rank: bool) -> App {
+    let mut app = App::new();
+    app.add_plugins((
+            MinimalPlugins,
+            ConfigPlugin { path: PathBuf::from("assets/config.ron") },
+            DifficultyPlugin
+        ))
+        .add_state::<GameState>()
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(100)))
+        .insert_resource(difficulty)
+        .insert_resource(RunStart(StartOptions { difficulty, rank, ..default() }));
+
+    app.world.resource_mut::<NextState<GameState>>().set(GameState::Playing);
+    app.update();
+    app
+}
+
+fn kill_foes(app: &mut App, count: usize) {
+    for _ in 0..count {
+        app.world.send_event(FoeKilledEvent { foe: Entity::PLACEHOLDER, kind: FoeKind::Fighter, position: Vec3::ZERO });
+    }
+    app.update();
+}
+
+#[test]
+fn presets_get_harder_in_order() {
+    let config = GameConfig::default();
+    let presets: Vec<_> = Difficulty::ALL.iter().map(|difficulty| config.scaling(*difficulty)).collect();
+
+    assert_eq!(config.scaling(Difficulty::Normal), DifficultyScaling::default());
+    for pair in presets.windows(2) {
+        assert!(pair[0].health < pair[1].health);
+        assert!(pair[0].projectile_speed < pair[1].projectile_speed);
+        assert!(pair[0].fire_rate < pair[1].fire_rate);
+        assert!(pair[0].spawn_density < pair[1].spawn_density);
+    }
+}
+
+#[test]
+fn scaling_never_drops_below_one_foe_or_one_health() {
+    let scaling = DifficultyScaling { health: 0.1, projectile_speed: 1.0, fire_rate: 1.0, spawn_density: 0.1 };
+
+    assert_eq!(scaling.foe_health(2), 1);
+    assert_eq!(scaling.wave_foes(3), 1);
+    assert_eq!(DifficultyScaling::default().scaled(1.5).wave_foes(10), 15);
+}
+
+#[test]
+fn chosen_preset_is_applied_without_rank() {
+    let mut app = playing_app(Difficulty::Hard, false);
+    kill_foes(&mut app, 20);
+
+    let hard = GameConfig::default().scaling(Difficulty::Hard);
+    assert_eq!(app.world.resource::<Rank>().value, 0.0);
+    assert_eq!(*app.world.resource::<DifficultyScaling>(), hard);
+}
+
+#[test]
+fn rank_rises_with_kills_and_falls_with_damage() {
+    let mut app = playing_app(Difficulty::Normal, true);
+
+    kill_foes(&mut app, 20);
+    let raised = app.world.resource::<Rank>().value;
+    assert!(raised > 0.0);
+    assert!(app.world.resource::<DifficultyScaling>().fire_rate > 1.0);
+
+    app.world.send_event(PlayerHitEvent { ship: Entity::PLACEHOLDER, position: Vec3::ZERO, damage: 100, fatal: true });
+    app.update();
+
+    // Clamped to the lowest rank, then regained a little over the remaining ticks of the frame
+    let rank = app.world.resource::<Rank>().value;
+    assert!(rank < -0.99);
+    assert!(app.world.resource::<DifficultyScaling>().fire_rate < 1.0);
+}
@@ -0,0 +1,116 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+
+use space_shooter::background::*;
+use space_shooter::components::*;
+use space_shooter::config::*;
+use space_shooter::difficulty::*;
+use space_shooter::endless::*;
+use space_shooter::level::*;
+use space_shooter::resources::*;
+use space_shooter::states::*;
+
+fn endless_app(seed: u64) -> App {
+    let start = StartOptions { seed: Some(seed), mode: GameMode::Endless, ..default() };
+
+    let mut app = App::new();
+    app.add_plugins((
+            MinimalPlugins,
+            ConfigPlugin { path: PathBuf::from("assets/config.ron") },
+            DifficultyPlugin,
+            EndlessPlugin
+        ))
+        .add_state::<GameState>()
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(250)))
+        .insert_resource(RunStart(start))
+        .insert_resource(GameRng::new(seed))
+        .insert_resource(SpriteCatalog::default())
+        .insert_resource(Wave(0))
+        .insert_resource(Score(0))
+        .insert_resource(Players(vec![PlayerState { lives: 3, bombs: 3, ..default() }]))
+        .init_resource::<ScrollPosition>()
+        .init_resource::<Levels>();
+
+    app.world.resource_mut::<NextState<GameState>>().set(GameState::Playing);
+    app.update();
+    app
+}
+
+fn spawned_foes(app: &mut App) -> Vec<(FoeKind, i32)> {
+    let mut foes: Vec<_> = app.world.query::<(&FoeKind, &Transform)>()
+        .iter(&app.world)
+        .map(|(kind, transform)| (*kind, transform.translation.x as i32))
+        .collect();
+    foes.sort_by_key(|(kind, x)| (kind.name(), *x));
+    foes
+}
+
+#[test]
+fn curve_is_interpolated_and_holds_after_the_last_point() {
+    let config = EndlessConfig::default();
+
+    let start = config.sample(0.0);
+    assert_eq!(start.interval, 1.4);
+
+    let between = config.sample(30.0);
+    assert!((between.interval - 1.15).abs() < 1e-5);
+    assert!((between.heavy_share - 0.075).abs() < 1e-5);
+
+    let late = config.sample(10000.0);
+    assert_eq!((late.interval, late.heavy_share), (0.3, 0.5));
+}
+
+#[test]
+fn unsorted_curves_are_rejected() {
+    let errors = GameConfig::parse("(endless: (curve: [(time: 10.0, interval: 1.0, heavy_share: 0.0), (time: 5.0, interval: 1.0, heavy_share: 0.0)]))").unwrap_err();
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].to_string(), "endless.curve[1].time is 5, it must be between 10 and 36000");
+}
+
+#[test]
+fn spawns_follow_the_seed() {
+    let mut first = endless_app(11);
+    let mut second = endless_app(11);
+    for _ in 0..40 {
+        first.update();
+        second.update();
+    }
+
+    let foes = spawned_foes(&mut first);
+    assert!(foes.len() > 5);
+    assert_eq!(foes, spawned_foes(&mut second));
+}
+
+#[test]
+fn every_wave_after_the_first_brings_a_mini_boss() {
+    let mut app = endless_app(3);
+    let wave_time = app.world.resource::<GameConfig>().endless.wave_time;
+
+    // Slightly over two waves
+    let updates = (wave_time * 2.0 / 0.25) as usize + 4;
+    for _ in 0..updates {
+        app.update();
+    }
+
+    let mini_bosses = spawned_foes(&mut app).iter().filter(|(kind, _)| *kind == FoeKind::MiniBoss).count();
+    assert_eq!(app.world.resource::<Wave>().0, 3);
+    assert_eq!(mini_bosses, 2);
+}
+
+#[test]
+fn milestones_alternate_bombs_and_lives() {
+    let mut app = endless_app(5);
+    let config = app.world.resource::<GameConfig>().endless.clone();
+
+    app.world.resource_mut::<Score>().0 = config.milestone_score * config.life_milestones;
+    app.update();
+
+    assert_eq!(app.world.resource::<EndlessRun>().milestones, config.life_milestones);
+    let player = &app.world.resource::<Players>().0[0];
+    assert_eq!(player.bombs, 3 + config.life_milestones - 1);
+    assert_eq!(player.lives, 4);
+}
@@ -0,0 +1,97 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use bevy_parallax::ParallaxCameraComponent;
+
+use space_shooter::constants::*;
+use space_shooter::events::*;
+use space_shooter::feedback::*;
+
+fn feedback_app(settings: FeedbackSettings) -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, FeedbackPlugin))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(16)))
+        .insert_resource(settings);
+    app.update();
+    app
+}
+
+fn spawn_camera(app: &mut App) -> Entity {
+    app.world.spawn((Transform::from_xyz(0.0, 500.0, 0.0), ParallaxCameraComponent::default())).id()
+}
+
+fn hit_ship(app: &mut App, ship: Entity, fatal: bool) {
+    app.world.send_event(PlayerHitEvent { ship, position: Vec3::ZERO, damage: 1, fatal });
+}
+
+#[test]
+fn hit_foes_flash_then_go_back_to_their_colors() {
+    let mut app = feedback_app(FeedbackSettings::default());
+    let foe = app.world.spawn(TextureAtlasSprite::default()).id();
+
+    app.world.send_event(FoeHitEvent { foe, position: Vec3::ZERO, damage: 1 });
+    app.update();
+    app.update();
+    assert!(app.world.get::<DamageFlash>(foe).is_some());
+    assert_eq!(app.world.get::<TextureAtlasSprite>(foe).unwrap().color, Color::rgb(FEEDBACK_FLASH_BRIGHTNESS, FEEDBACK_FLASH_BRIGHTNESS, FEEDBACK_FLASH_BRIGHTNESS));
+
+    for _ in 0..10 {
+        app.update();
+    }
+    assert!(app.world.get::<DamageFlash>(foe).is_none());
+    assert_eq!(app.world.get::<TextureAtlasSprite>(foe).unwrap().color, Color::WHITE);
+}
+
+#[test]
+fn camera_shake_decays_back_to_where_the_camera_was() {
+    let mut app = feedback_app(FeedbackSettings::default());
+    let camera = spawn_camera(&mut app);
+    let ship = app.world.spawn_empty().id();
+
+    hit_ship(&mut app, ship, false);
+    app.update();
+    let trauma = app.world.resource::<CameraShake>().trauma;
+    assert!(trauma > 0.0 && trauma < FEEDBACK_TRAUMA_PLAYER_HIT);
+
+    // Gone within a second, taking its offset with it
+    for _ in 0..60 {
+        app.update();
+    }
+    assert_eq!(app.world.resource::<CameraShake>().trauma, 0.0);
+    let translation = app.world.get::<Transform>(camera).unwrap().translation;
+    assert!(translation.distance(Vec3::new(0.0, 500.0, 0.0)) < 1e-3, "camera left at {}", translation);
+}
+
+#[test]
+fn fatal_hits_stop_time_for_a_moment() {
+    let mut app = feedback_app(FeedbackSettings::default());
+    let ship = app.world.spawn_empty().id();
+
+    hit_ship(&mut app, ship, true);
+    app.update();
+    assert!(app.world.resource::<HitStop>().is_active());
+    assert!(app.world.resource::<Time<Virtual>>().is_paused());
+
+    for _ in 0..(FEEDBACK_HIT_STOP_TIME / 0.016).ceil() as usize {
+        app.update();
+    }
+    assert!(!app.world.resource::<HitStop>().is_active());
+    assert!(!app.world.resource::<Time<Virtual>>().is_paused());
+}
+
+#[test]
+fn reduced_effects_turn_off_flashes_and_shake() {
+    let mut app = feedback_app(FeedbackSettings { reduce_effects: true, ..default() });
+    let camera = spawn_camera(&mut app);
+    let ship = app.world.spawn(TextureAtlasSprite::default()).id();
+
+    hit_ship(&mut app, ship, false);
+    app.update();
+    app.update();
+
+    assert!(app.world.get::<DamageFlash>(ship).is_none());
+    assert_eq!(app.world.get::<TextureAtlasSprite>(ship).unwrap().color, Color::WHITE);
+    assert_eq!(app.world.resource::<CameraShake>().trauma, 0.0);
+    assert_eq!(app.world.get::<Transform>(camera).unwrap().translation, Vec3::new(0.0, 500.0, 0.0));
+}
@@ -0,0 +1,211 @@
+use std::fs;
+use std::path::PathBuf;
+
+use bevy::ecs::system::RunSystemOnce;
+use bevy::prelude::*;
+
+use space_shooter::components::*;
+use space_shooter::config::*;
+use space_shooter::difficulty::*;
+use space_shooter::events::*;
+use space_shooter::formation::*;
+use space_shooter::level::*;
+use space_shooter::resources::*;
+use space_shooter::snapshot::*;
+
+mod common;
+use common::*;
+
+fn formation_app() -> App {
+    let mut app = gameplay_app();
+    app.add_plugins(FormationPlugin);
+    start_playing(app)
+}
+
+fn launch(app: &mut App, data: SquadronData, origin: Vec2) -> Entity {
+    app.world.run_system_once(move |mut commands: Commands, sprite_catalog: Res<SpriteCatalog>, config: Res<GameConfig>| {
+        spawn_squadron(&mut commands, &sprite_catalog, &config, &DifficultyScaling::default(), &data, origin)
+    })
+}
+
+fn members(app: &mut App) -> Vec<(Entity, Vec3, SquadronMember, Option<Vec2>)> {
+    app.world.query::<(Entity, &Transform, &SquadronMember, Option<&Diving>)>()
+        .iter(&app.world)
+        .map(|(entity, transform, member, diving)| (entity, transform.translation, *member, diving.map(|diving| diving.0)))
+        .collect()
+}
+
+fn destroy_members(app: &mut App) {
+    for (entity, ..) in members(app) {
+        app.world.entity_mut(entity).insert(Health(0));
+    }
+    app.update();
+}
+
+fn squadron_events(app: &App) -> Vec<SquadronDestroyedEvent> {
+    let events = app.world.resource::<Events<SquadronDestroyedEvent>>();
+    events.get_reader().read(events).copied().collect()
+}
+
+#[test]
+fn formations_lay_out_every_member_around_the_leader() {
+    for formation in [Formation::V, Formation::Line, Formation::Column, Formation::Circle, Formation::Grid] {
+        let offsets = formation.offsets(9, 40.0);
+        assert_eq!(offsets.len(), 9, "{:?}", formation);
+        for (index, offset) in offsets.iter().enumerate() {
+            assert!(offsets[index + 1..].iter().all(|other| other.distance(*offset) > 1.0), "{:?}", formation);
+        }
+    }
+
+    assert_eq!(Formation::V.offsets(3, 40.0), vec![Vec2::ZERO, Vec2::new(-40.0, 40.0), Vec2::new(40.0, 40.0)]);
+    assert_eq!(Formation::Line.offsets(3, 40.0), vec![Vec2::new(-40.0, 0.0), Vec2::ZERO, Vec2::new(40.0, 0.0)]);
+    assert_eq!(Formation::Grid.offsets(4, 40.0)[3], Vec2::new(20.0, 40.0));
+
+    let circle = Formation::Circle.offsets(6, 40.0);
+    assert!(circle.iter().all(|offset| (offset.length() - circle[0].length()).abs() < 1e-3));
+}
+
+#[test]
+fn members_keep_their_offsets_along_the_leader_path() {
+    let mut app = formation_app();
+    let data = SquadronData {
+        formation: Formation::Grid,
+        size: 6,
+        path: FormationPath::Weave { amplitude: 80.0, period: 1.0 },
+        ..default()
+    };
+    let squadron = launch(&mut app, data, Vec2::new(0.0, 200.0));
+
+    for _ in 0..30 {
+        app.update();
+    }
+
+    let leader = app.world.get::<Squadron>(squadron).unwrap().leader();
+    assert!(leader.x.abs() > 1.0);
+    let members = members(&mut app);
+    assert_eq!(members.len(), 6);
+    for (_, translation, member, _) in members {
+        assert_eq!(member.squadron, squadron);
+        assert!((translation.truncate() - leader - member.offset).length() < 1e-3);
+    }
+}
+
+#[test]
+fn destroying_every_member_pays_the_squadron_bonus() {
+    let mut app = formation_app();
+    let data = SquadronData { size: 4, bonus: 700, ..default() };
+    let squadron = launch(&mut app, data, Vec2::new(0.0, 100.0));
+    app.update();
+
+    destroy_members(&mut app);
+
+    let kill_score = app.world.resource::<GameConfig>().foe.score * 4;
+    assert_eq!(app.world.resource::<Score>().0, kill_score + 700);
+    assert_eq!(app.world.resource::<Players>().0[0].score, kill_score + 700);
+    let events = squadron_events(&app);
+    assert_eq!(events.len(), 1);
+    assert_eq!((events[0].player, events[0].bonus), (0, 700));
+    assert!(app.world.get_entity(squadron).is_none());
+}
+
+#[test]
+fn squadrons_lose_their_bonus_once_a_member_gets_away() {
+    let mut app = formation_app();
+    let data = SquadronData { formation: Formation::Column, size: 3, spacing: 200.0, ..default() };
+
+    // The leader is already past the bottom of the screen
+    let squadron = launch(&mut app, data, Vec2::new(0.0, -300.0));
+    app.update();
+    app.update();
+
+    assert_eq!(members(&mut app).len(), 2);
+    assert!(app.world.get::<Squadron>(squadron).unwrap().escaped);
+
+    destroy_members(&mut app);
+
+    let kill_score = app.world.resource::<GameConfig>().foe.score * 2;
+    assert_eq!(app.world.resource::<Score>().0, kill_score);
+    assert!(squadron_events(&app).is_empty());
+    assert!(app.world.get_entity(squadron).is_none());
+}
+
+#[test]
+fn members_break_off_one_by_one_to_dive_at_the_ship() {
+    let tick = Time::<Fixed>::default().timestep().as_secs_f32();
+    let mut app = formation_app();
+    let data = SquadronData { size: 3, break_off: Some(0.0), dive_interval: tick * 2.0, ..default() };
+    launch(&mut app, data, Vec2::new(0.0, 150.0));
+    let ship = app.world.query_filtered::<&Transform, With<SpaceShip>>().single(&app.world).translation;
+
+    for _ in 0..3 {
+        app.update();
+    }
+
+    let divers: Vec<_> = members(&mut app).into_iter().filter(|(.., diving)| diving.is_some()).collect();
+    assert_eq!(divers.len(), 1);
+    let (_, translation, member, velocity) = divers[0];
+    assert_eq!(member.offset, Vec2::new(48.0, 48.0));
+    let towards_ship = (ship - translation).truncate().normalize();
+    assert!(velocity.unwrap().normalize().dot(towards_ship) > 0.99);
+
+    for _ in 0..4 {
+        app.update();
+    }
+    assert!(members(&mut app).iter().all(|(.., diving)| diving.is_some()));
+}
+
+#[test]
+fn squadrons_are_restored_mid_flight() {
+    let tick = Time::<Fixed>::default().timestep().as_secs_f32();
+    let mut app = formation_app();
+    let data = SquadronData {
+        formation: Formation::Circle,
+        size: 6,
+        path: FormationPath::Weave { amplitude: 60.0, period: 2.0 },
+        break_off: Some(tick * 20.0),
+        dive_interval: tick * 10.0,
+        ..default()
+    };
+    launch(&mut app, data, Vec2::new(0.0, 300.0));
+    for _ in 0..35 {
+        app.update();
+    }
+
+    let snapshot = GameSnapshot::capture(&mut app.world);
+    assert_eq!(snapshot.squadrons.len(), 1);
+    assert!(snapshot.foes.iter().any(|foe| foe.diving.is_some()));
+    let saved = ron::to_string(&snapshot).unwrap();
+    for _ in 0..40 {
+        app.update();
+    }
+    let expected = ron::to_string(&GameSnapshot::capture(&mut app.world)).unwrap();
+
+    snapshot.restore(&mut app.world);
+    assert_eq!(ron::to_string(&GameSnapshot::capture(&mut app.world)).unwrap(), saved);
+    for _ in 0..40 {
+        app.update();
+    }
+    assert_eq!(ron::to_string(&GameSnapshot::capture(&mut app.world)).unwrap(), expected);
+}
+
+#[test]
+fn shipped_levels_bring_in_squadrons() {
+    for name in LEVEL_FILES {
+        let contents = fs::read_to_string(PathBuf::from("assets").join(name.path())).unwrap();
+        let level = LevelData::parse(&contents).unwrap();
+        assert!(level.timeline.iter().any(|marker| matches!(marker.event, TimelineEvent::Squadron(_))));
+    }
+}
+
+#[test]
+fn squadrons_out_of_range_are_rejected() {
+    let level = |squadron: &str| format!(
+        "(name: \"Test\", background: (layers: []), length: 1000.0, timeline: [(distance: 100.0, event: Squadron(({})))])",
+        squadron
+    );
+
+    assert!(LevelData::parse(&level("size: 4, break_off: Some(1.0)")).is_ok());
+    for squadron in ["dive_interval: -1.0", "size: 0", "size: 10000", "speed: -50.0", "path: Weave(amplitude: 40.0, period: 0.0)"] {
+        assert!(LevelData::parse(&level(squadron)).is_err(), "{}", squadron);
+    }
+}
@@ -0,0 +1,124 @@
+use bevy::ecs::system::RunSystemOnce;
+use bevy::prelude::*;
+
+use space_shooter::components::*;
+use space_shooter::config::*;
+use space_shooter::damage::*;
+use space_shooter::difficulty::*;
+use space_shooter::foe::*;
+use space_shooter::projectiles::*;
+use space_shooter::resources::*;
+
+mod common;
+use common::*;
+
+/// Playing with a ship left without its shield, so hits come straight off its health
+fn graze_app() -> App {
+    let mut app = start_playing(gameplay_app());
+    let ship = app.world.query_filtered::<Entity, With<SpaceShip>>().single(&app.world);
+    app.world.entity_mut(ship).remove::<Shield>();
+    app
+}
+
+/// Drops a foe projectile from above the ship, `x` away from its core
+fn drop_projectile(app: &mut App, x: f32) {
+    app.world.run_system_once(move |
+        mut commands: Commands,
+        mut projectile_pool: ResMut<ProjectilePool>,
+        sprite_catalog: Res<SpriteCatalog>,
+        config: Res<GameConfig>
+    | {
+        spawn_foe_projectile(
+            &mut commands,
+            &mut projectile_pool,
+            &sprite_catalog,
+            &config,
+            Vec2::new(x, 80.0),
+            Vec2::NEG_Y,
+            400.0,
+            Damage::new(1, DamageType::Energy)
+        );
+    });
+}
+
+fn ship(app: &mut App) -> (Vec3, i32) {
+    let (transform, health) = app.world.query_filtered::<(&Transform, &Health), With<SpaceShip>>()
+        .single(&app.world);
+    (transform.translation, health.0)
+}
+
+#[test]
+fn projectiles_passing_by_the_core_are_grazed_once() {
+    let mut app = graze_app();
+    let (_, health) = ship(&mut app);
+
+    // Well within the ship sprite, but clear of its core
+    drop_projectile(&mut app, 20.0);
+    for _ in 0..40 {
+        app.update();
+    }
+
+    let graze_score = app.world.resource::<GameConfig>().ship.graze_score;
+    let player = &app.world.resource::<Players>().0[0];
+    assert_eq!(player.graze, 1);
+    assert_eq!(player.score, graze_score);
+    assert_eq!(app.world.resource::<Score>().0, graze_score);
+    assert_eq!(ship(&mut app).1, health);
+}
+
+#[test]
+fn projectiles_hitting_the_core_are_not_grazed() {
+    let mut app = graze_app();
+    let (_, health) = ship(&mut app);
+
+    drop_projectile(&mut app, 2.0);
+    for _ in 0..40 {
+        app.update();
+    }
+
+    assert_eq!(app.world.resource::<Players>().0[0].graze, 0);
+    assert_eq!(ship(&mut app).1, health - 1);
+}
+
+#[test]
+fn foes_ram_the_whole_ship_not_only_its_core() {
+    let mut app = graze_app();
+    let (start, health) = ship(&mut app);
+    let ram_damage = app.world.resource::<GameConfig>().ship.ram_damage;
+
+    // Into the side of the ship sprite, clear of its core
+    let position = start.truncate() + Vec2::new(25.0, 0.0);
+    app.world.run_system_once(move |mut commands: Commands, sprite_catalog: Res<SpriteCatalog>, config: Res<GameConfig>| {
+        spawn_foe(&mut commands, &sprite_catalog, &config, &DifficultyScaling::default(), FoeKind::Fighter, position);
+    });
+    app.update();
+
+    assert_eq!(ship(&mut app).1, health - ram_damage);
+}
+
+#[test]
+fn focus_slows_the_ship_and_shows_its_core() {
+    let mut app = graze_app();
+    let config = app.world.resource::<GameConfig>().ship.clone();
+    let tick = Time::<Fixed>::default().timestep().as_secs_f32();
+    let (start, _) = ship(&mut app);
+
+    app.world.resource_mut::<Input<KeyCode>>().press(KeyCode::Right);
+    app.world.resource_mut::<Input<KeyCode>>().press(KeyCode::ShiftLeft);
+    app.update();
+
+    let (focused, _) = ship(&mut app);
+    assert!((focused.x - start.x - config.speed * config.focus_speed * tick).abs() < 1e-3);
+
+    let (core, visibility) = app.world.query_filtered::<(&Transform, &Visibility), With<FocusCore>>()
+        .single(&app.world);
+    assert_eq!(*visibility, Visibility::Visible);
+    assert_eq!(core.translation.truncate(), focused.truncate());
+
+    app.world.resource_mut::<Input<KeyCode>>().release(KeyCode::ShiftLeft);
+    app.update();
+
+    let (unfocused, _) = ship(&mut app);
+    assert!((unfocused.x - focused.x - config.speed * tick).abs() < 1e-3);
+    assert_eq!(*app.world.query_filtered::<&Visibility, With<FocusCore>>().single(&app.world), Visibility::Hidden);
+}
@@ -0,0 +1,164 @@
+use std::fs;
+use std::path::PathBuf;
+
+use bevy::ecs::system::RunSystemOnce;
+use bevy::prelude::*;
+
+use space_shooter::background::*;
+use space_shooter::components::*;
+use space_shooter::config::*;
+use space_shooter::difficulty::*;
+use space_shooter::ground::*;
+use space_shooter::level::*;
+use space_shooter::resources::*;
+use space_shooter::snapshot::*;
+
+mod common;
+use common::*;
+
+const SCROLL_SPEED: f32 = 120.0;
+const TERRAIN_SPEED: f32 = 0.8;
+
+fn ground_app() -> App {
+    let mut app = gameplay_app();
+    app.add_plugins(GroundPlugin)
+        .insert_resource(ScrollSpeed::new(SCROLL_SPEED))
+        .insert_resource(TerrainSpeed(TERRAIN_SPEED));
+    start_playing(app)
+}
+
+fn place(app: &mut App, kind: FoeKind, position: Vec2) -> Entity {
+    app.world.run_system_once(move |mut commands: Commands, sprite_catalog: Res<SpriteCatalog>, config: Res<GameConfig>| {
+        spawn_ground_foe(&mut commands, &sprite_catalog, &config, &DifficultyScaling::default(), kind, position, TERRAIN_SPEED)
+    })
+}
+
+fn foe_projectiles(app: &mut App) -> Vec<Vec2> {
+    app.world.query_filtered::<&GDirection, With<FoeProjectile>>()
+        .iter(&app.world)
+        .map(|direction| Vec2::new(direction.x, direction.y))
+        .collect()
+}
+
+fn state(app: &mut App) -> String {
+    ron::to_string(&GameSnapshot::capture(&mut app.world)).unwrap()
+}
+
+#[test]
+fn ground_foes_scroll_with_the_terrain_and_are_flown_over() {
+    let tick = Time::<Fixed>::default().timestep().as_secs_f32();
+    let mut app = ground_app();
+    let ship = app.world.query_filtered::<&Transform, With<SpaceShip>>().single(&app.world).translation;
+    let bunker = place(&mut app, FoeKind::Bunker, ship.truncate());
+
+    for _ in 0..10 {
+        app.update();
+    }
+
+    let position = app.world.get::<Transform>(bunker).unwrap().translation;
+    assert!((position.y - ship.y - SCROLL_SPEED * TERRAIN_SPEED * tick * 10.0).abs() < 1e-3);
+    assert_eq!(position.z, space_shooter::constants::GROUND_Z);
+    assert!(app.world.get::<ShootingDelay>(bunker).is_none());
+
+    // Right under the ship all along without ramming it
+    let health = app.world.query_filtered::<&Health, With<SpaceShip>>().single(&app.world).0;
+    assert_eq!(health, app.world.resource::<GameConfig>().ship.health);
+}
+
+#[test]
+fn turrets_turn_towards_the_ship_before_firing() {
+    let tick = Time::<Fixed>::default().timestep().as_secs_f32();
+    let mut app = ground_app();
+    app.world.resource_mut::<GameConfig>().turret.turn_rate = 0.3;
+    let config = app.world.resource::<GameConfig>().turret.clone();
+    app.world.resource_mut::<ScrollSpeed>().stop();
+    app.world.resource_mut::<ScrollSpeed>().current = 0.0;
+
+    let ship = app.world.query_filtered::<&Transform, With<SpaceShip>>().single(&app.world).translation.truncate();
+    let position = ship + Vec2::new(200.0, 200.0);
+    let turret = place(&mut app, FoeKind::Turret, position);
+    app.update();
+
+    // Comes in pointing down, a tick of turning away from it
+    let angle = app.world.get::<Turret>(turret).unwrap().angle;
+    assert!((angle + std::f32::consts::FRAC_PI_2 + config.turn_rate * tick).abs() < 1e-4);
+
+    // Reloaded long before it lines up with the ship, so it holds its fire while it turns
+    let wanted = (ship - position).normalize();
+    let mut ticks = 1;
+    while foe_projectiles(&mut app).is_empty() {
+        let direction = app.world.get::<Turret>(turret).unwrap().direction();
+        assert!(direction.angle_between(wanted).abs() > config.aim_tolerance - 1e-3, "fired off target");
+        app.update();
+        ticks += 1;
+        assert!(ticks < 600, "never fired");
+    }
+
+    let fired = foe_projectiles(&mut app)[0];
+    assert!(fired.angle_between(wanted).abs() <= config.aim_tolerance);
+    assert!(ticks as f32 * tick > config.shoot_delay);
+}
+
+#[test]
+fn destroyed_ground_foes_leave_wrecks_on_the_terrain() {
+    let tick = Time::<Fixed>::default().timestep().as_secs_f32();
+    let mut app = ground_app();
+    let turret = place(&mut app, FoeKind::Turret, Vec2::new(-100.0, 150.0));
+    app.update();
+    let transform = *app.world.get::<Transform>(turret).unwrap();
+
+    app.world.entity_mut(turret).insert(Health(0));
+    app.update();
+
+    assert!(app.world.get_entity(turret).is_none());
+    let (wreck, wreck_transform) = app.world.query::<(&Wreck, &Transform)>().single(&app.world);
+    assert_eq!(wreck.0, FoeKind::Turret);
+    // Its gun stays turned the way it last pointed
+    assert_ne!(wreck_transform.rotation, Quat::IDENTITY);
+    assert_eq!(wreck_transform.translation.x, transform.translation.x);
+
+    let start = wreck_transform.translation.y;
+    for _ in 0..10 {
+        app.update();
+    }
+    let y = app.world.query_filtered::<&Transform, With<Wreck>>().single(&app.world).translation.y;
+    assert!((y - start - SCROLL_SPEED * TERRAIN_SPEED * tick * 10.0).abs() < 1e-3);
+}
+
+#[test]
+fn ground_foes_and_wrecks_are_restored() {
+    let mut app = ground_app();
+    place(&mut app, FoeKind::Turret, Vec2::new(120.0, 200.0));
+    let bunker = place(&mut app, FoeKind::Bunker, Vec2::new(-120.0, 200.0));
+    app.update();
+    app.world.entity_mut(bunker).insert(Health(0));
+    for _ in 0..20 {
+        app.update();
+    }
+
+    let snapshot = GameSnapshot::capture(&mut app.world);
+    assert_eq!(snapshot.wrecks.len(), 1);
+    assert!(snapshot.foes.iter().any(|foe| foe.turret.is_some() && foe.grounded == Some(TERRAIN_SPEED)));
+    let saved = ron::to_string(&snapshot).unwrap();
+    for _ in 0..120 {
+        app.update();
+    }
+    let expected = state(&mut app);
+
+    snapshot.restore(&mut app.world);
+    assert_eq!(state(&mut app), saved);
+    for _ in 0..120 {
+        app.update();
+    }
+    assert_eq!(state(&mut app), expected);
+}
+
+#[test]
+fn shipped_levels_put_ground_foes_on_the_terrain() {
+    for name in LEVEL_FILES {
+        let contents = fs::read_to_string(PathBuf::from("assets").join(name.path())).unwrap();
+        let level = LevelData::parse(&contents).unwrap();
+        assert!(level.timeline.iter().any(|marker| matches!(marker.event, TimelineEvent::Ground { kind: FoeKind::Turret, .. })));
+        assert!(level.background.terrain_speed() > 0.0);
+    }
+}
@@ -0,0 +1,58 @@
+use space_shooter::constants::*;
+use space_shooter::leaderboard::*;
+use space_shooter::resources::*;
+
+fn entry(score: u32) -> LeaderboardEntry {
+    LeaderboardEntry { score, wave: 1, difficulty: Difficulty::Normal, time: 60.0 }
+}
+
+#[test]
+fn runs_are_ranked_by_score() {
+    let mut leaderboard = Leaderboard::default();
+
+    assert_eq!(leaderboard.submit(GameMode::Campaign, entry(500)), Some(0));
+    assert_eq!(leaderboard.submit(GameMode::Campaign, entry(900)), Some(0));
+    assert_eq!(leaderboard.submit(GameMode::Campaign, entry(700)), Some(1));
+    // Ties go below earlier runs
+    assert_eq!(leaderboard.submit(GameMode::Campaign, entry(700)), Some(2));
+
+    let scores: Vec<_> = leaderboard.entries(GameMode::Campaign).iter().map(|entry| entry.score).collect();
+    assert_eq!(scores, vec![900, 700, 700, 500]);
+    assert_eq!(leaderboard.best(GameMode::Campaign), 900);
+}
+
+#[test]
+fn modes_have_separate_categories() {
+    let mut leaderboard = Leaderboard::default();
+    leaderboard.submit(GameMode::Endless, entry(1200));
+
+    assert!(leaderboard.entries(GameMode::Campaign).is_empty());
+    assert_eq!(leaderboard.best(GameMode::Endless), 1200);
+    assert_eq!(leaderboard.best(GameMode::Campaign), 0);
+}
+
+#[test]
+fn only_the_best_runs_are_kept() {
+    let mut leaderboard = Leaderboard::default();
+    for score in 1..=LEADERBOARD_SIZE as u32 {
+        leaderboard.submit(GameMode::Endless, entry(score * 100));
+    }
+
+    assert_eq!(leaderboard.submit(GameMode::Endless, entry(50)), None);
+    assert_eq!(leaderboard.submit(GameMode::Endless, entry(150)), Some(LEADERBOARD_SIZE - 1));
+    assert_eq!(leaderboard.entries(GameMode::Endless).len(), LEADERBOARD_SIZE);
+    assert_eq!(leaderboard.entries(GameMode::Endless).last().unwrap().score, 150);
+}
+
+#[test]
+fn leaderboard_survives_a_restart() {
+    let path = std::env::temp_dir().join("space_shooter_leaderboard_test.ron");
+    let mut leaderboard = Leaderboard::default();
+    leaderboard.submit(GameMode::Campaign, entry(300));
+    leaderboard.submit(GameMode::Endless, entry(800));
+
+    leaderboard.save(&path).unwrap();
+    assert_eq!(Leaderboard::load(&path), leaderboard);
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,101 @@
+use bevy::prelude::*;
+
+use space_shooter::background::*;
+use space_shooter::events::*;
+use space_shooter::level::*;
+use space_shooter::resources::*;
+use space_shooter::sound::*;
+
+mod common;
+use common::*;
+
+/// Each kind of marker once, with the swap slowing the scroll down
+const LEVEL: &str = r#"(
+    name: "Test",
+    background: (layers: [(image: Layer1, speed: 0.9)], scroll_speed: 128.0),
+    length: 100000.0,
+    timeline: [
+        (distance: 0.0, event: Music(Stage1)),
+        (distance: 40.0, event: Wave(foes: 2, spacing: 50000.0)),
+        (distance: 90.0, event: Warning("Incoming")),
+        (distance: 150.0, event: Background((layers: [(image: Stars, speed: 0.5)], scroll_speed: 64.0))),
+        (distance: 200.0, event: Boss)
+    ]
+)"#;
+
+/// Markers seen so far, with how far the level had scrolled on the tick they fired
+#[derive(Resource, Default)]
+struct Fired(Vec<(&'static str, f32)>);
+
+fn record_markers(
+    mut fired: ResMut<Fired>,
+    mut music_events: EventReader<PlayMusicEvent>,
+    mut wave_start_events: EventReader<WaveStartEvent>,
+    mut warning_events: EventReader<WarningEvent>,
+    mut swap_events: EventReader<SwapBackgroundEvent>,
+    mut boss_events: EventReader<BossStartEvent>,
+    progress: Res<LevelProgress>
+) {
+    let distance = progress.distance;
+    fired.0.extend(swap_events.read().map(|_| ("background", distance)));
+    fired.0.extend(music_events.read().map(|_| ("music", distance)));
+    fired.0.extend(wave_start_events.read().map(|_| ("wave", distance)));
+    fired.0.extend(warning_events.read().map(|_| ("warning", distance)));
+    fired.0.extend(boss_events.read().map(|_| ("boss", distance)));
+}
+
+fn level_app() -> App {
+    let mut app = gameplay_app();
+    app.add_plugins((AssetPlugin::default(), BackgroundPlugin::headless(), LevelPlugin))
+        .init_asset::<Image>()
+        .init_asset::<TextureAtlas>()
+        .init_resource::<LoadingAssets>()
+        .init_resource::<Fired>()
+        .insert_resource(Levels(vec![LevelData::parse(LEVEL).unwrap()]))
+        .add_systems(FixedUpdate, record_markers.after(TimelineSet));
+    start_playing(app)
+}
+
+/// Steps until the level scrolled `distance` pixels
+fn scroll_to(app: &mut App, distance: f32) {
+    for _ in 0..10000 {
+        if app.world.resource::<LevelProgress>().distance >= distance { return }
+        app.update();
+    }
+    panic!("the level never scrolled to {}", distance);
+}
+
+#[test]
+fn markers_fire_once_the_background_scrolled_to_them() {
+    let mut app = level_app();
+    let tick = Time::<Fixed>::default().timestep().as_secs_f32();
+    // The boss stops the scroll
+    scroll_to(&mut app, 200.0);
+
+    let fired = &app.world.resource::<Fired>().0;
+    let kinds: Vec<&str> = fired.iter().map(|(kind, _)| *kind).collect();
+    // The level starts by swapping in its own background
+    assert_eq!(kinds, ["background", "music", "wave", "warning", "background", "boss"]);
+
+    // Each on the first tick the scroll reached its distance, at 128 pixels per second before the swap and 64 after
+    let markers = [(0.0, 128.0), (0.0, 128.0), (40.0, 128.0), (90.0, 128.0), (150.0, 128.0), (200.0, 64.0)];
+    for ((kind, distance), (marker, speed)) in fired.iter().zip(markers) {
+        assert!(*distance >= marker && *distance <= marker + speed * tick, "{} fired at {}", kind, distance);
+    }
+
+    assert_eq!(app.world.resource::<Wave>().0, 1);
+    assert_eq!(app.world.resource::<ScrollSpeed>().target, 0.0);
+}
+
+#[test]
+fn markers_follow_the_distance_whatever_the_speed() {
+    let mut app = level_app();
+    let tick = Time::<Fixed>::default().timestep().as_secs_f32();
+    app.world.resource_mut::<ScrollSpeed>().set(32.0);
+    scroll_to(&mut app, 100.0);
+
+    let fired = &app.world.resource::<Fired>().0;
+    let (_, warning) = fired.iter().find(|(kind, _)| *kind == "warning").unwrap();
+    assert!(*warning >= 90.0 && *warning <= 90.0 + 32.0 * tick, "warning fired at {}", warning);
+    assert!(fired.iter().all(|(kind, _)| *kind != "boss"));
+}
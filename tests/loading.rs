@@ -0,0 +1,55 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use space_shooter::components::*;
+use space_shooter::loading::*;
+use space_shooter::resources::*;
+use space_shooter::states::*;
+
+fn loading_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, AssetPlugin::default(), LoadingPlugin))
+        .add_state::<GameState>()
+        .init_asset::<Image>();
+    app
+}
+
+/// Steps until the loading screen reports an error, assets load on other threads
+fn wait_for_errors(app: &mut App) {
+    for _ in 0..500 {
+        app.update();
+        if !app.world.resource::<LoadingAssets>().errors.is_empty() { return }
+        std::thread::sleep(Duration::from_millis(2));
+    }
+    panic!("no loading error was reported");
+}
+
+#[test]
+fn nothing_left_to_load_goes_to_the_menu() {
+    let mut app = loading_app();
+    app.update();
+    app.update();
+
+    assert!(app.world.resource::<LoadingAssets>().errors.is_empty(), "{:?}", app.world.resource::<LoadingAssets>().errors);
+    assert_eq!(*app.world.resource::<State<GameState>>().get(), GameState::Menu);
+}
+
+#[test]
+fn failed_assets_are_reported_and_keep_the_game_loading() {
+    let mut app = loading_app();
+    let handle: Handle<Image> = app.world.resource::<AssetServer>().load("missing/nothing.png");
+    app.world.resource_mut::<LoadingAssets>().add(&handle);
+    wait_for_errors(&mut app);
+
+    for _ in 0..5 {
+        app.update();
+    }
+
+    // Reported once, the manifest of the shipped assets checking out
+    let message = "Failed to load asset 'missing/nothing.png'";
+    assert_eq!(app.world.resource::<LoadingAssets>().errors, [message]);
+    let text = app.world.query_filtered::<&Text, With<LoadingErrorText>>().single(&app.world);
+    assert_eq!(text.sections[0].value, message);
+    assert_eq!(*app.world.resource::<State<GameState>>().get(), GameState::Loading);
+}
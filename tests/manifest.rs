@@ -0,0 +1,45 @@
+use std::fs;
+use std::path::Path;
+
+use space_shooter::manifest::*;
+
+#[test]
+fn shipped_assets_match_the_manifest() {
+    assert_eq!(validate_manifest(Path::new("assets")), []);
+}
+
+#[test]
+fn backslashes_and_absolute_paths_are_rejected() {
+    let root = Path::new("assets");
+
+    // Rejected before looking at the files, even where Windows would find them
+    let error = validate_entry(root, AssetName::Level1, "levels\\level1.ron").unwrap();
+    assert_eq!(error, ManifestError::InvalidPath { name: AssetName::Level1, path: "levels\\level1.ron" });
+    assert_eq!(error.to_string(), "Level1: 'levels\\level1.ron' must be relative and use forward slashes");
+
+    let error = validate_entry(root, AssetName::Level1, "/levels/level1.ron").unwrap();
+    assert_eq!(error, ManifestError::InvalidPath { name: AssetName::Level1, path: "/levels/level1.ron" });
+}
+
+#[test]
+fn letter_case_mismatches_are_told_apart_from_missing_files() {
+    let root = std::env::temp_dir().join("space_shooter_manifest_test");
+    fs::create_dir_all(root.join("Textures")).unwrap();
+    fs::write(root.join("Textures/Ships.PNG"), b"").unwrap();
+
+    let error = validate_entry(&root, AssetName::ShipSprites, "textures/ships.png").unwrap();
+    assert_eq!(error, ManifestError::Misnamed {
+        name: AssetName::ShipSprites,
+        path: "textures/ships.png",
+        found: "Textures/Ships.PNG".to_string()
+    });
+    assert_eq!(
+        error.to_string(),
+        "ShipSprites: 'textures/ships.png' does not exist, but 'Textures/Ships.PNG' does (check the letter case)"
+    );
+
+    let error = validate_entry(&root, AssetName::UiSprites, "textures/ui.png").unwrap();
+    assert_eq!(error, ManifestError::Missing { name: AssetName::UiSprites, path: "textures/ui.png" });
+
+    fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,136 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+
+use space_shooter::endless::*;
+use space_shooter::level::*;
+use space_shooter::netcode::*;
+use space_shooter::resources::*;
+
+mod common;
+use common::*;
+
+fn net_app(local_player: usize, transport: LoopbackTransport, seed: u64) -> App {
+    let start = StartOptions { seed: Some(seed), mode: GameMode::Endless, co_op: true, ..default() };
+
+    let mut app = gameplay_app();
+    app.add_plugins((EndlessPlugin, NetPlugin))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(50)))
+        .insert_resource(start)
+        .insert_resource(NetSession::new(local_player, transport))
+        .init_resource::<Levels>();
+    start_playing(app)
+}
+
+fn net_pair(conditions: LinkConditions, seeds: (u64, u64)) -> (App, App) {
+    let (host, join) = LoopbackTransport::pair(conditions, 3);
+    (net_app(0, host, seeds.0), net_app(1, join, seeds.1))
+}
+
+/// Holds a different set of keys every few updates, so the remote input keeps being guessed wrong
+fn press_keys(app: &mut App, step: usize, offset: usize) {
+    const KEYS: [KeyCode; 4] = [KeyCode::Left, KeyCode::Right, KeyCode::Up, KeyCode::Z];
+
+    let mut keyboard_input = app.world.resource_mut::<Input<KeyCode>>();
+    keyboard_input.release_all();
+    keyboard_input.press(KEYS[(step / 7 + offset) % KEYS.len()]);
+    if (step + offset).is_multiple_of(5) {
+        keyboard_input.press(KeyCode::X);
+    }
+}
+
+fn play(host: &mut App, join: &mut App, steps: usize) {
+    for step in 0..steps {
+        press_keys(host, step, 0);
+        press_keys(join, step, 2);
+        host.update();
+        join.update();
+    }
+}
+
+#[test]
+fn messages_survive_encoding() {
+    let messages = [
+        NetMessage::Hello,
+        NetMessage::Start(StartOptions {
+            seed: Some(u64::MAX - 3),
+            level: 1,
+            wave: 2,
+            difficulty: Difficulty::Insane,
+            rank: true,
+            mode: GameMode::Endless,
+            co_op: true,
+            continues: Continues::Shared
+        }),
+        NetMessage::Inputs {
+            first_tick: 40,
+            inputs: vec![
+                PlayerInput { left: true, fire: true, ..default() },
+                PlayerInput::default(),
+                PlayerInput { up: true, down: true, bomb: true, ..default() }
+            ],
+            received: 38,
+            checksum: Some((32, 0xdead_beef_0123_4567))
+        },
+        NetMessage::Inputs { first_tick: 0, inputs: Vec::new(), received: 0, checksum: None }
+    ];
+
+    for message in messages {
+        assert_eq!(NetMessage::decode(&message.encode()), Some(message));
+    }
+
+    let mut truncated = NetMessage::Hello.encode();
+    truncated.pop();
+    assert_eq!(NetMessage::decode(&truncated), None);
+    assert_eq!(NetMessage::decode(&[0, 0]), None);
+}
+
+#[test]
+fn loopback_delays_and_drops_packets() {
+    let conditions = LinkConditions { latency: 2, jitter: 0, loss: 0.0 };
+    let (mut first, mut second) = LoopbackTransport::pair(conditions, 1);
+
+    first.send(&[1]);
+    assert!(second.receive().is_empty());
+    assert!(second.receive().is_empty());
+    assert_eq!(second.receive(), vec![vec![1]]);
+    assert!(first.receive().is_empty());
+
+    let conditions = LinkConditions { latency: 0, jitter: 0, loss: 0.5 };
+    let (mut first, mut second) = LoopbackTransport::pair(conditions, 1);
+    for packet in 0..200u8 {
+        first.send(&[packet]);
+    }
+
+    let arrived = second.receive().len();
+    assert!(arrived > 50 && arrived < 150, "{} of 200 packets arrived", arrived);
+}
+
+#[test]
+fn peers_stay_in_sync_over_a_bad_link() {
+    let conditions = LinkConditions { latency: 2, jitter: 2, loss: 0.2 };
+    let (mut host, mut join) = net_pair(conditions, (7, 7));
+
+    play(&mut host, &mut join, 300);
+
+    for app in [&host, &join] {
+        let report = app.world.resource::<NetReport>();
+        assert_eq!(report.desyncs, 0, "{:?}", report);
+        assert!(report.checked_ticks > 0, "{:?}", report);
+        assert!(report.rollbacks > 0, "{:?}", report);
+        assert!(report.resimulated_ticks >= report.rollbacks);
+    }
+}
+
+#[test]
+fn diverging_peers_are_reported() {
+    let conditions = LinkConditions { latency: 1, jitter: 0, loss: 0.0 };
+    // Peers that did not agree on the seed play different games from the first tick
+    let (mut host, mut join) = net_pair(conditions, (7, 8));
+
+    play(&mut host, &mut join, 60);
+
+    assert!(host.world.resource::<NetReport>().desyncs > 0);
+    assert!(join.world.resource::<NetReport>().desyncs > 0);
+}
@@ -0,0 +1,102 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+
+use space_shooter::components::*;
+use space_shooter::events::*;
+use space_shooter::particles::*;
+
+fn headless_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, ParticlePlugin))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(16)));
+    app.update();
+    app
+}
+
+fn burst(app: &mut App, effect: ParticleEffect) {
+    app.world.send_event(ParticleBurstEvent { effect, position: Vec3::ZERO });
+    app.update();
+}
+
+#[test]
+fn burst_spawns_configured_particle_count() {
+    let mut app = headless_app();
+
+    burst(&mut app, ParticleEffect::Explosion);
+
+    let particles = app.world.query::<&Particle>().iter(&app.world).count();
+    let pool = app.world.resource::<ParticlePool>();
+    assert_eq!(pool.active(), ParticleEffect::Explosion.settings().burst);
+    assert_eq!(particles, pool.spawned());
+}
+
+#[test]
+fn expired_particles_are_reused() {
+    let mut app = headless_app();
+
+    burst(&mut app, ParticleEffect::ImpactSpark);
+    for _ in 0..30 {
+        app.update();
+    }
+    assert_eq!(app.world.resource::<ParticlePool>().active(), 0);
+
+    burst(&mut app, ParticleEffect::ImpactSpark);
+
+    let pool = app.world.resource::<ParticlePool>();
+    assert_eq!(pool.active(), ParticleEffect::ImpactSpark.settings().burst);
+    assert_eq!(pool.spawned(), ParticleEffect::ImpactSpark.settings().burst);
+}
+
+#[test]
+fn pool_never_exceeds_capacity() {
+    let mut app = headless_app();
+    app.world.resource_mut::<ParticlePool>().capacity = 30;
+
+    for _ in 0..5 {
+        burst(&mut app, ParticleEffect::Explosion);
+    }
+
+    assert_eq!(app.world.resource::<ParticlePool>().active(), 30);
+}
+
+#[test]
+fn particles_despawned_outside_the_pool_are_forgotten() {
+    let mut app = headless_app();
+
+    burst(&mut app, ParticleEffect::Explosion);
+    let particles: Vec<Entity> = app.world.query_filtered::<Entity, With<Particle>>().iter(&app.world).collect();
+    for particle in particles {
+        app.world.despawn(particle);
+    }
+    app.update();
+
+    let pool = app.world.resource::<ParticlePool>();
+    assert_eq!(pool.active(), 0);
+    assert_eq!(pool.spawned(), 0);
+}
+
+#[test]
+fn emitter_follows_spawn_rate() {
+    let mut app = headless_app();
+    app.world.spawn((TransformBundle::default(), ParticleEmitter::new(ParticleEffect::ThrustTrail)));
+
+    // 40 particles per second over a quarter second of 16ms frames
+    for _ in 0..15 {
+        app.update();
+    }
+
+    let active = app.world.resource::<ParticlePool>().active();
+    assert!((8..=10).contains(&active), "unexpected particle count {}", active);
+}
+
+#[test]
+fn foe_kill_triggers_explosion() {
+    let mut app = headless_app();
+
+    app.world.send_event(FoeKilledEvent { foe: Entity::PLACEHOLDER, kind: FoeKind::Fighter, position: Vec3::ZERO });
+    app.update();
+
+    assert_eq!(app.world.resource::<ParticlePool>().active(), ParticleEffect::Explosion.settings().burst);
+}
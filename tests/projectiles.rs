@@ -0,0 +1,286 @@
+use bevy::ecs::system::RunSystemOnce;
+use bevy::prelude::*;
+
+use space_shooter::components::*;
+use space_shooter::config::*;
+use space_shooter::constants::*;
+use space_shooter::difficulty::*;
+use space_shooter::events::*;
+use space_shooter::foe::*;
+use space_shooter::player::*;
+use space_shooter::projectiles::*;
+use space_shooter::resources::*;
+
+mod common;
+use common::*;
+
+fn pool_app() -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .insert_resource(GameConfig::default())
+        .insert_resource(SpriteCatalog::default())
+        .init_resource::<ProjectilePool>();
+    app
+}
+
+fn fire(app: &mut App, count: usize) -> Vec<Entity> {
+    app.world.run_system_once(move |
+        mut commands: Commands,
+        mut projectile_pool: ResMut<ProjectilePool>,
+        sprite_catalog: Res<SpriteCatalog>,
+        config: Res<GameConfig>
+    | {
+        (0..count)
+            .filter_map(|_| spawn_bullet(&mut commands, &mut projectile_pool, &sprite_catalog, &config, 0, Vec2::ZERO))
+            .collect()
+    })
+}
+
+fn release(app: &mut App, entities: Vec<Entity>) {
+    app.world.run_system_once(move |mut commands: Commands, mut projectile_pool: ResMut<ProjectilePool>| {
+        for entity in &entities {
+            projectile_pool.release(&mut commands, *entity);
+        }
+    });
+}
+
+fn bullets(app: &mut App) -> usize {
+    app.world.query_filtered::<(), With<Bullet>>().iter(&app.world).count()
+}
+
+#[test]
+fn released_projectiles_are_reused() {
+    let mut app = pool_app();
+
+    let fired = fire(&mut app, 4);
+    release(&mut app, fired[..2].to_vec());
+    assert_eq!(bullets(&mut app), 2);
+    assert_eq!(*app.world.get::<Visibility>(fired[0]).unwrap(), Visibility::Hidden);
+
+    let refired = fire(&mut app, 3);
+
+    let pool = app.world.resource::<ProjectilePool>();
+    assert_eq!(pool.active(ProjectileKind::Bullet), 5);
+    assert_eq!(pool.active(ProjectileKind::FoeProjectile), 0);
+    assert_eq!(pool.spawned(), 5);
+    assert!(refired.contains(&fired[0]) && refired.contains(&fired[1]));
+    assert_eq!(bullets(&mut app), 5);
+}
+
+#[test]
+fn releasing_twice_frees_a_projectile_once() {
+    let mut app = pool_app();
+
+    let fired = fire(&mut app, 1);
+    release(&mut app, vec![fired[0], fired[0]]);
+    release(&mut app, fired.clone());
+
+    let refired = fire(&mut app, 2);
+    assert_ne!(refired[0], refired[1]);
+    assert_eq!(app.world.resource::<ProjectilePool>().spawned(), 2);
+}
+
+#[test]
+fn pool_never_exceeds_its_cap() {
+    let mut app = pool_app();
+    app.world.resource_mut::<GameConfig>().projectiles.bullets = 3;
+
+    assert_eq!(fire(&mut app, 5).len(), 3);
+    assert_eq!(app.world.resource::<ProjectilePool>().active(ProjectileKind::Bullet), 3);
+    assert_eq!(bullets(&mut app), 3);
+}
+
+#[test]
+fn projectiles_despawned_outside_the_pool_are_forgotten() {
+    let mut app = game_app(ProjectileBehaviours::default());
+
+    let in_flight = fire(&mut app, 2);
+    let released = fire(&mut app, 1);
+    release(&mut app, released.clone());
+    let spawned = app.world.resource::<ProjectilePool>().spawned();
+
+    app.world.despawn(in_flight[0]);
+    app.world.despawn(released[0]);
+    app.update();
+
+    let pool = app.world.resource::<ProjectilePool>();
+    assert_eq!(pool.active(ProjectileKind::Bullet), 1);
+    assert_eq!(pool.spawned(), spawned - 2);
+}
+
+fn game_app(behaviours: ProjectileBehaviours) -> App {
+    let mut app = gameplay_app();
+    app.world.resource_mut::<GameConfig>().projectiles.bullet_behaviours = behaviours;
+    start_playing(app)
+}
+
+/// Foes all on top of each other at `position`
+fn place_foes(app: &mut App, count: usize, health: i32, position: Vec2) -> Vec<Entity> {
+    app.world.run_system_once(move |mut commands: Commands, sprite_catalog: Res<SpriteCatalog>, config: Res<GameConfig>| {
+        (0..count)
+            .map(|_| {
+                let foe = spawn_foe(&mut commands, &sprite_catalog, &config, &DifficultyScaling::default(), FoeKind::Fighter, position);
+                commands.entity(foe).insert(Health(health));
+                foe
+            })
+            .collect()
+    })
+}
+
+fn fire_at(app: &mut App, position: Vec2, direction: Vec2) -> Entity {
+    app.world.run_system_once(move |
+        mut commands: Commands,
+        mut projectile_pool: ResMut<ProjectilePool>,
+        sprite_catalog: Res<SpriteCatalog>,
+        config: Res<GameConfig>
+    | {
+        let bullet = spawn_bullet(&mut commands, &mut projectile_pool, &sprite_catalog, &config, 0, position).unwrap();
+        commands.entity(bullet).insert(GDirection::new(direction.x, direction.y));
+        bullet
+    })
+}
+
+fn direction(app: &App, entity: Entity) -> Vec2 {
+    let direction = app.world.get::<GDirection>(entity).unwrap();
+    Vec2::new(direction.x, direction.y)
+}
+
+#[test]
+fn firing_all_run_long_keeps_reusing_bullets() {
+    let mut app = game_app(ProjectileBehaviours::default());
+
+    // Ten seconds of fire is over 160 bullets, while only a second worth of them is ever on screen
+    app.world.resource_mut::<Input<KeyCode>>().press(KeyCode::Z);
+    for _ in 0..640 {
+        app.update();
+    }
+
+    let pool = app.world.resource::<ProjectilePool>();
+    assert!(pool.active(ProjectileKind::Bullet) > 0);
+    assert!(pool.spawned() < 40, "{} bullets spawned", pool.spawned());
+}
+
+#[test]
+fn piercing_bullets_go_through_that_many_foes() {
+    let mut app = game_app(ProjectileBehaviours { pierce: Some(2), ..default() });
+    place_foes(&mut app, 4, 1, Vec2::new(0.0, 300.0));
+    fire_at(&mut app, Vec2::new(0.0, 300.0), Vec2::Y);
+
+    app.update();
+
+    assert_eq!(app.world.resource::<Events<FoeKilledEvent>>().len(), 3);
+    assert_eq!(app.world.query_filtered::<(), With<Foe>>().iter(&app.world).count(), 1);
+    assert_eq!(bullets(&mut app), 0);
+}
+
+#[test]
+fn foes_destroyed_by_a_bullet_do_not_ram_the_ship_in_the_same_tick() {
+    let mut app = game_app(ProjectileBehaviours::default());
+    let ship = app.world.query_filtered::<&Transform, With<SpaceShip>>().single(&app.world).translation.truncate();
+    let lives = app.world.resource::<Players>().0[0].lives;
+
+    // The foe runs into the ship as the bullet hits it
+    place_foes(&mut app, 1, 1, ship);
+    fire_at(&mut app, ship, Vec2::Y);
+
+    app.update();
+
+    assert_eq!(app.world.resource::<Events<FoeKilledEvent>>().len(), 1);
+    assert_eq!(app.world.query_filtered::<(), With<SpaceShip>>().iter(&app.world).count(), 1);
+    assert_eq!(app.world.resource::<Players>().0[0].lives, lives);
+    assert_eq!(app.world.resource::<Events<PlayerHitEvent>>().len(), 0);
+}
+
+#[test]
+fn bouncing_bullets_come_back_off_the_edge() {
+    let mut app = game_app(ProjectileBehaviours { bounce: Some(1), ..default() });
+    let bullet = fire_at(&mut app, Vec2::new(WINDOW_WIDTH * 0.5 - 1.0, 0.0), Vec2::X);
+
+    app.update();
+
+    let transform = app.world.get::<Transform>(bullet).unwrap();
+    assert!(transform.translation.x < WINDOW_WIDTH * 0.5);
+    assert!(direction(&app, bullet).x < 0.0);
+    assert_eq!(app.world.get::<Bounce>(bullet).unwrap().0, 0);
+}
+
+#[test]
+fn homing_bullets_turn_towards_the_nearest_foe_at_their_turn_rate() {
+    let mut app = game_app(ProjectileBehaviours { homing: Some(2.0), ..default() });
+    place_foes(&mut app, 1, 100, Vec2::new(200.0, 0.0));
+    place_foes(&mut app, 1, 100, Vec2::new(-350.0, 0.0));
+    let bullet = fire_at(&mut app, Vec2::ZERO, Vec2::Y);
+
+    app.update();
+
+    let turned = Vec2::Y.angle_between(direction(&app, bullet));
+    let max_turn = 2.0 * Time::<Fixed>::default().timestep().as_secs_f32();
+    assert!((turned + max_turn).abs() < 1e-4, "turned {turned} radians");
+}
+
+#[test]
+fn homing_bullets_leave_destroyed_foes_alone() {
+    let mut app = game_app(ProjectileBehaviours { homing: Some(2.0), ..default() });
+    let destroyed = place_foes(&mut app, 1, 100, Vec2::new(200.0, 0.0))[0];
+    place_foes(&mut app, 1, 100, Vec2::new(-350.0, 0.0));
+    app.world.entity_mut(destroyed).insert(Dead);
+    let bullet = fire_at(&mut app, Vec2::ZERO, Vec2::Y);
+
+    app.update();
+
+    // Turns left towards the foe still flying
+    assert!(Vec2::Y.angle_between(direction(&app, bullet)) > 0.0);
+}
+
+#[test]
+fn bullets_expire_at_the_end_of_their_lifetime() {
+    let mut app = game_app(ProjectileBehaviours { lifetime: Some(0.1), ..default() });
+    fire_at(&mut app, Vec2::ZERO, Vec2::Y);
+
+    for _ in 0..6 {
+        app.update();
+    }
+    assert_eq!(bullets(&mut app), 1);
+
+    app.update();
+    assert_eq!(bullets(&mut app), 0);
+    assert_eq!(app.world.resource::<ProjectilePool>().active(ProjectileKind::Bullet), 0);
+}
+
+#[test]
+fn splitting_bullets_fan_out_without_hitting_the_same_foe_again() {
+    let split = Split { count: 3, spread: 1.0 };
+    let mut app = game_app(ProjectileBehaviours { split: Some(split), ..default() });
+    let foe = place_foes(&mut app, 1, 100, Vec2::new(0.0, 300.0))[0];
+    fire_at(&mut app, Vec2::new(0.0, 300.0), Vec2::Y);
+
+    app.update();
+
+    let mut fragments: Vec<Vec2> = app.world.query_filtered::<&GDirection, With<Bullet>>()
+        .iter(&app.world)
+        .map(|direction| Vec2::new(direction.x, direction.y))
+        .collect();
+    fragments.sort_by(|a, b| a.x.total_cmp(&b.x));
+
+    assert_eq!(fragments.len(), 3);
+    assert!((fragments[0].angle_between(fragments[2]) + split.spread).abs() < 1e-4);
+    assert!(fragments[1].abs_diff_eq(Vec2::Y, 1e-4));
+    assert!(app.world.query::<&Split>().iter(&app.world).next().is_none());
+
+    let damage = app.world.resource::<GameConfig>().ship.damage;
+    app.update();
+    assert_eq!(app.world.get::<Health>(foe).unwrap().0, 100 - damage);
+}
+
+#[test]
+fn piercing_bullets_split_only_once_they_are_spent() {
+    let split = Split { count: 3, spread: 1.0 };
+    let mut app = game_app(ProjectileBehaviours { pierce: Some(2), split: Some(split), ..default() });
+    place_foes(&mut app, 3, 100, Vec2::new(0.0, 300.0));
+    fire_at(&mut app, Vec2::new(0.0, 300.0), Vec2::Y);
+
+    app.update();
+
+    assert_eq!(app.world.resource::<Events<ProjectileHitEvent>>().len(), 3);
+    assert_eq!(bullets(&mut app), 3);
+}
@@ -0,0 +1,131 @@
+use bevy::prelude::*;
+
+use space_shooter::config::*;
+use space_shooter::endless::*;
+use space_shooter::level::*;
+use space_shooter::projectiles::*;
+use space_shooter::resources::*;
+use space_shooter::snapshot::*;
+use space_shooter::states::*;
+
+mod common;
+use common::*;
+
+fn snapshot_app(seed: u64) -> App {
+    let start = StartOptions { seed: Some(seed), mode: GameMode::Endless, co_op: true, rank: true, ..default() };
+
+    let mut app = gameplay_app();
+    app.add_plugins(EndlessPlugin)
+        .insert_resource(start)
+        .init_resource::<Levels>();
+    start_playing(app)
+}
+
+/// Both ships weave around and fire, each step holding keys picked from its number alone
+fn play(app: &mut App, steps: std::ops::Range<usize>) {
+    const FIRST: [KeyCode; 3] = [KeyCode::Left, KeyCode::Right, KeyCode::Up];
+    const SECOND: [KeyCode; 3] = [KeyCode::D, KeyCode::S, KeyCode::A];
+
+    for step in steps {
+        let mut keyboard_input = app.world.resource_mut::<Input<KeyCode>>();
+        keyboard_input.release_all();
+        keyboard_input.press(FIRST[step / 11 % FIRST.len()]);
+        keyboard_input.press(SECOND[step / 17 % SECOND.len()]);
+        keyboard_input.press(KeyCode::Z);
+        keyboard_input.press(KeyCode::F);
+        if step % 97 == 50 {
+            keyboard_input.press(KeyCode::X);
+        }
+
+        app.update();
+    }
+}
+
+/// Everything the snapshot holds, written out so any bit of difference shows
+fn state(app: &mut App) -> String {
+    ron::to_string(&GameSnapshot::capture(&mut app.world)).unwrap()
+}
+
+#[test]
+fn snapshots_survive_serialization() {
+    let mut app = snapshot_app(11);
+    play(&mut app, 0..300);
+
+    let snapshot = GameSnapshot::capture(&mut app.world);
+    assert!(!snapshot.foes.is_empty());
+    assert!(!snapshot.bullets.is_empty());
+
+    let text = ron::to_string(&snapshot).unwrap();
+    let read: GameSnapshot = ron::from_str(&text).unwrap();
+    assert_eq!(ron::to_string(&read).unwrap(), text);
+    assert_eq!(read.checksum(), snapshot.checksum());
+}
+
+#[test]
+fn restored_runs_play_on_bit_identical() {
+    let mut app = snapshot_app(11);
+    play(&mut app, 0..300);
+
+    let saved = ron::to_string(&GameSnapshot::capture(&mut app.world)).unwrap();
+    play(&mut app, 300..600);
+    let expected = state(&mut app);
+
+    // Rewinds the same run
+    let snapshot: GameSnapshot = ron::from_str(&saved).unwrap();
+    snapshot.restore(&mut app.world);
+    assert_eq!(state(&mut app), saved);
+    play(&mut app, 300..600);
+    assert_eq!(state(&mut app), expected);
+
+    // Picks it up in a run that started differently
+    let mut other = snapshot_app(12);
+    play(&mut other, 0..40);
+    snapshot.restore(&mut other.world);
+    play(&mut other, 300..600);
+    assert_eq!(state(&mut other), expected);
+}
+
+#[test]
+fn restoring_undoes_a_pending_game_over() {
+    let mut app = snapshot_app(11);
+    let snapshot = GameSnapshot::capture(&mut app.world);
+
+    app.world.resource_mut::<NextState<GameState>>().set(GameState::GameOver);
+    snapshot.restore(&mut app.world);
+    app.update();
+
+    assert_eq!(*app.world.resource::<State<GameState>>().get(), GameState::Playing);
+}
+
+#[test]
+fn projectile_behaviours_are_restored_mid_flight() {
+    let behaviours = ProjectileBehaviours {
+        pierce: Some(1),
+        bounce: Some(2),
+        homing: Some(3.0),
+        lifetime: Some(1.5),
+        split: Some(Split { count: 2, spread: 0.6 })
+    };
+    let mut app = snapshot_app(11);
+    let mut config = app.world.resource_mut::<GameConfig>();
+    config.projectiles.bullet_behaviours = behaviours.clone();
+    config.projectiles.foe_projectile_behaviours = behaviours;
+
+    play(&mut app, 0..300);
+    let snapshot = GameSnapshot::capture(&mut app.world);
+    assert!(snapshot.bullets.iter().any(|bullet| bullet.behaviours.lifetime.is_some()));
+    let saved = ron::to_string(&snapshot).unwrap();
+    play(&mut app, 300..500);
+    let expected = state(&mut app);
+
+    snapshot.restore(&mut app.world);
+    assert_eq!(state(&mut app), saved);
+    play(&mut app, 300..500);
+    assert_eq!(state(&mut app), expected);
+}
+
+#[test]
+fn checksums_are_the_same_on_every_build() {
+    // FNV-1a of the empty entity sum, eight zero bytes
+    assert_eq!(GameSnapshot::default().checksum(), 0xa8c7_f832_281a_39c5);
+}
@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+
+use space_shooter::components::*;
+use space_shooter::events::*;
+use space_shooter::settings::*;
+use space_shooter::sound::*;
+
+fn headless_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, SoundPlugin::null()))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(16)))
+        .init_resource::<Settings>();
+    app.update();
+    app
+}
+
+#[test]
+fn gameplay_events_play_their_cues() {
+    let mut app = headless_app();
+
+    app.world.send_event(FoeKilledEvent { foe: Entity::PLACEHOLDER, kind: FoeKind::Fighter, position: Vec3::ZERO });
+    app.world.send_event(WaveStartEvent { wave: 1 });
+    app.update();
+
+    let played = app.world.resource::<PlayedCues>();
+    assert_eq!(played.count(AudioCue::Effect(SoundEffect::FoeKilled)), 1);
+    assert_eq!(played.count(AudioCue::Effect(SoundEffect::WaveStart)), 1);
+}
+
+#[test]
+fn rapid_fire_is_voice_limited() {
+    let mut app = headless_app();
+
+    for _ in 0..10 {
+        app.world.send_event(PlayerShotEvent { position: Vec3::ZERO, bullets: 2 });
+    }
+    app.update();
+
+    let max_voices = SoundEffect::PlayerShot.max_voices();
+    assert_eq!(app.world.resource::<PlayedCues>().count(AudioCue::Effect(SoundEffect::PlayerShot)), max_voices);
+
+    // Voices free up once the sound finished playing
+    for _ in 0..10 {
+        app.update();
+    }
+    app.world.send_event(PlayerShotEvent { position: Vec3::ZERO, bullets: 2 });
+    app.update();
+
+    assert_eq!(app.world.resource::<PlayedCues>().count(AudioCue::Effect(SoundEffect::PlayerShot)), max_voices + 1);
+}
+
+#[test]
+fn volumes_follow_settings() {
+    let mut app = headless_app();
+    app.insert_resource(Settings { master_volume: 0.5, music_volume: 0.4, sfx_volume: 1.0 });
+
+    app.world.send_event(PlayMusicEvent(Some(MusicTrack::Stage2)));
+    app.world.send_event(BombEvent { position: Vec3::ZERO });
+    app.update();
+
+    let played = &app.world.resource::<PlayedCues>().0;
+    assert!(played.contains(&(AudioCue::Music(MusicTrack::Stage2), 0.2)));
+    assert!(played.contains(&(AudioCue::Effect(SoundEffect::Bomb), 0.5)));
+}
+
+#[test]
+fn settings_round_trip_through_file() {
+    let path = std::env::temp_dir().join("space_shooter_settings_test.ron");
+    let settings = Settings { master_volume: 0.3, music_volume: 0.2, sfx_volume: 0.9 };
+
+    settings.save(&path).unwrap();
+    assert_eq!(Settings::load(&path), settings);
+
+    std::fs::write(&path, "(master_volume: 4.0)").unwrap();
+    assert_eq!(Settings::load(&path).master_volume, 1.0);
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,109 @@
+use bevy::ecs::system::RunSystemOnce;
+use bevy::prelude::*;
+
+use space_shooter::components::*;
+use space_shooter::config::*;
+use space_shooter::constants::*;
+use space_shooter::difficulty::*;
+use space_shooter::events::*;
+use space_shooter::foe::*;
+use space_shooter::player::*;
+use space_shooter::projectiles::*;
+use space_shooter::resources::*;
+use space_shooter::stats::*;
+
+mod common;
+use common::*;
+
+const STACK: Vec2 = Vec2::new(0.0, 300.0);
+
+fn stats_app() -> App {
+    let mut app = gameplay_app();
+    app.add_plugins(StatsPlugin);
+    start_playing(app)
+}
+
+/// Foes all on top of each other, each one going down to a single bullet
+fn stack_foes(app: &mut App, count: usize) {
+    app.world.run_system_once(move |mut commands: Commands, sprite_catalog: Res<SpriteCatalog>, config: Res<GameConfig>| {
+        for _ in 0..count {
+            let foe = spawn_foe(&mut commands, &sprite_catalog, &config, &DifficultyScaling::default(), FoeKind::Fighter, STACK);
+            commands.entity(foe).insert(Health(1));
+        }
+    });
+}
+
+fn fire_into_stack(app: &mut App, count: usize) {
+    app.world.run_system_once(move |
+        mut commands: Commands,
+        mut projectile_pool: ResMut<ProjectilePool>,
+        sprite_catalog: Res<SpriteCatalog>,
+        config: Res<GameConfig>
+    | {
+        for _ in 0..count {
+            spawn_bullet(&mut commands, &mut projectile_pool, &sprite_catalog, &config, 0, STACK);
+        }
+    });
+}
+
+fn stats(app: &App) -> RunStats {
+    app.world.resource::<RunStats>().clone()
+}
+
+fn tick() -> f32 {
+    Time::<Fixed>::default().timestep().as_secs_f32()
+}
+
+#[test]
+fn kills_and_hits_of_a_tick_are_counted() {
+    let mut app = stats_app();
+    stack_foes(&mut app, 3);
+    fire_into_stack(&mut app, 2);
+
+    app.update();
+
+    let stats = stats(&app);
+    assert_eq!(stats.kills.get(&FoeKind::Fighter), Some(&2));
+    assert_eq!(stats.total_kills(), 2);
+    assert_eq!(stats.hits, 2);
+    assert_eq!((stats.combo, stats.max_combo), (2, 2));
+    assert_eq!(stats.score, app.world.resource::<Score>().0);
+    assert!(stats.score > 0);
+}
+
+#[test]
+fn time_shots_and_damage_add_up_tick_by_tick() {
+    let mut app = stats_app();
+    let before = stats(&app);
+
+    app.world.send_event(PlayerShotEvent { position: Vec3::ZERO, bullets: 3 });
+    for _ in 0..10 {
+        app.update();
+    }
+    let ship = app.world.query_filtered::<Entity, With<SpaceShip>>().single(&app.world);
+    app.world.send_event(PlayerHitEvent { ship, position: Vec3::ZERO, damage: 4, fatal: false });
+    app.update();
+
+    let stats = stats(&app);
+    assert!((stats.time_survived - before.time_survived - 11.0 * tick()).abs() < 1e-4);
+    assert_eq!(stats.shots_fired - before.shots_fired, 3);
+    assert_eq!(stats.damage_taken - before.damage_taken, 4);
+}
+
+#[test]
+fn combos_break_once_the_window_goes_by_without_a_kill() {
+    let mut app = stats_app();
+
+    for _ in 0..3 {
+        app.world.send_event(FoeKilledEvent { foe: Entity::PLACEHOLDER, kind: FoeKind::Fighter, position: Vec3::ZERO });
+    }
+    app.update();
+    assert_eq!(stats(&app).combo, 3);
+
+    for _ in 0..(STATS_COMBO_WINDOW / tick()).ceil() as usize {
+        app.update();
+    }
+
+    let stats = stats(&app);
+    assert_eq!((stats.combo, stats.max_combo), (0, 3));
+}